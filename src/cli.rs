@@ -0,0 +1,987 @@
+use std::{
+    fs,
+    io::Write,
+    path::{Path, PathBuf},
+};
+
+use anyhow::{Context, anyhow};
+use clap::{ArgGroup, Args, Parser, Subcommand};
+use itertools::Itertools;
+
+use crate::controller::is_valid_name;
+use crate::git_utils;
+use crate::report::{self, ReportFormat};
+use crate::repositories::{Repositories, RepositoryId, Review, ReviewId};
+use crate::review_json::{self, ReviewJson};
+use crate::review_snapshot::{self, BEFORE_RESTORE};
+use crate::storage::migration;
+use crate::storage::repository_storage::{DiffMode, INDEX, LineRange, ReviewEvent, ReviewEventStore, ReviewName, SnapshotStore};
+use crate::storage::{RepositoryName, ReviewHelperStorage, StorageBackend, create_storage};
+
+/// Without a subcommand, or with arguments that are no command, the graphical user interface is started.
+#[derive(Parser)]
+#[command(name = "review-helper", version, about = "Local code review tool")]
+pub struct Cli {
+    #[command(subcommand)]
+    pub command: Option<CliCommand>,
+}
+
+#[derive(Subcommand)]
+pub enum CliCommand {
+    /// Inspect the registered repositories
+    #[command(subcommand)]
+    Repo(RepoCommand),
+    /// Inspect and modify reviews
+    #[command(subcommand)]
+    Review(ReviewCommand),
+    /// Modify the notes of a review
+    #[command(subcommand)]
+    Note(NoteCommand),
+    /// Take, restore and compare named snapshots of a review
+    #[command(subcommand)]
+    Snapshot(SnapshotCommand),
+    /// Manage the storage of the review data
+    #[command(subcommand)]
+    Storage(StorageCommand),
+}
+
+#[derive(Subcommand)]
+pub enum RepoCommand {
+    /// List all repositories
+    List,
+}
+
+#[derive(Args)]
+pub struct ReviewArgs {
+    /// Name of the repository
+    pub repository: String,
+    /// Name of the review
+    pub review: String,
+}
+
+#[derive(Subcommand)]
+pub enum ReviewCommand {
+    /// List the reviews of a repository
+    List {
+        /// Name of the repository
+        repository: String,
+    },
+    /// Show the diff range, files and notes of a review
+    Show(ReviewArgs),
+    /// Show the activity log of a review, oldest event first
+    Log(ReviewArgs),
+    /// Mark a changed file as reviewed or not reviewed
+    #[command(group(ArgGroup::new("state").required(true).args(["reviewed", "not_reviewed"])))]
+    Mark {
+        #[command(flatten)]
+        review: ReviewArgs,
+        /// Path of the file relative to the repository
+        file: String,
+        /// Mark the file as reviewed
+        #[arg(long)]
+        reviewed: bool,
+        /// Mark the file as not reviewed
+        #[arg(long)]
+        not_reviewed: bool,
+        /// Mark the file only in this commit of the range, abbreviated as by `git log --oneline`
+        #[arg(long)]
+        commit: Option<String>,
+    },
+    /// Export the review as Markdown or HTML report, as JSON for `review import` or its open notes as SARIF log
+    Export {
+        #[command(flatten)]
+        review: ReviewArgs,
+        /// Write the report to this file instead of stdout
+        #[arg(long, short)]
+        output: Option<PathBuf>,
+        /// Report format, derived from the output file extension by default
+        #[arg(long, value_enum)]
+        format: Option<ReportFormat>,
+    },
+    /// Show the files changed since an earlier iteration of the review and the `git range-diff` of a rebased branch
+    Interdiff {
+        #[command(flatten)]
+        review: ReviewArgs,
+        /// Number of the iteration as listed by `review show`
+        iteration: usize,
+    },
+    /// Import a review exported as JSON
+    Import {
+        /// JSON file written by `review export --format json`
+        file: PathBuf,
+        /// Target repository, by default the one with the same first commit as the exported repository
+        #[arg(long)]
+        repository: Option<String>,
+        /// Name of the imported review, by default the exported name
+        #[arg(long)]
+        name: Option<String>,
+    },
+}
+
+#[derive(Subcommand)]
+pub enum NoteCommand {
+    /// Add a note to a review
+    Add {
+        #[command(flatten)]
+        review: ReviewArgs,
+        /// Text of the note
+        text: String,
+        /// File path or free text the note refers to
+        #[arg(long, default_value = "")]
+        context: String,
+        /// Line range in the file, e.g. 142, 142-145 or old:142-145
+        #[arg(long, requires = "context")]
+        lines: Option<LineRange>,
+    },
+}
+
+#[derive(Args)]
+pub struct SnapshotArgs {
+    #[command(flatten)]
+    pub review: ReviewArgs,
+    /// Name of the snapshot
+    pub name: String,
+}
+
+#[derive(Subcommand)]
+pub enum SnapshotCommand {
+    /// Store the diff range, file diffs and notes of a review as snapshot
+    Create(SnapshotArgs),
+    /// List the snapshots of a review, oldest first
+    List(ReviewArgs),
+    /// Replace the review by a snapshot, the current state is kept as snapshot before
+    Restore(SnapshotArgs),
+    /// Show what changed since a snapshot
+    Compare(SnapshotArgs),
+    /// Delete a snapshot
+    Delete(SnapshotArgs),
+}
+
+#[derive(Subcommand)]
+pub enum StorageCommand {
+    /// Copy all repositories and reviews into another storage backend and verify the copy
+    Migrate {
+        /// Backend to read from
+        #[arg(long, value_enum)]
+        from: StorageBackend,
+        /// Backend to write to
+        #[arg(long, value_enum)]
+        to: StorageBackend,
+        /// Data directory to read from, the application data directory by default
+        #[arg(long)]
+        from_path: Option<PathBuf>,
+        /// Data directory to write to, the application data directory by default
+        #[arg(long)]
+        to_path: Option<PathBuf>,
+    },
+}
+
+/// Runs a command against the storage without starting the user interface.
+pub struct CliApp {
+    storage: Box<dyn ReviewHelperStorage>,
+    repositories: Repositories,
+    data_path: PathBuf,
+}
+
+impl CliApp {
+    pub fn new(storage: Box<dyn ReviewHelperStorage>, data_path: PathBuf) -> anyhow::Result<Self> {
+        let loaded_repositories = storage.load_repositories().context("Could not load repositories")?;
+        for error in &loaded_repositories.errors {
+            eprintln!("Warning: {}", error);
+        }
+        let repositories = Repositories::new(loaded_repositories.repositories);
+        Ok(Self {
+            storage,
+            repositories,
+            data_path,
+        })
+    }
+
+    pub fn run(&mut self, command: CliCommand, out: &mut impl Write) -> anyhow::Result<()> {
+        match command {
+            CliCommand::Repo(RepoCommand::List) => self.list_repositories(out),
+            CliCommand::Review(ReviewCommand::List { repository }) => self.list_reviews(&repository, out),
+            CliCommand::Review(ReviewCommand::Show(review)) => self.show_review(&review, out),
+            CliCommand::Review(ReviewCommand::Log(review)) => self.show_review_log(&review, out),
+            CliCommand::Review(ReviewCommand::Mark {
+                review,
+                file,
+                reviewed,
+                commit,
+                ..
+            }) => self.mark_file(&review, &file, reviewed, commit, out),
+            CliCommand::Review(ReviewCommand::Export { review, output, format }) => self.export_report(&review, output, format, out),
+            CliCommand::Review(ReviewCommand::Interdiff { review, iteration }) => self.show_interdiff(&review, iteration, out),
+            CliCommand::Review(ReviewCommand::Import { file, repository, name }) => self.import_review(&file, repository, name, out),
+            CliCommand::Note(NoteCommand::Add { review, text, context, lines }) => self.add_note(&review, text, context, lines, out),
+            CliCommand::Snapshot(SnapshotCommand::Create(snapshot)) => self.create_snapshot(&snapshot, out),
+            CliCommand::Snapshot(SnapshotCommand::List(review)) => self.list_snapshots(&review, out),
+            CliCommand::Snapshot(SnapshotCommand::Restore(snapshot)) => self.restore_snapshot(&snapshot, out),
+            CliCommand::Snapshot(SnapshotCommand::Compare(snapshot)) => self.compare_snapshot(&snapshot, out),
+            CliCommand::Snapshot(SnapshotCommand::Delete(snapshot)) => self.delete_snapshot(&snapshot, out),
+            CliCommand::Storage(StorageCommand::Migrate { from, to, from_path, to_path }) => self.migrate_storage(from, to, from_path, to_path, out),
+        }
+    }
+
+    fn list_repositories(&self, out: &mut impl Write) -> anyhow::Result<()> {
+        for repository in self
+            .repositories
+            .iter()
+            .map(|(_, repository)| repository)
+            .sorted_by_key(|r| r.name.as_str().to_string())
+        {
+            let store = repository.store();
+            writeln!(out, "{}\t{}\t{}", store.name.as_str(), store.path.display(), store.base_branch)?;
+        }
+        Ok(())
+    }
+
+    fn list_reviews(&mut self, repository: &str, out: &mut impl Write) -> anyhow::Result<()> {
+        let repository_id = self.repository_id(repository)?;
+        for review_name in self.review_names(&repository_id)?.iter().sorted_by_key(|name| name.as_str().to_string()) {
+            writeln!(out, "{}", review_name.as_str())?;
+        }
+        Ok(())
+    }
+
+    fn show_review(&mut self, args: &ReviewArgs, out: &mut impl Write) -> anyhow::Result<()> {
+        let (repository_id, review_id) = self.load_review(args)?;
+        let review = self.review(&repository_id, &review_id);
+
+        let diff_range = review.diff_range();
+        let end = if diff_range.end.is_empty() { "working copy" } else { diff_range.end.as_str() };
+        writeln!(out, "Review:     {}", review.name().as_str())?;
+        writeln!(out, "Diff range: {}..{}", diff_range.start, end)?;
+        if diff_range.mode != DiffMode::Commits {
+            writeln!(out, "Diff mode:  {}", diff_range.mode)?;
+        }
+        for (index, iteration) in diff_range.iterations.iter().enumerate() {
+            writeln!(out, "Iteration {}: {}..{}", index + 1, iteration.start, iteration.end)?;
+        }
+
+        let files = review
+            .file_diffs
+            .stores()
+            .into_iter()
+            .sorted_by_key(|f| f.file_path.clone())
+            .collect::<Vec<_>>();
+        let reviewed_count = files.iter().filter(|f| f.is_reviewed).count();
+        writeln!(out, "\nFiles ({}/{} reviewed):", reviewed_count, files.len())?;
+        for file in files {
+            let changed = if file.is_changed_since_review { " (changed since review)" } else { "" };
+            writeln!(out, "  [{}] {}{}", checkbox(file.is_reviewed), file.file_path.display(), changed)?;
+        }
+
+        writeln!(out, "\nNotes:")?;
+        for (_, note) in review.notes.iter().sorted_by_key(|(id, _)| (*id).clone()) {
+            let mut location = note.context.clone();
+            if let Some(line_range) = note.line_range {
+                location.push_str(&format!(":{}", line_range));
+            }
+            if note.is_outdated {
+                location.push_str(" (outdated)");
+            }
+            if location.is_empty() {
+                writeln!(out, "  [{}] {}", checkbox(note.is_done), note.text)?;
+            } else {
+                writeln!(out, "  [{}] {}: {}", checkbox(note.is_done), location, note.text)?;
+            }
+        }
+        Ok(())
+    }
+
+    fn show_review_log(&mut self, args: &ReviewArgs, out: &mut impl Write) -> anyhow::Result<()> {
+        let (repository_id, review_id) = self.load_review(args)?;
+        let repository = self
+            .repositories
+            .get(&repository_id)
+            .unwrap_or_else(|| panic!("[BUG] Could not find {}", repository_id));
+        let review = self.review(&repository_id, &review_id);
+
+        for event in self.storage.load_review_events(&repository.name, review.name())? {
+            writeln!(out, "{} {}: {}", event.time, event.author, event.event)?;
+        }
+        Ok(())
+    }
+
+    fn show_interdiff(&mut self, args: &ReviewArgs, iteration_number: usize, out: &mut impl Write) -> anyhow::Result<()> {
+        let (repository_id, review_id) = self.load_review(args)?;
+        let repository = self
+            .repositories
+            .get(&repository_id)
+            .unwrap_or_else(|| panic!("[BUG] Could not find {}", repository_id));
+        let review = self.review(&repository_id, &review_id);
+
+        let diff_range = review.diff_range();
+        let iteration = iteration_number
+            .checked_sub(1)
+            .and_then(|index| diff_range.iterations.get(index))
+            .ok_or_else(|| anyhow!("Review '{}' has no iteration {}", args.review, iteration_number))?;
+
+        writeln!(out, "Changes since iteration {} ({}..{}):", iteration_number, iteration.start, iteration.end)?;
+        // The compared side may be the index or working tree, see `DiffRangeStore::revisions`.
+        let (_, end) = diff_range.revisions();
+        let mut file_diff_map = git_utils::diff_git_repo(repository.path(), &iteration.end, end)?;
+        for file in review.file_diffs.file_id_map.keys().sorted() {
+            if let Some(status) = file_diff_map.remove(file) {
+                let change_type = report::change_type_name(&status.change_type);
+                writeln!(out, "  {} {} (+{} -{})", change_type, file, status.added_lines, status.removed_lines)?;
+            }
+        }
+
+        let is_commit = !end.is_empty() && end != INDEX;
+        if is_commit && !git_utils::is_ancestor(repository.path(), &iteration.end, end)? {
+            let range_diff = git_utils::range_diff(repository.path(), &iteration.start, &iteration.end, &diff_range.start, end)?;
+            writeln!(out, "\nThe branch was rebased since iteration {}, changes of its commits:", iteration_number)?;
+            write!(out, "{}", range_diff)?;
+        }
+        Ok(())
+    }
+
+    fn mark_file(&mut self, args: &ReviewArgs, file: &str, is_reviewed: bool, commit: Option<String>, out: &mut impl Write) -> anyhow::Result<()> {
+        let (repository_id, review_id) = self.load_review(args)?;
+        let repository = self
+            .repositories
+            .get_mut(&repository_id)
+            .unwrap_or_else(|| panic!("[BUG] Could not find {}", repository_id));
+        let author = git_utils::user_name(repository.path());
+        let repository_path = repository.path().clone();
+        let review = repository
+            .reviews
+            .get_mut(&review_id)
+            .unwrap_or_else(|| panic!("[BUG] Could not find {} ({})", review_id, repository_id));
+
+        let file_diff_id = review
+            .file_diffs
+            .file_id_map
+            .get(file)
+            .cloned()
+            .ok_or_else(|| anyhow!("File '{}' is not part of review '{}'", file, args.review))?;
+
+        let state = if is_reviewed { "reviewed" } else { "not reviewed" };
+        let event = match commit {
+            Some(commit) => {
+                review.file_diffs.set_is_reviewed_in_commit(&file_diff_id, &commit, is_reviewed);
+                writeln!(out, "Marked {} as {} in commit {}", file, state, commit)?;
+                ReviewEvent::FileReviewedInCommit {
+                    file: file.to_string(),
+                    commit,
+                    is_reviewed,
+                }
+            }
+            None => {
+                let reviewed_blob = if is_reviewed {
+                    git_utils::blob_hash(&repository_path, review.diff_range().revisions().1, file)
+                        .inspect_err(|e| log::warn!("Could not hash {}: {}", file, e))
+                        .ok()
+                        .flatten()
+                } else {
+                    None
+                };
+                review.file_diffs.set_is_reviewed(&file_diff_id, is_reviewed, reviewed_blob);
+                writeln!(out, "Marked {} as {}", file, state)?;
+                ReviewEvent::FileReviewed {
+                    file: file.to_string(),
+                    is_reviewed,
+                }
+            }
+        };
+        self.storage
+            .save_review_file_diffs(&repository.name, review.name(), review.diff_range(), &review.file_diffs.stores())?;
+        self.storage
+            .append_review_events(&repository.name, review.name(), &[ReviewEventStore::new(author, event)])?;
+        Ok(())
+    }
+
+    fn export_report(&mut self, args: &ReviewArgs, output: Option<PathBuf>, format: Option<ReportFormat>, out: &mut impl Write) -> anyhow::Result<()> {
+        let (repository_id, review_id) = self.load_review(args)?;
+        let repository = self
+            .repositories
+            .get(&repository_id)
+            .unwrap_or_else(|| panic!("[BUG] Could not find {}", repository_id));
+        let review = self.review(&repository_id, &review_id);
+
+        let format = format.unwrap_or_else(|| output.as_deref().map_or(ReportFormat::Markdown, ReportFormat::from_path));
+        let content = report::render_report(repository, review, format)?;
+        match output {
+            Some(path) => {
+                fs::write(&path, content).with_context(|| format!("Could not write {}", path.display()))?;
+                writeln!(out, "Exported report to {}", path.display())?;
+            }
+            None => write!(out, "{}", content)?,
+        }
+        Ok(())
+    }
+
+    fn import_review(&mut self, file: &Path, repository: Option<String>, name: Option<String>, out: &mut impl Write) -> anyhow::Result<()> {
+        let content = fs::read_to_string(file).with_context(|| format!("Could not read {}", file.display()))?;
+        let review_json = ReviewJson::from_json(&content)?;
+
+        let repository_id = match repository {
+            Some(repository) => self.repository_id(&repository)?,
+            None => self
+                .repositories
+                .iter()
+                .find(|(_, repository)| repository.store().first_commit == review_json.repository.first_commit)
+                .map(|(id, _)| id.clone())
+                .ok_or_else(|| {
+                    anyhow!(
+                        "No repository matches '{}' (first commit {}), select one with --repository",
+                        review_json.repository.name,
+                        review_json.repository.first_commit
+                    )
+                })?,
+        };
+        let repository = self
+            .repositories
+            .get_mut(&repository_id)
+            .unwrap_or_else(|| panic!("[BUG] Could not find {}", repository_id));
+
+        let (_, review_name) = review_json::import_review(self.storage.as_ref(), repository, &review_json, name.as_deref().map(ReviewName::from))?;
+        writeln!(out, "Imported review {} into {}", review_name.as_str(), repository.name.as_str())?;
+        Ok(())
+    }
+
+    fn add_note(&mut self, args: &ReviewArgs, text: String, context: String, line_range: Option<LineRange>, out: &mut impl Write) -> anyhow::Result<()> {
+        if text.trim().is_empty() {
+            return Err(anyhow!("The text of a note must not be empty"));
+        }
+        let (repository_id, review_id) = self.load_review(args)?;
+        let repository = self
+            .repositories
+            .get_mut(&repository_id)
+            .unwrap_or_else(|| panic!("[BUG] Could not find {}", repository_id));
+        let author = git_utils::user_name(repository.path());
+        let review = repository
+            .reviews
+            .get_mut(&review_id)
+            .unwrap_or_else(|| panic!("[BUG] Could not find {} ({})", review_id, repository_id));
+
+        let event = ReviewEvent::NoteAdded {
+            context: context.clone(),
+            text: text.clone(),
+        };
+        review.notes.add_note(text, context, line_range);
+        self.storage.save_review_notes(&repository.name, review.name(), &review.notes.stores())?;
+        self.storage
+            .append_review_events(&repository.name, review.name(), &[ReviewEventStore::new(author, event)])?;
+
+        writeln!(out, "Added note to {}", args.review)?;
+        Ok(())
+    }
+
+    fn create_snapshot(&mut self, args: &SnapshotArgs, out: &mut impl Write) -> anyhow::Result<()> {
+        if !is_valid_name(&args.name) {
+            return Err(anyhow!("'{}' is not a valid snapshot name, use letters and digits", args.name));
+        }
+        let (repository_id, review_id) = self.load_review(&args.review)?;
+        if self.snapshots(&repository_id, &review_id)?.iter().any(|snapshot| snapshot.name == args.name) {
+            return Err(anyhow!("Snapshot '{}' already exists", args.name));
+        }
+        self.save_snapshot(&repository_id, &review_id, args.name.clone())?;
+
+        writeln!(out, "Took snapshot {} of {}", args.name, args.review.review)?;
+        Ok(())
+    }
+
+    fn list_snapshots(&mut self, args: &ReviewArgs, out: &mut impl Write) -> anyhow::Result<()> {
+        let (repository_id, review_id) = self.load_review(args)?;
+        for snapshot in self.snapshots(&repository_id, &review_id)? {
+            writeln!(
+                out,
+                "{}\t{}\t{} files, {} notes",
+                snapshot.time,
+                snapshot.name,
+                snapshot.review.file_diff_list.len(),
+                snapshot.review.notes.len()
+            )?;
+        }
+        Ok(())
+    }
+
+    fn restore_snapshot(&mut self, args: &SnapshotArgs, out: &mut impl Write) -> anyhow::Result<()> {
+        let (repository_id, review_id) = self.load_review(&args.review)?;
+        let snapshot = self.snapshot(&repository_id, &review_id, &args.name)?;
+        let backup_name = review_snapshot::auto_snapshot_name(BEFORE_RESTORE);
+        self.save_snapshot(&repository_id, &review_id, backup_name.clone())?;
+
+        let repository = self
+            .repositories
+            .get(&repository_id)
+            .unwrap_or_else(|| panic!("[BUG] Could not find {}", repository_id));
+        let review = self.review(&repository_id, &review_id);
+        review_snapshot::restore_snapshot(self.storage.as_ref(), &repository.name, review.name(), &snapshot)?;
+        let event = ReviewEvent::SnapshotRestored { name: args.name.clone() };
+        self.storage.append_review_events(
+            &repository.name,
+            review.name(),
+            &[ReviewEventStore::new(git_utils::user_name(repository.path()), event)],
+        )?;
+
+        writeln!(out, "Restored snapshot {}, the previous state is kept as snapshot {}", args.name, backup_name)?;
+        Ok(())
+    }
+
+    fn compare_snapshot(&mut self, args: &SnapshotArgs, out: &mut impl Write) -> anyhow::Result<()> {
+        let (repository_id, review_id) = self.load_review(&args.review)?;
+        let snapshot = self.snapshot(&repository_id, &review_id, &args.name)?;
+        let differences = review_snapshot::compare_snapshot(&snapshot.review, &self.review(&repository_id, &review_id).store());
+        if differences.is_empty() {
+            writeln!(out, "Nothing changed since snapshot {}", args.name)?;
+        }
+        for difference in differences {
+            writeln!(out, "{}", difference)?;
+        }
+        Ok(())
+    }
+
+    fn delete_snapshot(&mut self, args: &SnapshotArgs, out: &mut impl Write) -> anyhow::Result<()> {
+        let (repository_id, review_id) = self.load_review(&args.review)?;
+        let repository = self
+            .repositories
+            .get(&repository_id)
+            .unwrap_or_else(|| panic!("[BUG] Could not find {}", repository_id));
+        let review = self.review(&repository_id, &review_id);
+        self.storage.delete_review_snapshot(&repository.name, review.name(), &args.name)?;
+
+        writeln!(out, "Deleted snapshot {} of {}", args.name, args.review.review)?;
+        Ok(())
+    }
+
+    fn migrate_storage(
+        &self,
+        from: StorageBackend,
+        to: StorageBackend,
+        from_path: Option<PathBuf>,
+        to_path: Option<PathBuf>,
+        out: &mut impl Write,
+    ) -> anyhow::Result<()> {
+        let from_path = from_path.unwrap_or_else(|| self.data_path.clone());
+        let to_path = to_path.unwrap_or_else(|| self.data_path.clone());
+        if from == to && from_path == to_path {
+            return Err(anyhow!("Source and target storage are the same"));
+        }
+
+        let source = create_storage(from_path, from)?;
+        let target = create_storage(to_path, to)?;
+        let summary = migration::migrate_storage(source.as_ref(), target.as_ref())?;
+        writeln!(
+            out,
+            "Migrated {} repositories, {} reviews, {} file diffs and {} notes from {} to {}",
+            summary.repositories, summary.reviews, summary.file_diffs, summary.notes, from, to
+        )?;
+        Ok(())
+    }
+
+    fn repository_id(&self, name: &str) -> anyhow::Result<RepositoryId> {
+        self.repositories
+            .repository_id(&RepositoryName::from(name))
+            .ok_or_else(|| anyhow!("Repository '{}' not found", name))
+    }
+
+    fn review_names(&self, repository_id: &RepositoryId) -> anyhow::Result<Vec<ReviewName>> {
+        let repository = self
+            .repositories
+            .get(repository_id)
+            .unwrap_or_else(|| panic!("[BUG] Could not find {}", repository_id));
+        Ok(self.storage.load_review_names(&repository.name)?)
+    }
+
+    fn load_review(&mut self, args: &ReviewArgs) -> anyhow::Result<(RepositoryId, ReviewId)> {
+        let repository_id = self.repository_id(&args.repository)?;
+        let review_names = self.review_names(&repository_id)?;
+
+        let repository = self
+            .repositories
+            .get_mut(&repository_id)
+            .unwrap_or_else(|| panic!("[BUG] Could not find {}", repository_id));
+        review_names.into_iter().for_each(|review_name| {
+            repository.reviews.register_review_name(review_name);
+        });
+
+        let review_name = ReviewName::from(args.review.as_str());
+        let review_id = repository
+            .reviews
+            .review_id(&review_name)
+            .ok_or_else(|| anyhow!("Review '{}' not found in repository '{}'", args.review, args.repository))?;
+
+        let store = self
+            .storage
+            .load_review(&repository.name, &review_name)?
+            .ok_or_else(|| anyhow!("Review '{}' could not be loaded", args.review))?;
+        repository.reviews.insert_review(review_id.clone(), Review::new(store, review_name));
+
+        Ok((repository_id, review_id))
+    }
+
+    fn snapshots(&self, repository_id: &RepositoryId, review_id: &ReviewId) -> anyhow::Result<Vec<SnapshotStore>> {
+        let repository = self
+            .repositories
+            .get(repository_id)
+            .unwrap_or_else(|| panic!("[BUG] Could not find {}", repository_id));
+        let review = self.review(repository_id, review_id);
+        Ok(self.storage.load_review_snapshots(&repository.name, review.name())?)
+    }
+
+    fn snapshot(&self, repository_id: &RepositoryId, review_id: &ReviewId, name: &str) -> anyhow::Result<SnapshotStore> {
+        self.snapshots(repository_id, review_id)?
+            .into_iter()
+            .find(|snapshot| snapshot.name == name)
+            .ok_or_else(|| anyhow!("Snapshot '{}' not found", name))
+    }
+
+    /// Stores the current state of the review as snapshot and logs it.
+    fn save_snapshot(&self, repository_id: &RepositoryId, review_id: &ReviewId, name: String) -> anyhow::Result<()> {
+        let repository = self
+            .repositories
+            .get(repository_id)
+            .unwrap_or_else(|| panic!("[BUG] Could not find {}", repository_id));
+        let review = self.review(repository_id, review_id);
+        self.storage
+            .save_review_snapshot(&repository.name, review.name(), &SnapshotStore::new(name.clone(), review.store()))?;
+        let event = ReviewEvent::SnapshotTaken { name };
+        self.storage.append_review_events(
+            &repository.name,
+            review.name(),
+            &[ReviewEventStore::new(git_utils::user_name(repository.path()), event)],
+        )?;
+        Ok(())
+    }
+
+    fn review(&self, repository_id: &RepositoryId, review_id: &ReviewId) -> &Review {
+        let repository = self
+            .repositories
+            .get(repository_id)
+            .unwrap_or_else(|| panic!("[BUG] Could not find {}", repository_id));
+        repository
+            .reviews
+            .get(review_id)
+            .unwrap_or_else(|| panic!("[BUG] Could not find {} ({})", review_id, repository_id))
+    }
+}
+
+fn checkbox(is_checked: bool) -> char {
+    if is_checked { 'x' } else { ' ' }
+}
+
+#[cfg(test)]
+mod tests {
+    use mockcmd::{mock, was_command_executed};
+    use serial_test::serial;
+    use std::{env, fs, path::PathBuf};
+
+    use super::*;
+    use crate::storage::repository_storage::{DiffRangeStore, FileDiffStore, IterationStore, RepositoryStore};
+    use crate::storage::{ReviewHelperSqliteStorage, create_storage};
+
+    struct Context(PathBuf);
+    impl Drop for Context {
+        fn drop(&mut self) {
+            let _ = fs::remove_dir_all(&self.0);
+        }
+    }
+
+    fn create_context() -> anyhow::Result<Context> {
+        let mut path = env::temp_dir();
+        path.push(format!("{}_cli_test", std::env!("CARGO_CRATE_NAME")));
+        let context = Context(path);
+        let _ = fs::remove_dir_all(&context.0);
+        fs::create_dir_all(&context.0)?;
+
+        let storage = create_storage(context.0.clone(), StorageBackend::File)?;
+        let repository_name = RepositoryName::from("review_helper");
+        storage.save_repository(&RepositoryStore {
+            path: PathBuf::from("/home/harry/workspace/review_helper"),
+            name: repository_name.clone(),
+            first_commit: "9f89049".to_string(),
+            base_branch: "main".to_string(),
+        })?;
+        let diff_range = DiffRangeStore {
+            start: "ed7811b".to_string(),
+            end: "a261b7b".to_string(),
+            ..Default::default()
+        };
+        let file_diffs = [
+            FileDiffStore {
+                file_path: PathBuf::from("src/main.rs"),
+                is_reviewed: false,
+                ..Default::default()
+            },
+            FileDiffStore {
+                file_path: PathBuf::from("README.md"),
+                is_reviewed: true,
+                ..Default::default()
+            },
+        ];
+        storage.save_review_file_diffs(
+            &repository_name,
+            &ReviewName::from("fancy_ui"),
+            &diff_range,
+            &file_diffs.iter().collect::<Vec<_>>(),
+        )?;
+        Ok(context)
+    }
+
+    fn run(context: &Context, args: &[&str]) -> anyhow::Result<String> {
+        let cli = Cli::try_parse_from(std::iter::once("review-helper").chain(args.iter().copied()))?;
+        let mut app = CliApp::new(create_storage(context.0.clone(), StorageBackend::File)?, context.0.clone())?;
+        let mut out = Vec::new();
+        app.run(cli.command.expect("subcommand expected"), &mut out)?;
+        Ok(String::from_utf8(out)?)
+    }
+
+    #[serial]
+    #[test]
+    fn test_list_commands() -> anyhow::Result<()> {
+        let context = create_context()?;
+
+        assert_eq!(run(&context, &["repo", "list"])?, "review_helper\t/home/harry/workspace/review_helper\tmain\n");
+        assert_eq!(run(&context, &["review", "list", "review_helper"])?, "fancy_ui\n");
+        assert!(run(&context, &["review", "list", "unknown"]).is_err());
+        Ok(())
+    }
+
+    #[serial]
+    #[test]
+    fn test_mark_file_and_add_note() -> anyhow::Result<()> {
+        let context = create_context()?;
+
+        run(&context, &["review", "mark", "review_helper", "fancy_ui", "src/main.rs", "--reviewed"])?;
+        run(
+            &context,
+            &[
+                "note",
+                "add",
+                "review_helper",
+                "fancy_ui",
+                "off-by-one",
+                "--context",
+                "src/main.rs",
+                "--lines",
+                "old:3-4",
+            ],
+        )?;
+        assert!(run(&context, &["review", "mark", "review_helper", "fancy_ui", "unknown.rs", "--reviewed"]).is_err());
+        assert!(run(&context, &["review", "mark", "review_helper", "fancy_ui", "src/main.rs"]).is_err());
+
+        let expected = [
+            "Review:     fancy_ui",
+            "Diff range: ed7811b..a261b7b",
+            "",
+            "Files (2/2 reviewed):",
+            "  [x] README.md",
+            "  [x] src/main.rs",
+            "",
+            "Notes:",
+            "  [ ] src/main.rs:old:3-4: off-by-one",
+            "",
+        ]
+        .join("\n");
+        assert_eq!(run(&context, &["review", "show", "review_helper", "fancy_ui"])?, expected);
+
+        let log = run(&context, &["review", "log", "review_helper", "fancy_ui"])?;
+        let descriptions = log
+            .lines()
+            .filter_map(|line| line.split_once(": "))
+            .map(|(_, description)| description)
+            .collect::<Vec<_>>();
+        assert_eq!(descriptions, ["Marked src/main.rs as reviewed", "Added note in src/main.rs: off-by-one"]);
+        assert!(run(&context, &["review", "log", "review_helper", "unknown"]).is_err());
+        Ok(())
+    }
+
+    #[serial]
+    #[test]
+    fn test_mark_file_in_commit() -> anyhow::Result<()> {
+        let context = create_context()?;
+
+        let mark = [
+            "review",
+            "mark",
+            "review_helper",
+            "fancy_ui",
+            "src/main.rs",
+            "--reviewed",
+            "--commit",
+            "9f89049",
+        ];
+        assert_eq!(run(&context, &mark)?, "Marked src/main.rs as reviewed in commit 9f89049\n");
+        // The file is only reviewed in the commit, not in the whole range.
+        assert!(run(&context, &["review", "show", "review_helper", "fancy_ui"])?.contains("  [ ] src/main.rs\n"));
+
+        let storage = create_storage(context.0.clone(), StorageBackend::File)?;
+        let review = storage
+            .load_review(&RepositoryName::from("review_helper"), &ReviewName::from("fancy_ui"))?
+            .expect("review fancy_ui exists");
+        let file_diff = review
+            .file_diff_list
+            .iter()
+            .find(|file_diff| file_diff.file_path == Path::new("src/main.rs"))
+            .unwrap();
+        assert_eq!(file_diff.reviewed_commits, ["9f89049"]);
+
+        let log = run(&context, &["review", "log", "review_helper", "fancy_ui"])?;
+        assert!(log.ends_with(": Marked src/main.rs as reviewed in commit 9f89049\n"));
+        Ok(())
+    }
+
+    #[serial]
+    #[test]
+    fn test_iterations() -> anyhow::Result<()> {
+        let context = create_context()?;
+        let storage = create_storage(context.0.clone(), StorageBackend::File)?;
+        let diff_range = DiffRangeStore {
+            start: "ed7811b".to_string(),
+            end: "a261b7b".to_string(),
+            iterations: vec![IterationStore {
+                start: "ed7811b".to_string(),
+                end: "9f89049".to_string(),
+            }],
+            ..Default::default()
+        };
+        storage.save_review_file_diffs(&RepositoryName::from("review_helper"), &ReviewName::from("fancy_ui"), &diff_range, &[])?;
+
+        let show = run(&context, &["review", "show", "review_helper", "fancy_ui"])?;
+        assert!(show.contains("Diff range: ed7811b..a261b7b\nIteration 1: ed7811b..9f89049\n"));
+        assert!(run(&context, &["review", "interdiff", "review_helper", "fancy_ui", "0"]).is_err());
+        assert!(run(&context, &["review", "interdiff", "review_helper", "fancy_ui", "2"]).is_err());
+
+        let diff_range = DiffRangeStore {
+            mode: DiffMode::Staged,
+            ..diff_range
+        };
+        let file_diff = FileDiffStore {
+            file_path: PathBuf::from("src/main.rs"),
+            ..Default::default()
+        };
+        storage.save_review_file_diffs(
+            &RepositoryName::from("review_helper"),
+            &ReviewName::from("fancy_ui"),
+            &diff_range,
+            &[&file_diff],
+        )?;
+        let show = run(&context, &["review", "show", "review_helper", "fancy_ui"])?;
+        assert!(show.contains("Diff mode:  staged\n"));
+
+        let repository_path = "/home/harry/workspace/review_helper";
+        let name_status_args = ["diff", "--name-status", "--cached", "9f89049"];
+        mock("git")
+            .current_dir(repository_path)
+            .with_args(name_status_args)
+            .with_stdout("M       src/main.rs\n")
+            .register();
+        let numstat_args = ["diff", "-z", "--numstat", "--cached", "9f89049"];
+        mock("git")
+            .current_dir(repository_path)
+            .with_args(numstat_args)
+            .with_stdout("3       1       src/main.rs\0")
+            .register();
+        assert_eq!(
+            run(&context, &["review", "interdiff", "review_helper", "fancy_ui", "1"])?,
+            "Changes since iteration 1 (ed7811b..9f89049):\n  Modified src/main.rs (+3 -1)\n"
+        );
+        assert!(was_command_executed(&[&["git"], &name_status_args[..]].concat(), Some(repository_path)));
+        // The index is no commit, so the rebase check is skipped.
+        assert!(!was_command_executed(
+            &["git", "merge-base", "--is-ancestor", "9f89049", "HEAD"],
+            Some(repository_path)
+        ));
+        Ok(())
+    }
+
+    #[serial]
+    #[test]
+    fn test_snapshots() -> anyhow::Result<()> {
+        let context = create_context()?;
+        let review = ["review_helper", "fancy_ui"];
+        let snapshot = |command: &'static str, name: &'static str| -> Vec<&str> { [&["snapshot", command], &review[..], &[name]].concat() };
+
+        assert_eq!(run(&context, &snapshot("create", "initial"))?, "Took snapshot initial of fancy_ui\n");
+        assert!(run(&context, &snapshot("create", "initial")).is_err());
+        assert!(run(&context, &snapshot("create", "no-name")).is_err());
+        assert_eq!(run(&context, &snapshot("compare", "initial"))?, "Nothing changed since snapshot initial\n");
+
+        run(&context, &["review", "mark", "review_helper", "fancy_ui", "src/main.rs", "--reviewed"])?;
+        run(&context, &["note", "add", "review_helper", "fancy_ui", "typo"])?;
+        assert_eq!(
+            run(&context, &snapshot("compare", "initial"))?,
+            "File src/main.rs was reviewed since the snapshot\nNote was added since the snapshot: typo\n"
+        );
+
+        let output = run(&context, &snapshot("restore", "initial"))?;
+        assert!(output.starts_with("Restored snapshot initial, the previous state is kept as snapshot before-restore-"));
+        assert_eq!(run(&context, &snapshot("compare", "initial"))?, "Nothing changed since snapshot initial\n");
+        assert!(run(&context, &["review", "show", "review_helper", "fancy_ui"])?.contains("  [ ] src/main.rs\n"));
+
+        let snapshots = run(&context, &[&["snapshot", "list"], &review[..]].concat())?;
+        // Both snapshots may be taken in the same second, so they are compared by name.
+        let snapshots = snapshots.lines().map(|line| line.split('\t').skip(1).join(" ")).sorted().collect::<Vec<_>>();
+        assert_eq!(snapshots.len(), 2);
+        assert!(snapshots[0].starts_with("before-restore-") && snapshots[0].ends_with(" 2 files, 1 notes"));
+        assert_eq!(snapshots[1], "initial 2 files, 0 notes");
+
+        assert_eq!(run(&context, &snapshot("delete", "initial"))?, "Deleted snapshot initial of fancy_ui\n");
+        assert!(run(&context, &snapshot("restore", "initial")).is_err());
+        Ok(())
+    }
+
+    #[serial]
+    #[test]
+    fn test_import_review() -> anyhow::Result<()> {
+        let context = create_context()?;
+        let json = r#"{
+            "version": 1,
+            "repository": { "name": "other_name", "path": "/tmp/review_helper", "first_commit": "9f89049", "base_branch": "main" },
+            "review": {
+                "name": "fancy_ui",
+                "diff_range": { "start": "ed7811b", "end": "" },
+                "files": [{ "path": "src/lib.rs", "is_reviewed": true, "status": null }],
+                "notes": [{ "text": "off-by-one", "context": "src/lib.rs", "line_range": { "side": "new", "start": 3, "end": 3 }, "is_outdated": false, "is_done": false }]
+            }
+        }"#;
+        let path = context.0.join("fancy_ui.json");
+        fs::write(&path, json)?;
+        let path = path.to_string_lossy().to_string();
+
+        assert!(run(&context, &["review", "import", &path]).is_err());
+        assert_eq!(
+            run(&context, &["review", "import", &path, "--name", "imported"])?,
+            "Imported review imported into review_helper\n"
+        );
+
+        let expected = [
+            "Review:     imported",
+            "Diff range: ed7811b..working copy",
+            "",
+            "Files (1/1 reviewed):",
+            "  [x] src/lib.rs",
+            "",
+            "Notes:",
+            "  [ ] src/lib.rs:3: off-by-one",
+            "",
+        ]
+        .join("\n");
+        assert_eq!(run(&context, &["review", "show", "review_helper", "imported"])?, expected);
+        Ok(())
+    }
+
+    #[serial]
+    #[test]
+    fn test_migrate_storage() -> anyhow::Result<()> {
+        let context = create_context()?;
+
+        assert!(run(&context, &["storage", "migrate", "--from", "file", "--to", "file"]).is_err());
+        assert_eq!(
+            run(&context, &["storage", "migrate", "--from", "file", "--to", "sqlite"])?,
+            "Migrated 1 repositories, 1 reviews, 2 file diffs and 0 notes from file to sqlite\n"
+        );
+        assert!(run(&context, &["storage", "migrate", "--from", "file", "--to", "sqlite"]).is_err());
+
+        let sqlite_storage = ReviewHelperSqliteStorage::new(context.0.clone())?;
+        assert_eq!(
+            sqlite_storage.load_review_names(&RepositoryName::from("review_helper"))?,
+            vec![ReviewName::from("fancy_ui")]
+        );
+        Ok(())
+    }
+}
@@ -0,0 +1,165 @@
+use crate::git_utils::{DiffHunk, DiffLine, DiffLineKind, HunkHeader};
+use crate::syntax_highlighting::{Highlighter, Language, Span};
+
+pub const CONTEXT_LINES: u32 = 3;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DiffCell {
+    pub line: u32,
+    pub text: String,
+    pub kind: DiffLineKind,
+    pub spans: Vec<Span>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SideBySideRow {
+    Hunk { index: usize, header: HunkHeader },
+    Lines { old: Option<DiffCell>, new: Option<DiffCell> },
+}
+
+fn old_cell(diff_line: DiffLine) -> Option<DiffCell> {
+    Some(DiffCell {
+        line: diff_line.old_line?,
+        text: diff_line.text,
+        kind: diff_line.kind,
+        spans: Vec::new(),
+    })
+}
+
+fn new_cell(diff_line: DiffLine) -> Option<DiffCell> {
+    Some(DiffCell {
+        line: diff_line.new_line?,
+        text: diff_line.text,
+        kind: diff_line.kind,
+        spans: Vec::new(),
+    })
+}
+
+/// Aligns the lines of the hunks for a side by side view. Removed and added
+/// lines between two context lines are placed next to each other.
+pub fn side_by_side_rows(hunks: Vec<DiffHunk>) -> Vec<SideBySideRow> {
+    let mut rows = Vec::new();
+
+    let flush = |rows: &mut Vec<SideBySideRow>, removed: &mut Vec<DiffLine>, added: &mut Vec<DiffLine>| {
+        let row_count = removed.len().max(added.len());
+        let mut removed_iter = removed.drain(..);
+        let mut added_iter = added.drain(..);
+        for _ in 0..row_count {
+            let old = removed_iter.next().and_then(old_cell);
+            let new = added_iter.next().and_then(new_cell);
+            rows.push(SideBySideRow::Lines { old, new });
+        }
+    };
+
+    for (index, hunk) in hunks.into_iter().enumerate() {
+        rows.push(SideBySideRow::Hunk { index, header: hunk.header });
+
+        let mut removed = Vec::new();
+        let mut added = Vec::new();
+        for diff_line in hunk.lines {
+            match diff_line.kind {
+                DiffLineKind::Removed => removed.push(diff_line),
+                DiffLineKind::Added => added.push(diff_line),
+                DiffLineKind::Context => {
+                    flush(&mut rows, &mut removed, &mut added);
+                    rows.push(SideBySideRow::Lines {
+                        old: old_cell(diff_line.clone()),
+                        new: new_cell(diff_line),
+                    });
+                }
+            }
+        }
+        flush(&mut rows, &mut removed, &mut added);
+    }
+    rows
+}
+
+/// Fills in the syntax highlighting spans of all cells. Each side is
+/// highlighted separately in line order, starting fresh at every hunk.
+pub fn highlight_rows(rows: &mut [SideBySideRow], language: Language) {
+    let mut old_highlighter = Highlighter::new(language);
+    let mut new_highlighter = Highlighter::new(language);
+
+    for row in rows {
+        match row {
+            SideBySideRow::Hunk { .. } => {
+                old_highlighter.reset();
+                new_highlighter.reset();
+            }
+            SideBySideRow::Lines { old, new } => {
+                if let Some(cell) = old {
+                    cell.spans = old_highlighter.highlight_line(&cell.text);
+                }
+                if let Some(cell) = new {
+                    cell.spans = new_highlighter.highlight_line(&cell.text);
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn diff_line(kind: DiffLineKind, old_line: Option<u32>, new_line: Option<u32>, text: &str) -> DiffLine {
+        DiffLine {
+            kind,
+            old_line,
+            new_line,
+            text: text.to_string(),
+        }
+    }
+
+    fn cell(line: u32, kind: DiffLineKind, text: &str) -> Option<DiffCell> {
+        Some(DiffCell {
+            line,
+            kind,
+            text: text.to_string(),
+            spans: Vec::new(),
+        })
+    }
+
+    #[test]
+    fn test_side_by_side_rows() {
+        let header = HunkHeader {
+            old_start: 1,
+            old_count: 4,
+            new_start: 1,
+            new_count: 3,
+        };
+        let hunks = vec![DiffHunk {
+            header: header.clone(),
+            lines: vec![
+                diff_line(DiffLineKind::Context, Some(1), Some(1), "a"),
+                diff_line(DiffLineKind::Removed, Some(2), None, "b"),
+                diff_line(DiffLineKind::Removed, Some(3), None, "c"),
+                diff_line(DiffLineKind::Added, None, Some(2), "B"),
+                diff_line(DiffLineKind::Context, Some(4), Some(3), "d"),
+            ],
+        }];
+
+        let rows = side_by_side_rows(hunks);
+
+        let expected_rows = vec![
+            SideBySideRow::Hunk { index: 0, header },
+            SideBySideRow::Lines {
+                old: cell(1, DiffLineKind::Context, "a"),
+                new: cell(1, DiffLineKind::Context, "a"),
+            },
+            SideBySideRow::Lines {
+                old: cell(2, DiffLineKind::Removed, "b"),
+                new: cell(2, DiffLineKind::Added, "B"),
+            },
+            SideBySideRow::Lines {
+                old: cell(3, DiffLineKind::Removed, "c"),
+                new: None,
+            },
+            SideBySideRow::Lines {
+                old: cell(4, DiffLineKind::Context, "d"),
+                new: cell(3, DiffLineKind::Context, "d"),
+            },
+        ];
+        assert_eq!(rows, expected_rows);
+    }
+}
@@ -14,4 +14,4 @@ pub use repository_controller::setup_repository_callbacks;
 pub use review_controller::setup_review_callbacks;
 pub use review_helper_controller::setup_review_helper;
 pub use review_helper_settings_controller::setup_review_helper_settings;
-pub use utils_controller::setup_utils;
+pub use utils_controller::{is_valid_name, setup_utils};
@@ -6,6 +6,7 @@ use crate::{
     worker::{WorkerChannel, WorkerMessage},
 };
 
+use native_dialog::FileDialog;
 use slint::{ComponentHandle, Model};
 
 pub fn setup_repository_callbacks(app_window: &ui::AppWindow, worker_channel: WorkerChannel) {
@@ -38,6 +39,23 @@ pub fn setup_repository_callbacks(app_window: &ui::AppWindow, worker_channel: Wo
                 .expect("Worker channel broken!");
         }
     });
+    app_window.global::<ui::SlintRepositoryCallbacks>().on_import_review({
+        let channel = worker_channel.clone();
+        move |id| {
+            if let Some(path) = FileDialog::new()
+                .set_location("~")
+                .add_filter("JSON", &["json"])
+                .show_open_single_file()
+                .expect("Could not create FileDialog! Check your dependencies!")
+            {
+                let message = WorkerMessage::ImportReview {
+                    repository_id: RepositoryId::from(id),
+                    path,
+                };
+                channel.send(message).expect("Worker channel broken!");
+            }
+        }
+    });
     app_window.global::<ui::SlintRepositoryCallbacks>().on_delete_review({
         let channel = worker_channel.clone();
         move |ids| {
@@ -10,6 +10,7 @@ use crate::{
     worker::{NoteChangeType, ReviewContent, WorkerChannel, WorkerMessage},
 };
 
+use native_dialog::FileDialog;
 use slint::{ComponentHandle, Model, ModelRc};
 
 pub fn setup_review_callbacks(app_window: &ui::AppWindow, worker_channel: WorkerChannel, proxy_models: Rc<RefCell<RepositoriesProxyModels>>) {
@@ -181,6 +182,24 @@ pub fn setup_review_callbacks(app_window: &ui::AppWindow, worker_channel: Worker
             channel.send(message).expect("Worker channel broken!");
         }
     });
+    app_window.global::<ui::SlintReviewCallbacks>().on_change_note_line_range({
+        let channel = worker_channel.clone();
+        move |ids, new_line_range| {
+            let repository_id = RepositoryId::from(ids.review_id_parameters.repository_id);
+            let review_id = ReviewId::from(ids.review_id_parameters.review_id);
+            let note_id = NoteId::from(ids.note_id);
+            let content_change = ReviewContent::Note {
+                note_id,
+                change_type: NoteChangeType::LineRange(model_utils::line_range_from_ui(&new_line_range)),
+            };
+            let message = WorkerMessage::ChangeReview {
+                repository_id,
+                review_id,
+                content_change,
+            };
+            channel.send(message).expect("Worker channel broken!");
+        }
+    });
     app_window.global::<ui::SlintReviewCallbacks>().on_change_note_is_done({
         let channel = worker_channel.clone();
         move |ids, new_is_done| {
@@ -210,7 +229,21 @@ pub fn setup_review_callbacks(app_window: &ui::AppWindow, worker_channel: Worker
                 diff_range: DiffRangeStore {
                     start: String::from(diff_range.start.as_str()),
                     end: String::from(diff_range.end.as_str()),
+                    mode: model_utils::diff_mode_from_ui(diff_range.mode),
+                    ..Default::default()
                 },
+                interdiff_iteration: usize::try_from(diff_range.interdiff_iteration).ok().filter(|number| *number > 0),
+            };
+            channel.send(message).expect("Worker channel broken!");
+        }
+    });
+    app_window.global::<ui::SlintReviewCallbacks>().on_select_commit({
+        let channel = worker_channel.clone();
+        move |ids, commit_index| {
+            let message = WorkerMessage::SelectCommit {
+                repository_id: RepositoryId::from(ids.repository_id),
+                review_id: ReviewId::from(ids.review_id),
+                commit_index: usize::try_from(commit_index).ok().and_then(|index| index.checked_sub(1)),
             };
             channel.send(message).expect("Worker channel broken!");
         }
@@ -226,6 +259,39 @@ pub fn setup_review_callbacks(app_window: &ui::AppWindow, worker_channel: Worker
             channel.send(message).expect("Worker channel broken!");
         }
     });
+    app_window.global::<ui::SlintReviewCallbacks>().on_load_content_diff({
+        let channel = worker_channel.clone();
+        move |ids| {
+            let message = WorkerMessage::LoadContentDiff {
+                repository_id: RepositoryId::from(ids.review_id_parameters.repository_id),
+                review_id: ReviewId::from(ids.review_id_parameters.review_id),
+                file_diff_id: FileDiffId::from(ids.file_diff_id),
+            };
+            channel.send(message).expect("Worker channel broken!");
+        }
+    });
+    app_window.global::<ui::SlintReviewCallbacks>().on_export_report({
+        let channel = worker_channel.clone();
+        move |ids| {
+            if let Some(path) = FileDialog::new()
+                .set_location("~")
+                .set_filename("review_report.md")
+                .add_filter("Markdown", &["md"])
+                .add_filter("HTML", &["html"])
+                .add_filter("JSON", &["json"])
+                .add_filter("SARIF", &["sarif"])
+                .show_save_single_file()
+                .expect("Could not create FileDialog! Check your dependencies!")
+            {
+                let message = WorkerMessage::ExportReport {
+                    repository_id: RepositoryId::from(ids.repository_id),
+                    review_id: ReviewId::from(ids.review_id),
+                    path,
+                };
+                channel.send(message).expect("Worker channel broken!");
+            }
+        }
+    });
     app_window.global::<ui::SlintReviewCallbacks>().on_delete_note({
         let channel = worker_channel.clone();
         move |ids| {
@@ -237,14 +303,79 @@ pub fn setup_review_callbacks(app_window: &ui::AppWindow, worker_channel: Worker
             channel.send(message).expect("Worker channel broken!");
         }
     });
+    app_window.global::<ui::SlintReviewCallbacks>().on_undo({
+        let channel = worker_channel.clone();
+        move |ids| {
+            let message = WorkerMessage::Undo {
+                repository_id: RepositoryId::from(ids.repository_id),
+                review_id: ReviewId::from(ids.review_id),
+            };
+            channel.send(message).expect("Worker channel broken!");
+        }
+    });
+    app_window.global::<ui::SlintReviewCallbacks>().on_redo({
+        let channel = worker_channel.clone();
+        move |ids| {
+            let message = WorkerMessage::Redo {
+                repository_id: RepositoryId::from(ids.repository_id),
+                review_id: ReviewId::from(ids.review_id),
+            };
+            channel.send(message).expect("Worker channel broken!");
+        }
+    });
+    app_window.global::<ui::SlintReviewCallbacks>().on_take_snapshot({
+        let channel = worker_channel.clone();
+        move |ids, name| {
+            let message = WorkerMessage::TakeSnapshot {
+                repository_id: RepositoryId::from(ids.repository_id),
+                review_id: ReviewId::from(ids.review_id),
+                name: name.to_string(),
+            };
+            channel.send(message).expect("Worker channel broken!");
+        }
+    });
+    app_window.global::<ui::SlintReviewCallbacks>().on_restore_snapshot({
+        let channel = worker_channel.clone();
+        move |ids, name| {
+            let message = WorkerMessage::RestoreSnapshot {
+                repository_id: RepositoryId::from(ids.repository_id),
+                review_id: ReviewId::from(ids.review_id),
+                name: name.to_string(),
+            };
+            channel.send(message).expect("Worker channel broken!");
+        }
+    });
+    app_window.global::<ui::SlintReviewCallbacks>().on_compare_snapshot({
+        let channel = worker_channel.clone();
+        move |ids, name| {
+            let message = WorkerMessage::CompareSnapshot {
+                repository_id: RepositoryId::from(ids.repository_id),
+                review_id: ReviewId::from(ids.review_id),
+                name: name.to_string(),
+            };
+            channel.send(message).expect("Worker channel broken!");
+        }
+    });
+    app_window.global::<ui::SlintReviewCallbacks>().on_delete_snapshot({
+        let channel = worker_channel.clone();
+        move |ids, name| {
+            let message = WorkerMessage::DeleteSnapshot {
+                repository_id: RepositoryId::from(ids.repository_id),
+                review_id: ReviewId::from(ids.review_id),
+                name: name.to_string(),
+            };
+            channel.send(message).expect("Worker channel broken!");
+        }
+    });
     app_window.global::<ui::SlintReviewCallbacks>().on_add_note({
         let channel = worker_channel.clone();
-        move |ids, note_text, note_context| {
+        move |ids, note_text, note_context, line_range| {
             let message = WorkerMessage::AddNote {
                 repository_id: RepositoryId::from(ids.repository_id),
                 review_id: ReviewId::from(ids.review_id),
                 text: String::from(note_text.as_str()),
                 context: String::from(note_context.as_str()),
+                line_range: model_utils::line_range_from_ui(&line_range),
             };
             channel.send(message).expect("Worker channel broken!");
         }
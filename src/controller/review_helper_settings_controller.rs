@@ -1,5 +1,6 @@
 use slint::ComponentHandle;
 
+use crate::storage::StorageBackend;
 use crate::ui::{self, AppWindow};
 use crate::unwrap_or_return;
 
@@ -18,12 +19,14 @@ pub fn setup_review_helper_settings(app_window: &AppWindow, worker_channel: Work
             let editor = ui_app_config.get_editor().to_string();
             let editor_args = ui_app_config.get_editor_args().split(",").map(|s| s.to_string()).collect();
             let color_scheme = ui_app_config.get_color_scheme().to_string();
+            let storage_backend = ui_app_config.get_storage_backend().parse::<StorageBackend>().unwrap_or_default();
             channel
                 .send(crate::worker::WorkerMessage::SaveReviewHelperSettings {
                     diff_tool,
                     editor,
                     editor_args,
                     color_scheme,
+                    storage_backend,
                 })
                 .expect("Worker channel broken!");
         }
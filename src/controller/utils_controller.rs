@@ -1,4 +1,4 @@
-use crate::ui;
+use crate::{model::model_utils, storage::repository_storage::LineRange, ui};
 use slint::{ComponentHandle, Model, SharedString};
 use std::path::PathBuf;
 
@@ -46,7 +46,24 @@ pub fn setup_utils(app_window: &ui::AppWindow) {
             let date_time: DateTime<Local> = date_time_string.to_string().parse().expect("Could not parse date time string!");
             SharedString::from(format!("{}", date_time.format("%d/%m/%Y %H:%M:%S")))
         }
-    })
+    });
+    app_window.global::<ui::SlintStringUtils>().on_format_line_range({
+        |line_range| -> SharedString {
+            match model_utils::line_range_from_ui(&line_range) {
+                Some(line_range) => SharedString::from(line_range.to_string()),
+                None => SharedString::new(),
+            }
+        }
+    });
+    app_window.global::<ui::SlintStringUtils>().on_parse_line_range({
+        |text| -> ui::SlintLineRange {
+            let line_range = text.parse::<LineRange>().ok();
+            model_utils::line_range_to_ui(line_range.as_ref())
+        }
+    });
+    app_window
+        .global::<ui::SlintStringUtils>()
+        .on_is_valid_line_range(|text| text.trim().is_empty() || text.parse::<LineRange>().is_ok());
 }
 
 #[cfg(test)]
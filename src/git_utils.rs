@@ -2,6 +2,7 @@
 use std::process::Command;
 use std::{
     collections::{HashMap, HashSet},
+    hash::{DefaultHasher, Hash, Hasher},
     path::{Path, PathBuf},
 };
 
@@ -12,6 +13,8 @@ use itertools::Itertools;
 
 use which::which;
 
+use crate::storage::repository_storage::INDEX;
+
 #[derive(Debug, Eq, Ord, PartialEq, PartialOrd, Clone)]
 pub enum ChangeType {
     Invalid,
@@ -52,6 +55,24 @@ pub struct DiffStatus {
 
 pub type FileDiffMap = HashMap<String, DiffStatus>;
 
+/// The revisions of a `git diff` from `start_commit` to `end_commit`.
+fn push_revisions<'a>(args: &mut Vec<&'a str>, start_commit: &'a str, end_commit: &'a str) {
+    if end_commit == INDEX {
+        args.push("--cached");
+    }
+    if !start_commit.is_empty() && start_commit != INDEX {
+        args.push(start_commit);
+    }
+    if !end_commit.is_empty() && end_commit != INDEX {
+        args.push(end_commit);
+    }
+}
+
+/// The commit of a revision that can't be the working tree or index, those are based on `HEAD`.
+fn commit_or_head(commit: &str) -> &str {
+    if commit.is_empty() || commit == INDEX { "HEAD" } else { commit }
+}
+
 use chrono::DateTime;
 
 #[cfg(windows)]
@@ -102,12 +123,7 @@ pub fn diff_git_repo(repo_path: &Path, start_commit: &str, end_commit: &str) ->
 fn diff_name_status(repo_path: &Path, start_commit: &str, end_commit: &str) -> anyhow::Result<HashMap<String, ChangeType>> {
     let mut args = vec!["diff", "--name-status"];
 
-    if !start_commit.is_empty() {
-        args.push(start_commit);
-    }
-    if !end_commit.is_empty() {
-        args.push(end_commit);
-    }
+    push_revisions(&mut args, start_commit, end_commit);
 
     let output = git_command!(repo_path, args).output()?;
 
@@ -145,12 +161,7 @@ fn query_file_stats(
 ) -> anyhow::Result<HashMap<String, DiffStatus>> {
     let mut args = vec!["diff", "-z", "--numstat"];
 
-    if !start_commit.is_empty() {
-        args.push(start_commit);
-    }
-    if !end_commit.is_empty() {
-        args.push(end_commit);
-    }
+    push_revisions(&mut args, start_commit, end_commit);
 
     let output = git_command!(repo_path, args).output()?;
 
@@ -203,12 +214,7 @@ pub fn diff_file(repo_path: &Path, start_commit: &str, end_commit: &str, file: &
 
     args.push(&diff_tool);
 
-    if !start_commit.is_empty() {
-        args.push(start_commit);
-    }
-    if !end_commit.is_empty() {
-        args.push(end_commit);
-    }
+    push_revisions(&mut args, start_commit, end_commit);
 
     args.push("--");
     args.push(file);
@@ -221,6 +227,216 @@ pub fn diff_file(repo_path: &Path, start_commit: &str, end_commit: &str, file: &
     Ok(())
 }
 
+/// Header of a unified diff hunk: `@@ -old_start,old_count +new_start,new_count @@`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HunkHeader {
+    pub old_start: u32,
+    pub old_count: u32,
+    pub new_start: u32,
+    pub new_count: u32,
+}
+
+impl HunkHeader {
+    pub fn parse(line: &str) -> Option<HunkHeader> {
+        let parse_range = |range: &str| -> Option<(u32, u32)> {
+            match range.split_once(',') {
+                Some((start, count)) => Some((start.parse().ok()?, count.parse().ok()?)),
+                None => Some((range.parse().ok()?, 1)),
+            }
+        };
+        let mut parts = line.strip_prefix("@@ ")?.split_whitespace();
+        let (old_start, old_count) = parse_range(parts.next()?.strip_prefix('-')?)?;
+        let (new_start, new_count) = parse_range(parts.next()?.strip_prefix('+')?)?;
+        Some(HunkHeader {
+            old_start,
+            old_count,
+            new_start,
+            new_count,
+        })
+    }
+}
+
+/// Maps a line of the old file version to the new version based on the
+/// hunks of a `-U0` diff. Returns `None` if the line was removed or changed.
+pub fn map_line(hunks: &[HunkHeader], line: u32) -> Option<u32> {
+    let mut offset: i64 = 0;
+    for hunk in hunks {
+        let last_old_line = if hunk.old_count == 0 {
+            hunk.old_start
+        } else {
+            if (hunk.old_start..hunk.old_start + hunk.old_count).contains(&line) {
+                return None;
+            }
+            hunk.old_start + hunk.old_count - 1
+        };
+        if line <= last_old_line {
+            break;
+        }
+        offset += hunk.new_count as i64 - hunk.old_count as i64;
+    }
+    u32::try_from(line as i64 + offset).ok()
+}
+
+/// Queries the hunk headers of `file` between two commits, an empty commit refers to the working
+/// tree and `INDEX` to the index.
+pub fn diff_hunk_headers(repo_path: &Path, from_commit: &str, to_commit: &str, file: &str) -> anyhow::Result<Vec<HunkHeader>> {
+    let mut args = vec!["diff", "--no-ext-diff", "-U0", "--no-color"];
+
+    // git diffs from a commit or the index only, the other directions are reversed.
+    match (from_commit, to_commit) {
+        (from, to) if from == to => return Ok(Vec::new()),
+        ("", INDEX) => args.push("-R"),
+        (from, INDEX) => args.extend(["--cached", from]),
+        (INDEX, "") => {}
+        (INDEX, to) => args.extend(["-R", "--cached", to]),
+        ("", to) => args.extend(["-R", to]),
+        (from, to) => push_revisions(&mut args, from, to),
+    }
+
+    args.push("--");
+    args.push(file);
+
+    let output = git_command!(repo_path, args).output()?;
+
+    if !output.status.success() {
+        anyhow::bail!("diff_hunk_headers: git diff of {} failed!", file);
+    }
+
+    let output_str = std::str::from_utf8(&output.stdout)?;
+
+    Ok(output_str.lines().filter_map(HunkHeader::parse).collect())
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DiffLineKind {
+    Context,
+    Added,
+    Removed,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DiffLine {
+    pub kind: DiffLineKind,
+    pub old_line: Option<u32>,
+    pub new_line: Option<u32>,
+    pub text: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DiffHunk {
+    pub header: HunkHeader,
+    pub lines: Vec<DiffLine>,
+}
+
+/// The changes of a single file, binary files have no hunks.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct FileHunks {
+    pub hunks: Vec<DiffHunk>,
+    pub is_binary: bool,
+}
+
+/// git only reports that binary files differ, e.g. `Binary files a/logo.png and b/logo.png differ`.
+fn is_binary_diff(diff: &str) -> bool {
+    diff.lines().any(|line| line.starts_with("Binary files ") && line.ends_with(" differ"))
+}
+
+/// Parses the hunks of a unified diff of a single file.
+pub fn parse_diff_hunks(diff: &str) -> Vec<DiffHunk> {
+    let mut hunks: Vec<DiffHunk> = Vec::new();
+    let mut old_line = 0;
+    let mut new_line = 0;
+
+    for line in diff.lines() {
+        if let Some(header) = HunkHeader::parse(line) {
+            old_line = header.old_start;
+            new_line = header.new_start;
+            hunks.push(DiffHunk { header, lines: Vec::new() });
+            continue;
+        }
+        let Some(hunk) = hunks.last_mut() else {
+            continue;
+        };
+        let diff_line = if let Some(text) = line.strip_prefix('+') {
+            new_line += 1;
+            DiffLine {
+                kind: DiffLineKind::Added,
+                old_line: None,
+                new_line: Some(new_line - 1),
+                text: text.to_string(),
+            }
+        } else if let Some(text) = line.strip_prefix('-') {
+            old_line += 1;
+            DiffLine {
+                kind: DiffLineKind::Removed,
+                old_line: Some(old_line - 1),
+                new_line: None,
+                text: text.to_string(),
+            }
+        } else if let Some(text) = line.strip_prefix(' ') {
+            old_line += 1;
+            new_line += 1;
+            DiffLine {
+                kind: DiffLineKind::Context,
+                old_line: Some(old_line - 1),
+                new_line: Some(new_line - 1),
+                text: text.to_string(),
+            }
+        } else {
+            // e.g. "\ No newline at end of file"
+            continue;
+        };
+        hunk.lines.push(diff_line);
+    }
+    hunks
+}
+
+/// A diff tool configured as `diff.external` would replace the unified diff, so it's disabled.
+pub fn diff_file_hunks(repo_path: &Path, start_commit: &str, end_commit: &str, file: &str, context_lines: u32) -> anyhow::Result<FileHunks> {
+    let context = format!("-U{}", context_lines);
+    let mut args = vec!["diff", "--no-ext-diff", "--no-color", context.as_str()];
+
+    push_revisions(&mut args, start_commit, end_commit);
+
+    args.push("--");
+    args.push(file);
+
+    let output = git_command!(repo_path, args).output()?;
+
+    if !output.status.success() {
+        anyhow::bail!("diff_file_hunks: git diff of {} failed!", file);
+    }
+
+    let output_str = String::from_utf8_lossy(&output.stdout);
+
+    Ok(FileHunks {
+        hunks: parse_diff_hunks(&output_str),
+        is_binary: is_binary_diff(&output_str),
+    })
+}
+
+/// A `git patch-id` like id of the changes to `file`: the changed lines without their line
+/// numbers and whitespace. Equal ids mean the same change, e.g. by a commit and its rebased
+/// version. Binary files have no lines, their blobs before and after the change are used instead.
+/// Ids are only comparable within one run.
+pub fn file_patch_id(repo_path: &Path, start_commit: &str, end_commit: &str, file: &str) -> anyhow::Result<u64> {
+    let file_hunks = diff_file_hunks(repo_path, start_commit, end_commit, file, 0)?;
+    let mut hasher = DefaultHasher::new();
+    if file_hunks.is_binary {
+        blob_hash(repo_path, start_commit, file)?.hash(&mut hasher);
+        blob_hash(repo_path, end_commit, file)?.hash(&mut hasher);
+    }
+    for line in file_hunks.hunks.iter().flat_map(|hunk| &hunk.lines) {
+        let sign = match line.kind {
+            DiffLineKind::Added => '+',
+            DiffLineKind::Removed => '-',
+            DiffLineKind::Context => ' ',
+        };
+        sign.hash(&mut hasher);
+        line.text.split_whitespace().for_each(|word| word.hash(&mut hasher));
+    }
+    Ok(hasher.finish())
+}
+
 pub fn first_commit(repo_path: &Path) -> anyhow::Result<String> {
     let args = vec!["rev-list", "--max-parents=0", "--reverse", "HEAD"];
     let output = git_command!(repo_path, args).output()?;
@@ -257,23 +473,55 @@ pub fn query_commits(repo_path: &Path) -> anyhow::Result<Vec<Commit>> {
 
     let output_str = std::str::from_utf8(&output.stdout)?;
 
-    output_str
-        .lines()
-        .filter(|line| !line.is_empty())
-        .map(|line| {
-            let parts = line.splitn(4, "¦").collect::<Vec<_>>();
-            if parts.len() < 4 {
-                anyhow::bail!("query_commits: Malformed git output line: {}", line);
-            }
-            let date_time = DateTime::parse_from_rfc3339(parts[2]).map_err(|e| anyhow::anyhow!("Invalid date {}: {}", parts[2], e))?;
-            Ok(Commit {
-                hash: parts[0].to_string(),
-                author: parts[1].to_string(),
-                date: date_time.to_string(),
-                message: parts[3].to_string(),
-            })
-        })
-        .collect()
+    output_str.lines().filter(|line| !line.is_empty()).map(parse_commit_line).collect()
+}
+
+/// Parses a `%h¦%an¦%aI¦%s` line of `git log`.
+fn parse_commit_line(line: &str) -> anyhow::Result<Commit> {
+    let parts = line.splitn(4, "¦").collect::<Vec<_>>();
+    if parts.len() < 4 {
+        anyhow::bail!("query_commits: Malformed git output line: {}", line);
+    }
+    let date_time = DateTime::parse_from_rfc3339(parts[2]).map_err(|e| anyhow::anyhow!("Invalid date {}: {}", parts[2], e))?;
+    Ok(Commit {
+        hash: parts[0].to_string(),
+        author: parts[1].to_string(),
+        date: date_time.to_string(),
+        message: parts[3].to_string(),
+    })
+}
+
+/// The commits of the diff range `start..end`, oldest first, with the files each of them changes.
+/// The range ends at `HEAD` for an empty `end`.
+pub fn query_range_commits(repo_path: &Path, start: &str, end: &str) -> anyhow::Result<Vec<(Commit, Vec<String>)>> {
+    let end = commit_or_head(end);
+    let range = format!("{}..{}", start, end);
+    let args = [
+        "--no-pager",
+        "log",
+        "--reverse",
+        "--first-parent",
+        "--name-only",
+        "--pretty=format:¦%h¦%an¦%aI¦%s",
+        range.as_str(),
+    ];
+    let output = git_command!(repo_path, args).output()?;
+
+    if !output.status.success() {
+        anyhow::bail!("git log failed: {}", String::from_utf8_lossy(&output.stderr));
+    }
+
+    let mut commits: Vec<(Commit, Vec<String>)> = Vec::new();
+    for line in std::str::from_utf8(&output.stdout)?.lines().filter(|line| !line.is_empty()) {
+        if let Some(commit_line) = line.strip_prefix("¦") {
+            commits.push((parse_commit_line(commit_line)?, Vec::new()));
+        } else if let Some((_, files)) = commits.last_mut() {
+            files.push(line.to_string());
+        } else {
+            anyhow::bail!("query_range_commits: Malformed git output line: {}", line);
+        }
+    }
+    Ok(commits)
 }
 
 fn query_diff_tools_from_config() -> anyhow::Result<HashSet<String>> {
@@ -376,6 +624,99 @@ pub fn current_branch(repo_path: &Path) -> anyhow::Result<String> {
     Ok(branch.to_string())
 }
 
+/// Whether `ancestor` is part of the history of `commit`, the working copy is based on `HEAD`.
+/// After a rebase the earlier commits of a branch aren't.
+pub fn is_ancestor(repo_path: &Path, ancestor: &str, commit: &str) -> anyhow::Result<bool> {
+    let commit = commit_or_head(commit);
+    let args = ["merge-base", "--is-ancestor", ancestor, commit];
+    let output = git_command!(repo_path, args).output()?;
+    match output.status.code() {
+        Some(0) => Ok(true),
+        Some(1) => Ok(false),
+        _ => anyhow::bail!("git merge-base failed: {}", String::from_utf8_lossy(&output.stderr)),
+    }
+}
+
+/// The `git range-diff` of two versions of a branch, e.g. before and after a rebase.
+pub fn range_diff(repo_path: &Path, old_start: &str, old_end: &str, new_start: &str, new_end: &str) -> anyhow::Result<String> {
+    let new_end = commit_or_head(new_end);
+    let old_range = format!("{}..{}", old_start, old_end);
+    let new_range = format!("{}..{}", new_start, new_end);
+    let args = ["range-diff", "--no-color", old_range.as_str(), new_range.as_str()];
+    let output = git_command!(repo_path, args).output()?;
+    if !output.status.success() {
+        anyhow::bail!("git range-diff failed: {}", String::from_utf8_lossy(&output.stderr));
+    }
+    Ok(String::from_utf8(output.stdout)?)
+}
+
+/// The blob hashes of `files` at `commit`, of the working copy for an empty commit and of the
+/// index for `INDEX`. Files that don't exist there, e.g. deleted ones, are missing in the result.
+pub fn blob_hashes(repo_path: &Path, commit: &str, files: &[&str]) -> anyhow::Result<HashMap<String, String>> {
+    if commit.is_empty() {
+        let files = files.iter().copied().filter(|file| repo_path.join(file).is_file()).collect::<Vec<_>>();
+        if files.is_empty() {
+            return Ok(HashMap::new());
+        }
+        let args = [&["hash-object", "--"], &files[..]].concat();
+        let output = git_command!(repo_path, args).output()?;
+        if !output.status.success() {
+            anyhow::bail!("git hash-object failed: {}", String::from_utf8_lossy(&output.stderr));
+        }
+        let hashes = std::str::from_utf8(&output.stdout)?.lines().map(str::to_string);
+        return Ok(files.into_iter().map(str::to_string).zip(hashes).collect());
+    }
+
+    if files.is_empty() {
+        return Ok(HashMap::new());
+    }
+    // Every entry looks like `<mode> blob <hash>\t<path>`, in the index `<mode> <hash> <stage>\t<path>`.
+    let (args, hash_position) = if commit == INDEX {
+        ([&["ls-files", "-s", "-z", "--"], files].concat(), 1)
+    } else {
+        ([&["ls-tree", "-r", "-z", commit, "--"], files].concat(), 2)
+    };
+    let output = git_command!(repo_path, &args).output()?;
+    if !output.status.success() {
+        anyhow::bail!("git {} failed: {}", args[0], String::from_utf8_lossy(&output.stderr));
+    }
+    std::str::from_utf8(&output.stdout)?
+        .split('\0')
+        .filter(|entry| !entry.is_empty())
+        .map(|entry| {
+            let (info, file) = entry
+                .split_once('\t')
+                .ok_or_else(|| anyhow::anyhow!("blob_hashes: Malformed tree entry: {}", entry))?;
+            let hash = info
+                .split_whitespace()
+                .nth(hash_position)
+                .ok_or_else(|| anyhow::anyhow!("blob_hashes: Malformed tree entry: {}", entry))?;
+            Ok((file.to_string(), hash.to_string()))
+        })
+        .collect()
+}
+
+/// The blob of one file at `commit`, `None` if it doesn't exist there.
+pub fn blob_hash(repo_path: &Path, commit: &str, file: &str) -> anyhow::Result<Option<String>> {
+    Ok(blob_hashes(repo_path, commit, &[file])?.remove(file))
+}
+
+/// The configured `user.name` of the repository, falls back to the user of the operating system.
+pub fn user_name(repo_path: &Path) -> String {
+    let args = ["config", "user.name"];
+    let user_name = git_command!(repo_path, args)
+        .output()
+        .ok()
+        .filter(|output| output.status.success())
+        .and_then(|output| String::from_utf8(output.stdout).ok())
+        .map(|user_name| user_name.trim().to_string())
+        .unwrap_or_default();
+    if !user_name.is_empty() {
+        return user_name;
+    }
+    std::env::var("USER").or_else(|_| std::env::var("USERNAME")).unwrap_or_default()
+}
+
 #[cfg(test)]
 #[cfg(not(windows))]
 mod tests {
@@ -526,6 +867,165 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn test_parse_hunk_header() {
+        assert_eq!(
+            HunkHeader::parse("@@ -10,2 +12,3 @@ fn main() {"),
+            Some(HunkHeader {
+                old_start: 10,
+                old_count: 2,
+                new_start: 12,
+                new_count: 3,
+            })
+        );
+        assert_eq!(
+            HunkHeader::parse("@@ -7 +6,0 @@"),
+            Some(HunkHeader {
+                old_start: 7,
+                old_count: 1,
+                new_start: 6,
+                new_count: 0,
+            })
+        );
+        assert_eq!(HunkHeader::parse("+@@ not a hunk"), None);
+    }
+
+    #[test]
+    fn test_map_line() {
+        // two lines inserted after line 3, line 10 removed
+        let hunks = [
+            HunkHeader {
+                old_start: 3,
+                old_count: 0,
+                new_start: 4,
+                new_count: 2,
+            },
+            HunkHeader {
+                old_start: 10,
+                old_count: 1,
+                new_start: 11,
+                new_count: 0,
+            },
+        ];
+
+        assert_eq!(map_line(&hunks, 1), Some(1));
+        assert_eq!(map_line(&hunks, 3), Some(3));
+        assert_eq!(map_line(&hunks, 4), Some(6));
+        assert_eq!(map_line(&hunks, 9), Some(11));
+        assert_eq!(map_line(&hunks, 10), None);
+        assert_eq!(map_line(&hunks, 11), Some(12));
+    }
+
+    #[test]
+    fn test_diff_hunk_headers() -> anyhow::Result<()> {
+        let ctx = setup();
+
+        let args = ["diff", "--no-ext-diff", "-U0", "--no-color", "-R", "a261b7b", "--", "src/lib.rs"];
+        let output = "diff --git a/src/lib.rs b/src/lib.rs\n\
+                      --- a/src/lib.rs\n\
+                      +++ b/src/lib.rs\n\
+                      @@ -5,0 +6,2 @@ use std::rc::Rc;\n\
+                      +mod foo;\n\
+                      +mod bar;\n";
+
+        git_mock(&ctx).with_args(args).with_stdout(output).register();
+
+        let hunks = diff_hunk_headers(&ctx.path, "", "a261b7b", "src/lib.rs")?;
+
+        let expected_git_cmd = [&["git"], &args[..]].concat();
+        assert!(was_command_executed(&expected_git_cmd, Some(&ctx.path.to_string_lossy())));
+
+        assert_eq!(
+            hunks,
+            vec![HunkHeader {
+                old_start: 5,
+                old_count: 0,
+                new_start: 6,
+                new_count: 2,
+            }]
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_diff_file_hunks() -> anyhow::Result<()> {
+        let ctx = setup();
+
+        let start_commit = "ed7811b";
+        let end_commit = "a261b7b";
+        let args = ["diff", "--no-ext-diff", "--no-color", "-U1", start_commit, end_commit, "--", "src/main.rs"];
+        let output = [
+            "diff --git a/src/main.rs b/src/main.rs",
+            "--- a/src/main.rs",
+            "+++ b/src/main.rs",
+            "@@ -2,3 +2,3 @@ fn main() {",
+            "     let a = 1;",
+            "-    let b = 2;",
+            "+    let b = 3;",
+            " }",
+            "\\ No newline at end of file",
+        ]
+        .join("\n");
+
+        git_mock(&ctx).with_args(args).with_stdout(output.as_str()).register();
+
+        let file_hunks = diff_file_hunks(&ctx.path, start_commit, end_commit, "src/main.rs", 1)?;
+        assert!(!file_hunks.is_binary);
+        let hunks = file_hunks.hunks;
+
+        let expected_git_cmd = [&["git"], &args[..]].concat();
+        assert!(was_command_executed(&expected_git_cmd, Some(&ctx.path.to_string_lossy())));
+
+        let expected_lines = vec![
+            DiffLine {
+                kind: DiffLineKind::Context,
+                old_line: Some(2),
+                new_line: Some(2),
+                text: "    let a = 1;".to_string(),
+            },
+            DiffLine {
+                kind: DiffLineKind::Removed,
+                old_line: Some(3),
+                new_line: None,
+                text: "    let b = 2;".to_string(),
+            },
+            DiffLine {
+                kind: DiffLineKind::Added,
+                old_line: None,
+                new_line: Some(3),
+                text: "    let b = 3;".to_string(),
+            },
+            DiffLine {
+                kind: DiffLineKind::Context,
+                old_line: Some(4),
+                new_line: Some(4),
+                text: "}".to_string(),
+            },
+        ];
+
+        assert_eq!(hunks.len(), 1);
+        assert_eq!(hunks[0].header.old_start, 2);
+        assert_eq!(hunks[0].lines, expected_lines);
+
+        let binary_output = "diff --git a/assets/logo.png b/assets/logo.png\n\
+                             index 3b18e51..8ab686e 100644\n\
+                             Binary files a/assets/logo.png and b/assets/logo.png differ\n";
+        git_mock(&ctx)
+            .with_args(["diff", "--no-ext-diff", "--no-color", "-U1", start_commit, end_commit, "--", "assets/logo.png"])
+            .with_stdout(binary_output)
+            .register();
+        assert_eq!(
+            diff_file_hunks(&ctx.path, start_commit, end_commit, "assets/logo.png", 1)?,
+            FileHunks {
+                hunks: Vec::new(),
+                is_binary: true,
+            }
+        );
+
+        Ok(())
+    }
+
     #[test]
     fn test_query_commits() -> anyhow::Result<()> {
         let ctx = setup();
@@ -553,6 +1053,36 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn test_query_range_commits() -> anyhow::Result<()> {
+        let ctx = setup();
+        let args = [
+            "--no-pager",
+            "log",
+            "--reverse",
+            "--first-parent",
+            "--name-only",
+            "--pretty=format:¦%h¦%an¦%aI¦%s",
+            "9f89049..HEAD",
+        ];
+        let output = "¦dd02a7c¦Christian von Wascinski¦2023-10-15T16:25:02+02:00¦feature: Add saving notes as todo.txt\n\
+                      src/main.rs\n\
+                      src/notes.rs\n\
+                      \n\
+                      ¦70989e0¦Christian von Wascinski¦2023-10-16T22:34:17+02:00¦feature: add open comments.\n\
+                      src/main.rs\n";
+        git_mock(&ctx).with_args(args).with_stdout(output).register();
+
+        let commits = query_range_commits(&ctx.path, "9f89049", "")?;
+
+        assert_eq!(commits.len(), 2);
+        assert_eq!(commits[0].0.hash, "dd02a7c");
+        assert_eq!(commits[0].1, vec!["src/main.rs".to_string(), "src/notes.rs".to_string()]);
+        assert_eq!(commits[1].0.message, "feature: add open comments.");
+        assert_eq!(commits[1].1, vec!["src/main.rs".to_string()]);
+        Ok(())
+    }
+
     #[test]
     fn test_query_diff_tools() -> anyhow::Result<()> {
         let args = ["config", "get", "--all", "--show-names", "--regexp", "difftool\\..*\\.(cmd|path)"];
@@ -612,4 +1142,128 @@ mod tests {
 
         Ok(())
     }
+
+    #[test]
+    fn test_user_name() {
+        let ctx = setup();
+        git_mock(&ctx).with_args(["config", "user.name"]).with_stdout("Harry Herold\n").register();
+
+        assert_eq!(user_name(&ctx.path), "Harry Herold");
+    }
+
+    #[test]
+    fn test_file_patch_id() -> anyhow::Result<()> {
+        let ctx = setup();
+        let diffs = [
+            ("ed7811b", "a261b7b", "@@ -3 +3 @@ fn main() {\n-    let b = 2;\n+    let b = 3;\n"),
+            // The rebased change moved by two lines and was reindented.
+            ("c134268", "9f89049", "@@ -5 +5 @@ fn main() {\n-  let b = 2;\n+  let  b = 3;\n"),
+            ("c134268", "70989e0", "@@ -5 +5 @@ fn main() {\n-  let b = 2;\n+  let b = 4;\n"),
+        ];
+        for (start_commit, end_commit, diff) in diffs {
+            git_mock(&ctx)
+                .with_args(["diff", "--no-ext-diff", "--no-color", "-U0", start_commit, end_commit, "--", "src/main.rs"])
+                .with_stdout(diff)
+                .register();
+        }
+
+        let patch_id = file_patch_id(&ctx.path, "ed7811b", "a261b7b", "src/main.rs")?;
+        assert_eq!(file_patch_id(&ctx.path, "c134268", "9f89049", "src/main.rs")?, patch_id);
+        assert_ne!(file_patch_id(&ctx.path, "c134268", "70989e0", "src/main.rs")?, patch_id);
+        Ok(())
+    }
+
+    #[test]
+    fn test_is_ancestor() -> anyhow::Result<()> {
+        let ctx = setup();
+        git_mock(&ctx).with_args(["merge-base", "--is-ancestor", "9f89049", "a261b7b"]).register();
+        git_mock(&ctx)
+            .with_args(["merge-base", "--is-ancestor", "ed7811b", "HEAD"])
+            .with_status(1)
+            .register();
+
+        assert!(is_ancestor(&ctx.path, "9f89049", "a261b7b")?);
+        assert!(!is_ancestor(&ctx.path, "ed7811b", "")?);
+        Ok(())
+    }
+
+    #[test]
+    fn test_range_diff() -> anyhow::Result<()> {
+        let ctx = setup();
+        let expected = "1:  9f89049 ! 1:  a261b7b Add iterations\n";
+        git_mock(&ctx)
+            .with_args(["range-diff", "--no-color", "ed7811b..9f89049", "c134268..a261b7b"])
+            .with_stdout(expected)
+            .register();
+
+        assert_eq!(range_diff(&ctx.path, "ed7811b", "9f89049", "c134268", "a261b7b")?, expected);
+        Ok(())
+    }
+
+    #[test]
+    fn test_blob_hashes() -> anyhow::Result<()> {
+        let ctx = setup();
+        git_mock(&ctx)
+            .with_args(["ls-tree", "-r", "-z", "a261b7b", "--", "src/lib.rs", "src/old.rs"])
+            .with_stdout("100644 blob 8ab686eafeb1f44702738c8b0f24f2567c36da6d\tsrc/lib.rs\0")
+            .register();
+        assert_eq!(
+            blob_hashes(&ctx.path, "a261b7b", &["src/lib.rs", "src/old.rs"])?,
+            HashMap::from([("src/lib.rs".to_string(), "8ab686eafeb1f44702738c8b0f24f2567c36da6d".to_string())])
+        );
+
+        // Files of the working copy are hashed, missing files are skipped.
+        git_mock(&ctx)
+            .with_args(["hash-object", "--", "Cargo.toml"])
+            .with_stdout("d670460b4b4aece5915caf5c68d12f560a9fe3e4\n")
+            .register();
+        assert_eq!(
+            blob_hashes(&ctx.path, "", &["Cargo.toml", "src/missing.rs"])?,
+            HashMap::from([("Cargo.toml".to_string(), "d670460b4b4aece5915caf5c68d12f560a9fe3e4".to_string())])
+        );
+
+        git_mock(&ctx)
+            .with_args(["ls-files", "-s", "-z", "--", "src/lib.rs"])
+            .with_stdout("100644 5d1e2a2cd3b4e6f7a8b9c0d1e2f3a4b5c6d7e8f9 0\tsrc/lib.rs\0")
+            .register();
+        assert_eq!(
+            blob_hash(&ctx.path, INDEX, "src/lib.rs")?,
+            Some("5d1e2a2cd3b4e6f7a8b9c0d1e2f3a4b5c6d7e8f9".to_string())
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_diff_index() -> anyhow::Result<()> {
+        let ctx = setup();
+        git_mock(&ctx)
+            .with_args(["diff", "--name-status", "--cached", "a261b7b"])
+            .with_stdout("M       src/main.rs\n")
+            .register();
+        git_mock(&ctx)
+            .with_args(["diff", "-z", "--numstat", "--cached", "a261b7b"])
+            .with_stdout("3       1       src/main.rs\0")
+            .register();
+        let staged = diff_git_repo(&ctx.path, "a261b7b", INDEX)?;
+        assert_eq!(staged["src/main.rs"].change_type, ChangeType::Modified);
+        assert_eq!(staged["src/main.rs"].added_lines, 3);
+
+        git_mock(&ctx)
+            .with_args(["diff", "--name-status"])
+            .with_stdout("A       src/lib.rs\n")
+            .register();
+        git_mock(&ctx)
+            .with_args(["diff", "-z", "--numstat"])
+            .with_stdout("7       0       src/lib.rs\0")
+            .register();
+        let unstaged = diff_git_repo(&ctx.path, INDEX, "")?;
+        assert_eq!(unstaged["src/lib.rs"].change_type, ChangeType::Added);
+
+        git_mock(&ctx)
+            .with_args(["diff", "--no-ext-diff", "-U0", "--no-color", "-R", "--cached", "a261b7b", "--", "src/lib.rs"])
+            .with_stdout("@@ -5,0 +6,2 @@\n")
+            .register();
+        assert_eq!(diff_hunk_headers(&ctx.path, INDEX, "a261b7b", "src/lib.rs")?.len(), 1);
+        Ok(())
+    }
 }
@@ -1,22 +1,30 @@
 #![deny(clippy::all)]
 
+use clap::{Parser, error::ErrorKind};
 use slint::{ComponentHandle, ModelRc, SharedString, VecModel};
-use std::{cell::RefCell, process, rc::Rc};
+use std::{cell::RefCell, io, process, rc::Rc};
 
 use crate::{
+    cli::{Cli, CliApp, CliCommand},
     log_utils::init_logger,
     model::{CommitProxyModels, RepositoriesProxyModels},
-    worker::Worker,
+    worker::{ReviewHelperSettings, Worker},
 };
 
 mod controller;
 mod model;
 mod storage;
 
+mod cli;
 mod command_utils;
+mod content_diff;
 mod git_utils;
 mod log_utils;
+mod report;
 mod repositories;
+mod review_json;
+mod review_snapshot;
+mod syntax_highlighting;
 mod worker;
 
 pub mod ui;
@@ -47,12 +55,39 @@ impl AppProxyModels {
 }
 
 pub fn main() {
+    match Cli::try_parse() {
+        Ok(Cli { command: Some(command) }) => {
+            if let Err(e) = run_cli(command) {
+                eprintln!("Error: {:#}", e);
+                process::exit(1);
+            }
+            return;
+        }
+        Ok(Cli { command: None }) => {}
+        Err(e)
+            if matches!(
+                e.kind(),
+                ErrorKind::DisplayHelp | ErrorKind::DisplayHelpOnMissingArgumentOrSubcommand | ErrorKind::DisplayVersion
+            ) =>
+        {
+            e.exit()
+        }
+        // Launchers may pass arguments of their own, e.g. a file to open, those must not prevent the GUI from starting.
+        Err(e) => eprintln!("Ignoring the command line arguments ({}), starting the GUI", e.kind()),
+    }
     if let Err(e) = run_app() {
         eprintln!("Critical error: {}", e);
         process::exit(1);
     }
 }
 
+fn run_cli(command: CliCommand) -> anyhow::Result<()> {
+    let app_data_path = storage::prepare_app_data_path();
+    let settings = ReviewHelperSettings::new(&app_data_path)?;
+    let storage = storage::create_storage(app_data_path.clone(), settings.storage_backend)?;
+    CliApp::new(storage, app_data_path)?.run(command, &mut io::stdout().lock())
+}
+
 fn run_app() -> Result<(), Box<dyn std::error::Error>> {
     init_logger()?;
 
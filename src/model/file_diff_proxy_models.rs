@@ -1,9 +1,7 @@
-use crate::ui;
+use crate::{model::model_utils::extension_from_filename, ui};
 use slint::{FilterModel, ModelRc, SharedString, SortModel};
 use std::cell::RefCell;
 use std::cmp::Ordering;
-use std::ffi::OsStr;
-use std::path::Path;
 use std::rc::Rc;
 
 use wildcard::Wildcard;
@@ -128,7 +126,3 @@ impl FileDiffProxyModels {
         self.sort_model.clone().into()
     }
 }
-
-fn extension_from_filename(filename: &str) -> Option<&str> {
-    Path::new(filename).extension().and_then(OsStr::to_str)
-}
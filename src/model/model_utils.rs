@@ -1,6 +1,13 @@
+use chrono::{DateTime, Local};
 use slint::{ComponentHandle, Model, ModelRc, SharedString, VecModel};
+use std::{ffi::OsStr, path::Path};
 
-use crate::{model::IdModel, ui};
+use crate::{
+    model::IdModel,
+    repositories::ReviewCommit,
+    storage::repository_storage::{DiffMode, DiffRangeStore, DiffSide, LineRange, ReviewEventStore, SnapshotStore},
+    ui,
+};
 
 #[macro_export]
 macro_rules! cast_model {
@@ -39,6 +46,87 @@ pub fn get_file_diff_model(app_window: &ui::AppWindow, repository_id: usize, rev
     Some(review.file_diff_model)
 }
 
+pub fn line_range_to_ui(line_range: Option<&LineRange>) -> ui::SlintLineRange {
+    match line_range {
+        Some(line_range) => ui::SlintLineRange {
+            start: line_range.start as i32,
+            end: line_range.end as i32,
+            is_old_side: line_range.side == DiffSide::Old,
+        },
+        None => ui::SlintLineRange::default(),
+    }
+}
+
+pub fn line_range_from_ui(line_range: &ui::SlintLineRange) -> Option<LineRange> {
+    if line_range.start <= 0 || line_range.end <= 0 {
+        return None;
+    }
+    let side = if line_range.is_old_side { DiffSide::Old } else { DiffSide::New };
+    Some(LineRange::new(side, line_range.start as u32, line_range.end as u32))
+}
+
+/// Times are shown in the local time zone, unparsable times as they were stored.
+fn stored_time_to_ui(time: &str) -> SharedString {
+    match DateTime::parse_from_rfc3339(time) {
+        Ok(time) => SharedString::from(time.with_timezone(&Local).format("%d/%m/%Y %H:%M:%S").to_string()),
+        Err(_) => SharedString::from(time),
+    }
+}
+
+pub fn review_event_to_ui(event: &ReviewEventStore) -> ui::SlintReviewEvent {
+    ui::SlintReviewEvent {
+        time: stored_time_to_ui(&event.time),
+        author: SharedString::from(event.author.as_str()),
+        description: SharedString::from(event.event.to_string()),
+    }
+}
+
+pub fn snapshot_to_ui(snapshot: &SnapshotStore) -> ui::SlintSnapshot {
+    ui::SlintSnapshot {
+        name: SharedString::from(snapshot.name.as_str()),
+        time: stored_time_to_ui(&snapshot.time),
+    }
+}
+
+/// The choices of what to compare, the whole diff range first and then the changes since each
+/// earlier iteration.
+pub fn iterations_to_ui(diff_range: &DiffRangeStore) -> Vec<SharedString> {
+    std::iter::once(SharedString::from("Whole diff range"))
+        .chain(
+            diff_range
+                .iterations
+                .iter()
+                .enumerate()
+                .map(|(index, iteration)| SharedString::from(format!("Since iteration {}: {}..{}", index + 1, iteration.start, iteration.end))),
+        )
+        .collect()
+}
+
+/// The index of the diff mode in the choices of the file diff setup view.
+pub fn diff_mode_to_ui(mode: DiffMode) -> i32 {
+    DiffMode::ALL.iter().position(|other| *other == mode).unwrap_or_default() as i32
+}
+
+pub fn diff_mode_from_ui(index: i32) -> DiffMode {
+    usize::try_from(index)
+        .ok()
+        .and_then(|index| DiffMode::ALL.get(index).copied())
+        .unwrap_or_default()
+}
+
+/// The choices of the commit-by-commit mode, all commits together first and then each commit with
+/// its position in the diff range.
+pub fn commits_to_ui(commits: &[ReviewCommit]) -> Vec<SharedString> {
+    std::iter::once(SharedString::from("All commits"))
+        .chain(
+            commits
+                .iter()
+                .enumerate()
+                .map(|(index, commit)| SharedString::from(format!("{}/{}: {} {}", index + 1, commits.len(), commit.hash, commit.message))),
+        )
+        .collect()
+}
+
 pub fn report_error(app_window: &ui::AppWindow, error: ui::SlintResult, detail_text: SharedString) {
     let model_rc = app_window.global::<ui::SlintErrors>().get_model();
     let model = cast_model!(model_rc, VecModel<ui::SlintErrorEntry>);
@@ -48,3 +136,7 @@ pub fn report_error(app_window: &ui::AppWindow, error: ui::SlintResult, detail_t
     });
     app_window.invoke_request_show_error();
 }
+
+pub fn extension_from_filename(filename: &str) -> Option<&str> {
+    Path::new(filename).extension().and_then(OsStr::to_str)
+}
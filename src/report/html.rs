@@ -0,0 +1,418 @@
+use std::collections::BTreeMap;
+use std::fmt::Write;
+
+use crate::git_utils::{ChangeType, DiffLine, DiffLineKind, FileHunks};
+use crate::report::{CommitReport, FileReport, ReviewReport, change_type_name};
+use crate::storage::repository_storage::{DiffMode, DiffSide, NoteStore};
+
+const STYLE: &str = r#"
+body { font-family: sans-serif; margin: 2em; color: #222; }
+code, .diff { font-family: monospace; }
+table { border-collapse: collapse; }
+th, td { border: 1px solid #ccc; padding: 4px 8px; text-align: left; vertical-align: top; }
+.icon svg { width: 16px; height: 16px; vertical-align: middle; }
+.progress { display: flex; align-items: center; gap: 1em; margin: 4px 0; }
+.progress .bar { width: 300px; height: 12px; background: #e8e8e8; border-radius: 2px; }
+.progress .bar div { height: 100%; background: #5e8262; border-radius: 2px; }
+details { margin: 8px 0; border: 1px solid #ccc; }
+summary { padding: 4px 8px; cursor: pointer; background: #f4f4f4; }
+.diff { width: 100%; }
+.diff td { border: none; padding: 0 8px; white-space: pre-wrap; }
+.diff .line { color: #767373; text-align: right; width: 4em; user-select: none; }
+.diff .added { background: #dff0e0; }
+.diff .removed { background: #f6dcdc; }
+.diff .hunk td { background: #eef; color: #767373; }
+.note { margin: 4px 8px; padding: 4px 8px; border-left: 3px solid #b08a00; background: #fff8e0; font-family: sans-serif; white-space: normal; }
+.note.done { border-left-color: #5e8262; background: #eef6ee; }
+.muted { color: #767373; }
+"#;
+
+const SCRIPT: &str = r#"
+function filterNotes() {
+    const text = document.getElementById('note-filter').value.toLowerCase();
+    const state = document.getElementById('note-state').value;
+    for (const row of document.querySelectorAll('#notes tbody tr')) {
+        const visible = (state === 'all' || row.dataset.state === state) && row.textContent.toLowerCase().includes(text);
+        row.style.display = visible ? '' : 'none';
+    }
+}
+document.getElementById('note-filter').addEventListener('input', filterNotes);
+document.getElementById('note-state').addEventListener('change', filterNotes);
+"#;
+
+/// Renders the review as a single HTML file without external assets.
+pub fn render_html(report: &ReviewReport, file_hunks: &BTreeMap<String, FileHunks>) -> String {
+    let mut html = String::new();
+    // Writing into a String can't fail.
+    let _ = write_report(&mut html, report, file_hunks);
+    html
+}
+
+fn write_report(html: &mut String, report: &ReviewReport, file_hunks: &BTreeMap<String, FileHunks>) -> std::fmt::Result {
+    let title = format!("Review report: {}", escape(&report.review_name));
+    writeln!(html, "<!DOCTYPE html>")?;
+    writeln!(html, "<html lang=\"en\">")?;
+    writeln!(html, "<head>")?;
+    writeln!(html, "<meta charset=\"utf-8\">")?;
+    writeln!(html, "<title>{}</title>", title)?;
+    writeln!(html, "<style>{}</style>", STYLE)?;
+    writeln!(html, "</head>")?;
+    writeln!(html, "<body>")?;
+    writeln!(html, "<h1>{}</h1>", title)?;
+    writeln!(html, "<p>Repository: <code>{}</code></p>", escape(&report.repository_name))?;
+
+    writeln!(html, "<h2>Diff range</h2>")?;
+    if report.mode != DiffMode::Commits {
+        writeln!(html, "<p>Diff mode: {}</p>", report.mode)?;
+    }
+    writeln!(html, "<table>")?;
+    write_commit_row(html, "Start", &report.start)?;
+    write_commit_row(html, "End", &report.end)?;
+    writeln!(html, "</table>")?;
+
+    writeln!(html, "<h2>Progress</h2>")?;
+    write_progress(html, "Files reviewed", report.reviewed_file_count(), report.files.len())?;
+    write_progress(html, "Notes done", report.done_note_count(), report.notes.len())?;
+    writeln!(html, "<p>Lines: +{} / -{}</p>", report.added_lines(), report.removed_lines())?;
+
+    writeln!(html, "<h2>Files</h2>")?;
+    if report.files.is_empty() {
+        writeln!(html, "<p class=\"muted\">No changed files.</p>")?;
+    } else {
+        writeln!(html, "<table>")?;
+        writeln!(
+            html,
+            "<tr><th></th><th>File</th><th>Change</th><th>Added</th><th>Removed</th><th>Reviewed</th></tr>"
+        )?;
+        for (index, file) in report.files.iter().enumerate() {
+            writeln!(
+                html,
+                "<tr><td class=\"icon\">{}</td><td><a href=\"#file-{}\"><code>{}</code></a></td><td>{}</td><td>+{}</td><td>-{}</td><td>{}</td></tr>",
+                change_type_icon(&file.change_type),
+                index,
+                escape(&file.file_path),
+                change_type_name(&file.change_type),
+                file.added_lines,
+                file.removed_lines,
+                if file.is_reviewed { "yes" } else { "no" }
+            )?;
+        }
+        writeln!(html, "</table>")?;
+
+        writeln!(html, "<h2>Changes</h2>")?;
+        let (file_notes, _) = report.notes_by_file();
+        let no_hunks = FileHunks::default();
+        for (index, file) in report.files.iter().enumerate() {
+            let notes = file_notes.get(file.file_path.as_str()).map(Vec::as_slice).unwrap_or_default();
+            let hunks = file_hunks.get(&file.file_path).unwrap_or(&no_hunks);
+            write_file_diff(html, index, file, hunks, notes)?;
+        }
+    }
+
+    writeln!(html, "<h2>Notes</h2>")?;
+    writeln!(html, "<p><input id=\"note-filter\" placeholder=\"Filter notes...\"> ")?;
+    writeln!(
+        html,
+        "<select id=\"note-state\"><option value=\"all\">All</option><option value=\"open\">Open</option><option value=\"done\">Done</option></select></p>"
+    )?;
+    writeln!(html, "<table id=\"notes\">")?;
+    writeln!(html, "<thead><tr><th>State</th><th>Context</th><th>Lines</th><th>Note</th></tr></thead>")?;
+    writeln!(html, "<tbody>")?;
+    for note in &report.notes {
+        let state = if note.is_done { "done" } else { "open" };
+        let mut lines = note.line_range.map(|range| range.to_string()).unwrap_or_default();
+        if note.is_outdated {
+            lines.push_str(" (outdated)");
+        }
+        writeln!(
+            html,
+            "<tr data-state=\"{}\"><td>{}</td><td><code>{}</code></td><td>{}</td><td>{}</td></tr>",
+            state,
+            state,
+            escape(&note.context),
+            escape(&lines),
+            escape_multiline(&note.text)
+        )?;
+    }
+    writeln!(html, "</tbody>")?;
+    writeln!(html, "</table>")?;
+
+    writeln!(html, "<script>{}</script>", SCRIPT)?;
+    writeln!(html, "</body>")?;
+    writeln!(html, "</html>")
+}
+
+fn write_commit_row(html: &mut String, label: &str, commit: &CommitReport) -> std::fmt::Result {
+    if let Some(revision_name) = commit.revision_name() {
+        return writeln!(html, "<tr><th>{}</th><td colspan=\"2\">{}</td></tr>", label, revision_name);
+    }
+    let subject = commit.subject.as_deref().map(escape).unwrap_or_default();
+    writeln!(
+        html,
+        "<tr><th>{}</th><td><code>{}</code></td><td>{}</td></tr>",
+        label,
+        escape(&commit.hash),
+        subject
+    )
+}
+
+fn write_progress(html: &mut String, label: &str, completed: usize, total: usize) -> std::fmt::Result {
+    let percent = (completed * 100).checked_div(total).unwrap_or(0);
+    writeln!(
+        html,
+        "<div class=\"progress\"><span>{} {}/{}</span><div class=\"bar\"><div style=\"width: {}%\"></div></div></div>",
+        label, completed, total, percent
+    )
+}
+
+fn write_file_diff(html: &mut String, index: usize, file: &FileReport, file_hunks: &FileHunks, notes: &[&NoteStore]) -> std::fmt::Result {
+    let hunks = &file_hunks.hunks;
+    writeln!(html, "<details id=\"file-{}\">", index)?;
+    writeln!(
+        html,
+        "<summary><span class=\"icon\">{}</span> <code>{}</code> <span class=\"muted\">+{} -{}{}</span></summary>",
+        change_type_icon(&file.change_type),
+        escape(&file.file_path),
+        file.added_lines,
+        file.removed_lines,
+        if file.is_reviewed { ", reviewed" } else { "" }
+    )?;
+
+    let is_shown_inline = |note: &NoteStore| {
+        let Some(range) = note.line_range else {
+            return false;
+        };
+        hunks
+            .iter()
+            .flat_map(|hunk| &hunk.lines)
+            .any(|line| anchor_line(line, range.side) == Some(range.end))
+    };
+    for note in notes.iter().filter(|note| !is_shown_inline(note)) {
+        write_note(html, note)?;
+    }
+
+    if file_hunks.is_binary {
+        writeln!(html, "<p class=\"muted\">&nbsp;Binary file, its changes can't be shown as text.</p>")?;
+        return writeln!(html, "</details>");
+    }
+    if hunks.is_empty() {
+        writeln!(html, "<p class=\"muted\">&nbsp;No textual changes.</p>")?;
+        return writeln!(html, "</details>");
+    }
+
+    writeln!(html, "<table class=\"diff\">")?;
+    for hunk in hunks {
+        let header = &hunk.header;
+        writeln!(
+            html,
+            "<tr class=\"hunk\"><td colspan=\"3\">@@ -{},{} +{},{} @@</td></tr>",
+            header.old_start, header.old_count, header.new_start, header.new_count
+        )?;
+        for line in &hunk.lines {
+            let class = match line.kind {
+                DiffLineKind::Context => "",
+                DiffLineKind::Added => " class=\"added\"",
+                DiffLineKind::Removed => " class=\"removed\"",
+            };
+            let line_number = |number: Option<u32>| number.map(|n| n.to_string()).unwrap_or_default();
+            writeln!(
+                html,
+                "<tr{}><td class=\"line\">{}</td><td class=\"line\">{}</td><td>{}</td></tr>",
+                class,
+                line_number(line.old_line),
+                line_number(line.new_line),
+                escape(&line.text)
+            )?;
+
+            let inline_notes = notes
+                .iter()
+                .filter(|note| note.line_range.is_some_and(|range| anchor_line(line, range.side) == Some(range.end)));
+            for note in inline_notes {
+                write!(html, "<tr><td></td><td></td><td>")?;
+                write_note(html, note)?;
+                writeln!(html, "</td></tr>")?;
+            }
+        }
+    }
+    writeln!(html, "</table>")?;
+    writeln!(html, "</details>")
+}
+
+/// The line a note on the given side is shown below. Old side notes are not
+/// shown below added lines and new side notes not below removed lines.
+fn anchor_line(line: &DiffLine, side: DiffSide) -> Option<u32> {
+    match side {
+        DiffSide::Old => line.old_line,
+        DiffSide::New => line.new_line,
+    }
+}
+
+fn write_note(html: &mut String, note: &NoteStore) -> std::fmt::Result {
+    let class = if note.is_done { "note done" } else { "note" };
+    let state = if note.is_done { "[x]" } else { "[ ]" };
+    let mut location = note.line_range.map(|range| format!(" lines {}", range)).unwrap_or_default();
+    if note.is_outdated {
+        location.push_str(" (outdated)");
+    }
+    writeln!(
+        html,
+        "<div class=\"{}\">{}<span class=\"muted\">{}</span> {}</div>",
+        class,
+        state,
+        escape(&location),
+        escape_multiline(&note.text)
+    )
+}
+
+fn change_type_icon(change_type: &ChangeType) -> String {
+    let svg = match change_type {
+        ChangeType::Added => include_str!("../../assets/icons/git_file_added.svg"),
+        ChangeType::Broken => include_str!("../../assets/icons/git_file_broken.svg"),
+        ChangeType::Copied => include_str!("../../assets/icons/git_file_copied.svg"),
+        ChangeType::Deleted => include_str!("../../assets/icons/git_file_deleted.svg"),
+        ChangeType::Modified => include_str!("../../assets/icons/git_file_edited.svg"),
+        ChangeType::Renamed => include_str!("../../assets/icons/git_file_renamed.svg"),
+        ChangeType::TypChanged => include_str!("../../assets/icons/git_file_type_changed.svg"),
+        ChangeType::Unknown => include_str!("../../assets/icons/git_file_unknown.svg"),
+        ChangeType::Unmerged => include_str!("../../assets/icons/git_file_unmerged.svg"),
+        ChangeType::Invalid => include_str!("../../assets/icons/invalid.svg"),
+    };
+    // The icons are white since the UI colorizes them.
+    svg.trim().replace("fill=\"#fff\"", "fill=\"currentColor\"")
+}
+
+fn escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&#39;"),
+            _ => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+fn escape_multiline(text: &str) -> String {
+    text.lines().map(escape).collect::<Vec<_>>().join("<br>")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::git_utils::{DiffHunk, HunkHeader};
+    use crate::storage::repository_storage::{INDEX, LineRange};
+
+    #[test]
+    fn test_render_html() {
+        let report = ReviewReport {
+            repository_name: "review_helper".to_string(),
+            review_name: "<fancy>".to_string(),
+            start: CommitReport {
+                hash: "ed7811b".to_string(),
+                subject: None,
+            },
+            end: CommitReport {
+                hash: String::new(),
+                subject: None,
+            },
+            mode: DiffMode::Commits,
+            files: vec![
+                FileReport {
+                    file_path: "src/lib.rs".to_string(),
+                    change_type: ChangeType::Modified,
+                    added_lines: 1,
+                    removed_lines: 1,
+                    is_reviewed: false,
+                },
+                FileReport {
+                    file_path: "assets/logo.png".to_string(),
+                    change_type: ChangeType::Modified,
+                    added_lines: 0,
+                    removed_lines: 0,
+                    is_reviewed: false,
+                },
+            ],
+            notes: vec![
+                NoteStore {
+                    text: "use & instead".to_string(),
+                    context: "src/lib.rs".to_string(),
+                    line_range: Some(LineRange::new(DiffSide::New, 2, 2)),
+                    ..Default::default()
+                },
+                NoteStore {
+                    text: "general".to_string(),
+                    context: "src/lib.rs".to_string(),
+                    is_done: true,
+                    ..Default::default()
+                },
+            ],
+        };
+        let line = |kind, old_line, new_line, text: &str| DiffLine {
+            kind,
+            old_line,
+            new_line,
+            text: text.to_string(),
+        };
+        let hunks = BTreeMap::from([
+            (
+                "src/lib.rs".to_string(),
+                FileHunks {
+                    hunks: vec![DiffHunk {
+                        header: HunkHeader {
+                            old_start: 1,
+                            old_count: 2,
+                            new_start: 1,
+                            new_count: 2,
+                        },
+                        lines: vec![
+                            line(DiffLineKind::Context, Some(1), Some(1), "fn main() {"),
+                            line(DiffLineKind::Removed, Some(2), None, "    a && b"),
+                            line(DiffLineKind::Added, None, Some(2), "    a & b"),
+                        ],
+                    }],
+                    is_binary: false,
+                },
+            ),
+            (
+                "assets/logo.png".to_string(),
+                FileHunks {
+                    hunks: Vec::new(),
+                    is_binary: true,
+                },
+            ),
+        ]);
+
+        let html = render_html(&report, &hunks);
+
+        assert!(html.contains("<title>Review report: &lt;fancy&gt;</title>"));
+        assert!(html.contains("fill=\"currentColor\""));
+        assert!(!html.contains("src=\"http"));
+        assert!(html.contains("<span>Notes done 1/2</span><div class=\"bar\"><div style=\"width: 50%\"></div>"));
+        assert!(html.contains("<td>    a &amp;&amp; b</td>"));
+
+        let added_line = html.find("<td>    a &amp; b</td>").expect("added line");
+        let inline_note = html.find("use &amp; instead</div>").expect("inline note");
+        let general_note = html.find("general</div>").expect("general note");
+        assert!(general_note < added_line);
+        assert!(added_line < inline_note);
+        assert!(html.contains("<tr data-state=\"done\"><td>done</td><td><code>src/lib.rs</code></td><td></td><td>general</td></tr>"));
+        assert!(html.contains("<tr><th>End</th><td colspan=\"2\">working copy</td></tr>"));
+        assert!(html.contains("Binary file, its changes can't be shown as text."));
+
+        let unstaged_report = ReviewReport {
+            start: CommitReport {
+                hash: INDEX.to_string(),
+                subject: None,
+            },
+            mode: DiffMode::Unstaged,
+            ..report
+        };
+        let html = render_html(&unstaged_report, &hunks);
+        assert!(html.contains("<p>Diff mode: unstaged</p>"));
+        assert!(html.contains("<tr><th>Start</th><td colspan=\"2\">index</td></tr>"));
+    }
+}
@@ -0,0 +1,232 @@
+use std::fmt::Write;
+
+use crate::report::{CommitReport, ReviewReport, change_type_name};
+use crate::storage::repository_storage::{DiffMode, NoteStore};
+
+/// Renders the review as one Markdown document, e.g. for a merge request description.
+pub fn render_markdown(report: &ReviewReport) -> String {
+    let mut md = String::new();
+    // Writing into a String can't fail.
+    let _ = write_report(&mut md, report);
+    md
+}
+
+fn write_report(md: &mut String, report: &ReviewReport) -> std::fmt::Result {
+    writeln!(md, "# Review report: {}", report.review_name)?;
+    writeln!(md)?;
+    writeln!(md, "Repository: `{}`", report.repository_name)?;
+    writeln!(md)?;
+
+    writeln!(md, "## Diff range")?;
+    writeln!(md)?;
+    if report.mode != DiffMode::Commits {
+        writeln!(md, "Diff mode: {}", report.mode)?;
+        writeln!(md)?;
+    }
+    writeln!(md, "| | Commit | Subject |")?;
+    writeln!(md, "|---|---|---|")?;
+    write_commit_row(md, "Start", &report.start)?;
+    write_commit_row(md, "End", &report.end)?;
+    writeln!(md)?;
+
+    writeln!(md, "## Statistics")?;
+    writeln!(md)?;
+    writeln!(md, "- Files reviewed: {}/{}", report.reviewed_file_count(), report.files.len())?;
+    writeln!(md, "- Notes done: {}/{}", report.done_note_count(), report.notes.len())?;
+    writeln!(md, "- Lines: +{} / -{}", report.added_lines(), report.removed_lines())?;
+    writeln!(md)?;
+
+    let change_type_counts = report.change_type_counts();
+    if !change_type_counts.is_empty() {
+        writeln!(md, "| Change type | Files |")?;
+        writeln!(md, "|---|---:|")?;
+        for (change_type, count) in change_type_counts {
+            writeln!(md, "| {} | {} |", change_type_name(&change_type), count)?;
+        }
+        writeln!(md)?;
+    }
+
+    writeln!(md, "## Files")?;
+    writeln!(md)?;
+    if report.files.is_empty() {
+        writeln!(md, "No changed files.")?;
+    } else {
+        writeln!(md, "| Reviewed | File | Change | Added | Removed |")?;
+        writeln!(md, "|:---:|---|---|---:|---:|")?;
+        for file in &report.files {
+            writeln!(
+                md,
+                "| {} | `{}` | {} | +{} | -{} |",
+                if file.is_reviewed { "yes" } else { "no" },
+                escape_table_cell(&file.file_path),
+                change_type_name(&file.change_type),
+                file.added_lines,
+                file.removed_lines
+            )?;
+        }
+    }
+    writeln!(md)?;
+
+    writeln!(md, "## Notes")?;
+    if report.notes.is_empty() {
+        writeln!(md)?;
+        writeln!(md, "No notes.")?;
+    }
+    let (file_notes, other_notes) = report.notes_by_file();
+    for (file, notes) in file_notes {
+        writeln!(md)?;
+        writeln!(md, "### `{}`", file)?;
+        writeln!(md)?;
+        for note in notes {
+            write_note(md, note, false)?;
+        }
+    }
+    if !other_notes.is_empty() {
+        writeln!(md)?;
+        writeln!(md, "### General")?;
+        writeln!(md)?;
+        for note in other_notes {
+            write_note(md, note, true)?;
+        }
+    }
+    Ok(())
+}
+
+fn write_commit_row(md: &mut String, label: &str, commit: &CommitReport) -> std::fmt::Result {
+    if let Some(revision_name) = commit.revision_name() {
+        return writeln!(md, "| {} | {} | |", label, revision_name);
+    }
+    let subject = commit.subject.as_deref().map(escape_table_cell).unwrap_or_default();
+    writeln!(md, "| {} | `{}` | {} |", label, commit.hash, subject)
+}
+
+fn write_note(md: &mut String, note: &NoteStore, with_context: bool) -> std::fmt::Result {
+    write!(md, "- [{}] ", if note.is_done { "x" } else { " " })?;
+    if with_context && !note.context.is_empty() {
+        write!(md, "*{}* ", note.context)?;
+    }
+    if let Some(line_range) = note.line_range {
+        write!(md, "`{}` ", line_range)?;
+    }
+    if note.is_outdated {
+        write!(md, "(outdated) ")?;
+    }
+    // Continuation lines of multi-line notes stay inside the list item.
+    writeln!(md, "{}", note.text.lines().collect::<Vec<_>>().join("\n  "))
+}
+
+fn escape_table_cell(text: &str) -> String {
+    text.replace('|', "\\|")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::git_utils::ChangeType;
+    use crate::report::FileReport;
+    use crate::storage::repository_storage::{DiffSide, INDEX, LineRange};
+
+    #[test]
+    fn test_render_markdown() {
+        let report = ReviewReport {
+            repository_name: "review_helper".to_string(),
+            review_name: "fancy_ui".to_string(),
+            start: CommitReport {
+                hash: "ed7811b".to_string(),
+                subject: Some("Add | pipes".to_string()),
+            },
+            end: CommitReport {
+                hash: String::new(),
+                subject: None,
+            },
+            mode: DiffMode::Commits,
+            files: vec![
+                FileReport {
+                    file_path: "src/lib.rs".to_string(),
+                    change_type: ChangeType::Modified,
+                    added_lines: 10,
+                    removed_lines: 2,
+                    is_reviewed: true,
+                },
+                FileReport {
+                    file_path: "src/new.rs".to_string(),
+                    change_type: ChangeType::Added,
+                    added_lines: 5,
+                    removed_lines: 0,
+                    is_reviewed: false,
+                },
+            ],
+            notes: vec![
+                NoteStore {
+                    text: "off-by-one".to_string(),
+                    context: "src/lib.rs".to_string(),
+                    line_range: Some(LineRange::new(DiffSide::Old, 3, 4)),
+                    ..Default::default()
+                },
+                NoteStore {
+                    text: "update docs".to_string(),
+                    context: "README".to_string(),
+                    is_done: true,
+                    ..Default::default()
+                },
+            ],
+        };
+
+        let expected = [
+            "# Review report: fancy_ui",
+            "",
+            "Repository: `review_helper`",
+            "",
+            "## Diff range",
+            "",
+            "| | Commit | Subject |",
+            "|---|---|---|",
+            "| Start | `ed7811b` | Add \\| pipes |",
+            "| End | working copy | |",
+            "",
+            "## Statistics",
+            "",
+            "- Files reviewed: 1/2",
+            "- Notes done: 1/2",
+            "- Lines: +15 / -2",
+            "",
+            "| Change type | Files |",
+            "|---|---:|",
+            "| Added | 1 |",
+            "| Modified | 1 |",
+            "",
+            "## Files",
+            "",
+            "| Reviewed | File | Change | Added | Removed |",
+            "|:---:|---|---|---:|---:|",
+            "| yes | `src/lib.rs` | Modified | +10 | -2 |",
+            "| no | `src/new.rs` | Added | +5 | -0 |",
+            "",
+            "## Notes",
+            "",
+            "### `src/lib.rs`",
+            "",
+            "- [ ] `old:3-4` off-by-one",
+            "",
+            "### General",
+            "",
+            "- [x] *README* update docs",
+            "",
+        ]
+        .join("\n");
+
+        assert_eq!(render_markdown(&report), expected);
+
+        let staged_report = ReviewReport {
+            end: CommitReport {
+                hash: INDEX.to_string(),
+                subject: None,
+            },
+            mode: DiffMode::Staged,
+            ..report
+        };
+        let markdown = render_markdown(&staged_report);
+        assert!(markdown.contains("## Diff range\n\nDiff mode: staged\n\n| | Commit | Subject |\n"));
+        assert!(markdown.contains("| End | index | |\n"));
+    }
+}
@@ -0,0 +1,209 @@
+use std::collections::BTreeMap;
+use std::path::Path;
+
+use itertools::Itertools;
+
+use crate::content_diff;
+use crate::git_utils::{self, ChangeType, Commit, FileDiffMap, FileHunks};
+use crate::repositories::{Repository, Review};
+use crate::review_json;
+use crate::storage::repository_storage::{DiffMode, INDEX, NoteStore};
+
+pub mod html;
+pub mod markdown;
+pub mod sarif;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum ReportFormat {
+    Markdown,
+    Html,
+    Json,
+    Sarif,
+}
+
+impl ReportFormat {
+    pub fn from_path(path: &Path) -> Self {
+        match path.extension().and_then(|e| e.to_str()).map(|e| e.to_lowercase()).as_deref() {
+            Some("html" | "htm") => ReportFormat::Html,
+            Some("json") => ReportFormat::Json,
+            Some("sarif") => ReportFormat::Sarif,
+            _ => ReportFormat::Markdown,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct CommitReport {
+    /// Empty for the working copy and `INDEX` for the index.
+    pub hash: String,
+    pub subject: Option<String>,
+}
+
+impl CommitReport {
+    /// The name of a compared side that isn't a commit.
+    pub fn revision_name(&self) -> Option<&'static str> {
+        match self.hash.as_str() {
+            "" => Some("working copy"),
+            INDEX => Some("index"),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct FileReport {
+    pub file_path: String,
+    pub change_type: ChangeType,
+    pub added_lines: u32,
+    pub removed_lines: u32,
+    pub is_reviewed: bool,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ReviewReport {
+    pub repository_name: String,
+    pub review_name: String,
+    /// The compared sides of the diff range, which depend on `mode`.
+    pub start: CommitReport,
+    pub end: CommitReport,
+    pub mode: DiffMode,
+    /// Sorted by file path.
+    pub files: Vec<FileReport>,
+    /// In the order the notes were created.
+    pub notes: Vec<NoteStore>,
+}
+
+impl ReviewReport {
+    pub fn new(repository_name: &str, review: &Review, commits: &[Commit], mut file_diff_map: FileDiffMap) -> Self {
+        let commit_report = |hash: &str| CommitReport {
+            hash: hash.to_string(),
+            subject: find_commit(commits, hash).map(|commit| commit.message.clone()),
+        };
+
+        let files = review
+            .file_diffs
+            .stores()
+            .into_iter()
+            .map(|store| {
+                let file_path = store.file_path.to_string_lossy().to_string();
+                let status = file_diff_map.remove(&file_path);
+                FileReport {
+                    change_type: status.as_ref().map_or(ChangeType::Invalid, |s| s.change_type.clone()),
+                    added_lines: status.as_ref().map_or(0, |s| s.added_lines),
+                    removed_lines: status.as_ref().map_or(0, |s| s.removed_lines),
+                    is_reviewed: store.is_reviewed,
+                    file_path,
+                }
+            })
+            .sorted_by(|lhs, rhs| lhs.file_path.cmp(&rhs.file_path))
+            .collect();
+
+        let notes = review
+            .notes
+            .iter()
+            .sorted_by_key(|(id, _)| (*id).clone())
+            .map(|(_, note)| note.clone())
+            .collect();
+
+        let (start, end) = review.diff_range().revisions();
+        Self {
+            repository_name: repository_name.to_string(),
+            review_name: review.name().as_str().to_string(),
+            start: commit_report(start),
+            end: commit_report(end),
+            mode: review.diff_range().mode,
+            files,
+            notes,
+        }
+    }
+
+    pub fn added_lines(&self) -> u32 {
+        self.files.iter().map(|f| f.added_lines).sum()
+    }
+    pub fn removed_lines(&self) -> u32 {
+        self.files.iter().map(|f| f.removed_lines).sum()
+    }
+    pub fn reviewed_file_count(&self) -> usize {
+        self.files.iter().filter(|f| f.is_reviewed).count()
+    }
+    pub fn done_note_count(&self) -> usize {
+        self.notes.iter().filter(|n| n.is_done).count()
+    }
+    pub fn change_type_counts(&self) -> BTreeMap<ChangeType, usize> {
+        self.files.iter().fold(BTreeMap::new(), |mut counts, f| {
+            *counts.entry(f.change_type.clone()).or_default() += 1;
+            counts
+        })
+    }
+
+    /// Notes that refer to a file of the review, grouped by file path, and
+    /// the remaining notes.
+    pub fn notes_by_file(&self) -> (BTreeMap<&str, Vec<&NoteStore>>, Vec<&NoteStore>) {
+        let mut file_notes: BTreeMap<&str, Vec<&NoteStore>> = BTreeMap::new();
+        let mut other_notes = Vec::new();
+        for note in &self.notes {
+            if self.files.iter().any(|f| f.file_path == note.context) {
+                file_notes.entry(note.context.as_str()).or_default().push(note);
+            } else {
+                other_notes.push(note);
+            }
+        }
+        (file_notes, other_notes)
+    }
+}
+
+/// Commit hashes of a review may be abbreviated differently than the ones of `git log`.
+fn find_commit<'a>(commits: &'a [Commit], hash: &str) -> Option<&'a Commit> {
+    if hash.is_empty() || hash == INDEX {
+        return None;
+    }
+    commits.iter().find(|commit| commit.hash.starts_with(hash) || hash.starts_with(&commit.hash))
+}
+
+pub fn change_type_name(change_type: &ChangeType) -> &'static str {
+    match change_type {
+        ChangeType::Invalid => "Invalid",
+        ChangeType::Added => "Added",
+        ChangeType::Copied => "Copied",
+        ChangeType::Deleted => "Deleted",
+        ChangeType::Modified => "Modified",
+        ChangeType::Renamed => "Renamed",
+        ChangeType::TypChanged => "Type changed",
+        ChangeType::Unmerged => "Unmerged",
+        ChangeType::Unknown => "Unknown",
+        ChangeType::Broken => "Broken",
+    }
+}
+
+/// Queries git for the commits and file statistics of the review.
+pub fn create_review_report(repository: &Repository, review: &Review) -> anyhow::Result<ReviewReport> {
+    let commits = git_utils::query_commits(repository.path())?;
+    let (start, end) = review.diff_range().revisions();
+    let file_diff_map = git_utils::diff_git_repo(repository.path(), start, end)?;
+    Ok(ReviewReport::new(repository.name.as_str(), review, &commits, file_diff_map))
+}
+
+fn query_file_hunks(repository: &Repository, review: &Review, review_report: &ReviewReport) -> anyhow::Result<BTreeMap<String, FileHunks>> {
+    let (start, end) = review.diff_range().revisions();
+    review_report
+        .files
+        .iter()
+        .map(|file| {
+            let hunks = git_utils::diff_file_hunks(repository.path(), start, end, &file.file_path, content_diff::CONTEXT_LINES)?;
+            Ok((file.file_path.clone(), hunks))
+        })
+        .collect()
+}
+
+pub fn render_report(repository: &Repository, review: &Review, format: ReportFormat) -> anyhow::Result<String> {
+    match format {
+        ReportFormat::Markdown => Ok(markdown::render_markdown(&create_review_report(repository, review)?)),
+        ReportFormat::Html => {
+            let review_report = create_review_report(repository, review)?;
+            let file_hunks = query_file_hunks(repository, review, &review_report)?;
+            Ok(html::render_html(&review_report, &file_hunks))
+        }
+        ReportFormat::Json => review_json::export_review_json(repository, review),
+        ReportFormat::Sarif => Ok(sarif::render_sarif(&create_review_report(repository, review)?)),
+    }
+}
@@ -0,0 +1,144 @@
+use serde_json::{Value, json};
+
+use crate::report::ReviewReport;
+use crate::storage::repository_storage::{DiffSide, NoteStore};
+
+const SARIF_SCHEMA: &str = "https://json.schemastore.org/sarif-2.1.0.json";
+const RULE_ID: &str = "review-note";
+
+/// Renders the open notes of the review as SARIF 2.1.0 log, so that CI dashboards
+/// and IDEs show them next to linter results.
+pub fn render_sarif(report: &ReviewReport) -> String {
+    let results = report
+        .notes
+        .iter()
+        .filter(|note| !note.is_done)
+        .map(|note| result(report, note))
+        .collect::<Vec<_>>();
+
+    let log = json!({
+        "$schema": SARIF_SCHEMA,
+        "version": "2.1.0",
+        "runs": [{
+            "tool": {
+                "driver": {
+                    "name": "review_helper",
+                    "version": env!("CARGO_PKG_VERSION"),
+                    "rules": [{
+                        "id": RULE_ID,
+                        "name": "ReviewNote",
+                        "shortDescription": { "text": "Open review note" },
+                        "defaultConfiguration": { "level": "warning" },
+                    }],
+                },
+            },
+            "automationDetails": { "id": format!("review_helper/{}/{}/", report.repository_name, report.review_name) },
+            "results": results,
+        }],
+    });
+    // Serializing a Value can't fail.
+    serde_json::to_string_pretty(&log).unwrap_or_default()
+}
+
+fn result(report: &ReviewReport, note: &NoteStore) -> Value {
+    let mut result = json!({
+        "ruleId": RULE_ID,
+        "level": "warning",
+        "message": { "text": note.text },
+        "properties": { "context": note.context, "isOutdated": note.is_outdated },
+    });
+
+    // Only contexts naming a file of the review are locations, others are free text.
+    if report.files.iter().any(|file| file.file_path == note.context) {
+        let mut physical_location = json!({
+            "artifactLocation": { "uri": note.context, "uriBaseId": "SRCROOT" },
+        });
+        match note.line_range {
+            // Lines of the old side don't exist in the reviewed version of the file.
+            Some(line_range) if line_range.side == DiffSide::New => {
+                physical_location["region"] = json!({ "startLine": line_range.start, "endLine": line_range.end });
+            }
+            Some(line_range) => {
+                result["properties"]["lineRange"] = json!(line_range.to_string());
+            }
+            None => {}
+        }
+        result["locations"] = json!([{ "physicalLocation": physical_location }]);
+    }
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::git_utils::ChangeType;
+    use crate::report::{CommitReport, FileReport};
+    use crate::storage::repository_storage::{DiffMode, LineRange};
+
+    #[test]
+    fn test_render_sarif() -> anyhow::Result<()> {
+        let report = ReviewReport {
+            repository_name: "review_helper".to_string(),
+            review_name: "fancy_ui".to_string(),
+            start: CommitReport {
+                hash: "ed7811b".to_string(),
+                subject: None,
+            },
+            end: CommitReport {
+                hash: String::new(),
+                subject: None,
+            },
+            mode: DiffMode::Commits,
+            files: vec![FileReport {
+                file_path: "src/lib.rs".to_string(),
+                change_type: ChangeType::Modified,
+                added_lines: 10,
+                removed_lines: 2,
+                is_reviewed: true,
+            }],
+            notes: vec![
+                NoteStore {
+                    text: "off-by-one".to_string(),
+                    context: "src/lib.rs".to_string(),
+                    line_range: Some(LineRange::new(DiffSide::New, 3, 4)),
+                    ..Default::default()
+                },
+                NoteStore {
+                    text: "removed check".to_string(),
+                    context: "src/lib.rs".to_string(),
+                    line_range: Some(LineRange::new(DiffSide::Old, 7, 7)),
+                    ..Default::default()
+                },
+                NoteStore {
+                    text: "update docs".to_string(),
+                    context: "README".to_string(),
+                    ..Default::default()
+                },
+                NoteStore {
+                    text: "done".to_string(),
+                    is_done: true,
+                    ..Default::default()
+                },
+            ],
+        };
+
+        let log: Value = serde_json::from_str(&render_sarif(&report))?;
+        assert_eq!(log["version"], "2.1.0");
+
+        let results = log["runs"][0]["results"].as_array().expect("results");
+        assert_eq!(results.len(), 3);
+
+        let location = &results[0]["locations"][0]["physicalLocation"];
+        assert_eq!(results[0]["message"]["text"], "off-by-one");
+        assert_eq!(location["artifactLocation"]["uri"], "src/lib.rs");
+        assert_eq!(location["region"]["startLine"], 3);
+        assert_eq!(location["region"]["endLine"], 4);
+
+        assert!(results[1]["locations"][0]["physicalLocation"]["region"].is_null());
+        assert_eq!(results[1]["properties"]["lineRange"], "old:7");
+
+        assert!(results[2]["locations"].is_null());
+        assert_eq!(results[2]["properties"]["context"], "README");
+        Ok(())
+    }
+}
@@ -8,7 +8,7 @@ use std::{
 
 use crate::storage::{
     RepositoryName, RepositoryStore,
-    repository_storage::{DiffRangeStore, FileDiffStore, NoteStore, ReviewName, ReviewStore},
+    repository_storage::{DiffRangeStore, DiffSide, FileDiffStore, LineRange, NoteStore, ReviewName, ReviewStore},
 };
 
 macro_rules! create_id {
@@ -85,14 +85,58 @@ impl Notes {
         self.last_note_id.increment();
         self.last_note_id.clone()
     }
+    pub fn get(&self, id: &NoteId) -> Option<&NoteStore> {
+        self.id_note_map.get(id)
+    }
     pub fn get_mut(&mut self, id: &NoteId) -> Option<&mut NoteStore> {
         self.id_note_map.get_mut(id)
     }
+    pub fn anchored_files(&self, side: DiffSide) -> HashSet<String> {
+        self.id_note_map
+            .values()
+            .filter(|note| !note.is_outdated && note.line_range.is_some_and(|line_range| line_range.side == side))
+            .map(|note| note.context.clone())
+            .collect()
+    }
+    pub fn relocate_line_ranges(&mut self, file: &str, side: DiffSide, map_line: impl Fn(u32) -> Option<u32>) -> Vec<NoteId> {
+        let mut relocated_note_ids = Vec::new();
+        for (id, note) in self.id_note_map.iter_mut() {
+            if note.is_outdated || note.context != file {
+                continue;
+            }
+            let Some(line_range) = note.line_range.filter(|line_range| line_range.side == side) else {
+                continue;
+            };
+            let mapped_lines = (line_range.start..=line_range.end).map(&map_line).collect::<Option<Vec<_>>>();
+            match mapped_lines {
+                Some(lines) => {
+                    let new_line_range = LineRange::new(side, lines[0], lines[lines.len() - 1]);
+                    if new_line_range == line_range {
+                        continue;
+                    }
+                    note.line_range = Some(new_line_range);
+                }
+                None => note.is_outdated = true,
+            }
+            relocated_note_ids.push(id.clone());
+        }
+        relocated_note_ids
+    }
     pub fn delete_note(&mut self, id: &NoteId) -> Option<NoteStore> {
         self.id_note_map.remove(id)
     }
-    pub fn add_note(&mut self, text: String, context: String) -> NoteId {
-        let store = NoteStore { text, context, is_done: false };
+    /// Adds a deleted note again under its previous id, ids of new notes are never reused.
+    pub fn restore_note(&mut self, id: NoteId, store: NoteStore) {
+        self.id_note_map.insert(id, store);
+    }
+    pub fn add_note(&mut self, text: String, context: String, line_range: Option<LineRange>) -> NoteId {
+        let store = NoteStore {
+            text,
+            context,
+            line_range,
+            is_outdated: false,
+            is_done: false,
+        };
         let id = self.allocate_note_id();
         self.id_note_map.insert(id.clone(), store);
         id
@@ -127,10 +171,75 @@ impl FileDiffs {
     pub fn get(&self, file_diff_id: &FileDiffId) -> Option<&FileDiffStore> {
         self.id_store_map.get(file_diff_id)
     }
-    pub fn set_is_reviewed(&mut self, file_diff_id: &FileDiffId, is_reviewed: bool) {
+    /// Replaces the file diffs by an earlier state, the ids allocated since then stay unused.
+    pub fn restore(&mut self, file_diffs: FileDiffs) {
+        let last_file_diff_id = self.last_file_diff_id.clone().max(file_diffs.last_file_diff_id.clone());
+        *self = file_diffs;
+        self.last_file_diff_id = last_file_diff_id;
+    }
+    /// A reviewed file keeps the blob it was reviewed at, marking a file clears its changed flag.
+    pub fn set_is_reviewed(&mut self, file_diff_id: &FileDiffId, is_reviewed: bool, reviewed_blob: Option<String>) {
         if let Some(file_diff) = self.id_store_map.get_mut(file_diff_id) {
             file_diff.is_reviewed = is_reviewed;
+            file_diff.reviewed_blob = if is_reviewed { reviewed_blob } else { None };
+            file_diff.is_changed_since_review = false;
+        }
+    }
+    /// Marks a file as reviewed in one commit of the commit-by-commit mode only.
+    pub fn set_is_reviewed_in_commit(&mut self, file_diff_id: &FileDiffId, commit: &str, is_reviewed: bool) {
+        if let Some(file_diff) = self.id_store_map.get_mut(file_diff_id) {
+            file_diff.reviewed_commits.retain(|reviewed_commit| reviewed_commit != commit);
+            if is_reviewed {
+                file_diff.reviewed_commits.push(commit.to_string());
+            }
+        }
+    }
+    pub fn is_reviewed_in_commit(&self, file: &str, commit: &str) -> bool {
+        self.file_id_map
+            .get(file)
+            .and_then(|id| self.id_store_map.get(id))
+            .is_some_and(|store| store.reviewed_commits.iter().any(|reviewed_commit| reviewed_commit == commit))
+    }
+    /// The files that were marked as reviewed at a known blob.
+    pub fn reviewed_files_with_blob(&self) -> Vec<String> {
+        self.file_id_map
+            .iter()
+            .filter(|(_, id)| {
+                self.id_store_map
+                    .get(*id)
+                    .is_some_and(|store| store.is_reviewed && store.reviewed_blob.is_some())
+            })
+            .map(|(file, _)| file.clone())
+            .collect()
+    }
+    /// Keeps a reviewed file reviewed at another blob, e.g. of its rebased version.
+    pub fn move_reviewed_blob(&mut self, file: &str, reviewed_blob: String) {
+        let Some(file_diff) = self.file_id_map.get(file).and_then(|id| self.id_store_map.get_mut(id)) else {
+            return;
+        };
+        if file_diff.is_reviewed {
+            file_diff.reviewed_blob = Some(reviewed_blob);
+        }
+    }
+    /// Resets the reviewed files whose blob isn't the one of `blob_hashes` anymore, deleted files
+    /// have none. Returns the ids of the reset files.
+    pub fn reset_changed_files(&mut self, blob_hashes: &HashMap<String, String>) -> Vec<FileDiffId> {
+        let mut changed_file_diff_ids = Vec::new();
+        for (file, id) in &self.file_id_map {
+            let Some(store) = self.id_store_map.get_mut(id) else {
+                continue;
+            };
+            let Some(reviewed_blob) = store.reviewed_blob.as_ref().filter(|_| store.is_reviewed) else {
+                continue;
+            };
+            if blob_hashes.get(file) != Some(reviewed_blob) {
+                store.is_reviewed = false;
+                store.reviewed_blob = None;
+                store.is_changed_since_review = true;
+                changed_file_diff_ids.push(id.clone());
+            }
         }
+        changed_file_diff_ids
     }
     pub fn update_file_diffs(&mut self, new_file_keys: HashSet<String>) -> (Vec<FileDiffId>, Vec<String>) {
         let old_file_keys = self.file_id_map.keys().cloned().collect::<HashSet<_>>();
@@ -176,7 +285,7 @@ impl FileDiffs {
             id.clone(),
             FileDiffStore {
                 file_path: PathBuf::from(&file),
-                is_reviewed: false,
+                ..Default::default()
             },
         );
 
@@ -189,10 +298,21 @@ impl FileDiffs {
     }
 }
 
+/// A commit of the diff range with the files it changes, reviewed one at a time in the
+/// commit-by-commit mode.
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct ReviewCommit {
+    pub hash: String,
+    pub message: String,
+    pub files: Vec<String>,
+}
+
 #[derive(Default, Clone)]
 pub struct Review {
     name: ReviewName,
     diff_range: DiffRangeStore,
+    commits: Vec<ReviewCommit>,
+    current_commit: Option<usize>,
     pub notes: Notes,
     pub file_diffs: FileDiffs,
 }
@@ -217,10 +337,65 @@ impl Review {
     }
     pub fn set_diff_range(&mut self, new_diff_range: DiffRangeStore) {
         self.diff_range = new_diff_range;
+        self.set_commits(Vec::new());
+    }
+    /// The commits of the diff range, oldest first. They aren't stored but queried from git.
+    pub fn commits(&self) -> &[ReviewCommit] {
+        &self.commits
+    }
+    /// Replaces the commits of the diff range and leaves the commit-by-commit mode.
+    pub fn set_commits(&mut self, commits: Vec<ReviewCommit>) {
+        self.commits = commits;
+        self.current_commit = None;
+    }
+    pub fn current_commit(&self) -> Option<&ReviewCommit> {
+        self.current_commit.and_then(|index| self.commits.get(index))
+    }
+    pub fn current_commit_index(&self) -> Option<usize> {
+        self.current_commit
+    }
+    /// Shows a single commit of the diff range, `None` or an unknown index show the whole range.
+    pub fn select_commit(&mut self, commit_index: Option<usize>) {
+        self.current_commit = commit_index.filter(|index| *index < self.commits.len());
+    }
+    /// The commits the shown files are compared between, the parent and the commit itself in
+    /// the commit-by-commit mode.
+    pub fn compared_commits(&self) -> (String, String) {
+        match self.current_commit() {
+            Some(commit) => (format!("{}^", commit.hash), commit.hash.clone()),
+            None => {
+                let (start, end) = self.diff_range.compared_commits();
+                (start.to_string(), end.to_string())
+            }
+        }
+    }
+    /// The reviewed and total number of files over all commits of the diff range, a file counts
+    /// once for every commit that changes it.
+    pub fn commit_progress(&self) -> (usize, usize) {
+        self.commits
+            .iter()
+            .flat_map(|commit| commit.files.iter().map(move |file| (commit, file)))
+            .filter(|(_, file)| self.file_diffs.file_id_map.contains_key(*file))
+            .fold((0, 0), |(completed, total), (commit, file)| {
+                let is_reviewed = self.file_diffs.is_reviewed_in_commit(file, &commit.hash);
+                (completed + usize::from(is_reviewed), total + 1)
+            })
     }
     pub fn rename(&mut self, new_review_name: ReviewName) {
         self.name = new_review_name;
     }
+    /// The stored state of the review, notes in the order they were added and file diffs by path.
+    pub fn store(&self) -> ReviewStore {
+        let mut notes = self.notes.iter().collect::<Vec<_>>();
+        notes.sort_by_key(|(id, _)| id.as_usize());
+        let mut file_diff_list = self.file_diffs.stores().into_iter().cloned().collect::<Vec<_>>();
+        file_diff_list.sort_by(|lhs, rhs| lhs.file_path.cmp(&rhs.file_path));
+        ReviewStore {
+            diff_range: self.diff_range.clone(),
+            file_diff_list,
+            notes: notes.into_iter().map(|(_, note)| note.clone()).collect(),
+        }
+    }
 }
 
 #[derive(Default, Clone)]
@@ -244,12 +419,22 @@ impl Reviews {
     pub fn review_name(&self, id: &ReviewId) -> Option<&ReviewName> {
         self.id_review_name_map.get(id)
     }
+    pub fn review_id(&self, name: &ReviewName) -> Option<ReviewId> {
+        self.id_review_name_map
+            .iter()
+            .find(|(_, review_name)| *review_name == name)
+            .map(|(id, _)| id.clone())
+    }
     pub fn has_review_name(&self, name: &ReviewName) -> bool {
         self.review_name_set.contains(name)
     }
     pub fn get(&self, id: &ReviewId) -> Option<&Review> {
         self.id_review_map.get(id)
     }
+    /// Iterates over the loaded reviews only.
+    pub fn iter(&self) -> hash_map::Iter<'_, ReviewId, Review> {
+        self.id_review_map.iter()
+    }
     pub fn register_review_name(&mut self, review_name: ReviewName) -> ReviewId {
         let id = self.allocate_review_id();
         self.id_review_name_map.insert(id.clone(), review_name.clone());
@@ -351,6 +536,12 @@ impl Repositories {
     pub fn iter(&self) -> hash_map::Iter<'_, RepositoryId, Repository> {
         self.id_repository_map.iter()
     }
+    pub fn repository_id(&self, name: &RepositoryName) -> Option<RepositoryId> {
+        self.id_repository_map
+            .iter()
+            .find(|(_, repository)| repository.name == *name)
+            .map(|(id, _)| id.clone())
+    }
     pub fn contains_repository_path(&self, path: &Path) -> bool {
         self.repository_path_set.contains(path)
     }
@@ -0,0 +1,529 @@
+//! Versioned JSON representation of a complete review.
+//!
+//! Version 2 looks like this, `status` is `null` for files that are not part
+//! of the diff range anymore and `line_range` is `null` for notes without
+//! line anchor:
+//!
+//! ```json
+//! {
+//!   "version": 2,
+//!   "repository": { "name": "review_helper", "path": "/home/me/review_helper", "first_commit": "9f89049...", "base_branch": "main" },
+//!   "review": {
+//!     "name": "fancy_ui",
+//!     "diff_range": {
+//!       "start": "ed7811b", "end": "", "mode": "commits",
+//!       "iterations": [{ "start": "ed7811b", "end": "9f89049" }], "interdiff_iteration": null
+//!     },
+//!     "files": [
+//!       {
+//!         "path": "src/lib.rs", "is_reviewed": true, "reviewed_blob": "3b18e51...", "is_changed_since_review": false, "reviewed_commits": [],
+//!         "status": { "change_type": "modified", "added_lines": 10, "removed_lines": 2 }
+//!       }
+//!     ],
+//!     "notes": [
+//!       { "text": "off-by-one", "context": "src/lib.rs", "line_range": { "side": "old", "start": 3, "end": 4 }, "is_outdated": false, "is_done": false }
+//!     ]
+//!   }
+//! }
+//! ```
+//!
+//! Fields are only added in new versions, renaming or removing a field requires a new version.
+//! Version 1 has no `mode`, `iterations` and `interdiff_iteration`, its diff ranges compare commits.
+//! Its files have no `reviewed_blob`, `is_changed_since_review` and `reviewed_commits`.
+
+use std::path::PathBuf;
+
+use anyhow::{anyhow, bail};
+use itertools::Itertools;
+use serde_derive::{Deserialize, Serialize};
+
+use crate::git_utils::{self, ChangeType, DiffStatus, FileDiffMap};
+use crate::repositories::{Repository, Review, ReviewId};
+use crate::storage::ReviewHelperStorage;
+use crate::storage::repository_storage::{DiffMode, DiffRangeStore, DiffSide, FileDiffStore, IterationStore, LineRange, NoteStore, ReviewName};
+
+pub const REVIEW_JSON_VERSION: u32 = 2;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ReviewJson {
+    pub version: u32,
+    pub repository: RepositoryJson,
+    pub review: ReviewContentJson,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RepositoryJson {
+    pub name: String,
+    pub path: PathBuf,
+    pub first_commit: String,
+    pub base_branch: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ReviewContentJson {
+    pub name: String,
+    pub diff_range: DiffRangeJson,
+    pub files: Vec<FileJson>,
+    pub notes: Vec<NoteJson>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DiffRangeJson {
+    pub start: String,
+    pub end: String,
+    #[serde(default)]
+    pub mode: DiffModeJson,
+    #[serde(default)]
+    pub iterations: Vec<IterationJson>,
+    #[serde(default)]
+    pub interdiff_iteration: Option<usize>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct IterationJson {
+    pub start: String,
+    pub end: String,
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DiffModeJson {
+    #[default]
+    Commits,
+    Staged,
+    Unstaged,
+    WorkingTree,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct FileJson {
+    pub path: PathBuf,
+    pub is_reviewed: bool,
+    #[serde(default)]
+    pub reviewed_blob: Option<String>,
+    #[serde(default)]
+    pub is_changed_since_review: bool,
+    #[serde(default)]
+    pub reviewed_commits: Vec<String>,
+    pub status: Option<DiffStatusJson>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DiffStatusJson {
+    pub change_type: ChangeTypeJson,
+    pub added_lines: u32,
+    pub removed_lines: u32,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ChangeTypeJson {
+    Invalid,
+    Added,
+    Copied,
+    Deleted,
+    Modified,
+    Renamed,
+    TypeChanged,
+    Unmerged,
+    Unknown,
+    Broken,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct NoteJson {
+    pub text: String,
+    pub context: String,
+    pub line_range: Option<LineRangeJson>,
+    pub is_outdated: bool,
+    pub is_done: bool,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DiffSideJson {
+    Old,
+    New,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct LineRangeJson {
+    pub side: DiffSideJson,
+    pub start: u32,
+    pub end: u32,
+}
+
+impl From<&ChangeType> for ChangeTypeJson {
+    fn from(value: &ChangeType) -> Self {
+        match value {
+            ChangeType::Invalid => ChangeTypeJson::Invalid,
+            ChangeType::Added => ChangeTypeJson::Added,
+            ChangeType::Copied => ChangeTypeJson::Copied,
+            ChangeType::Deleted => ChangeTypeJson::Deleted,
+            ChangeType::Modified => ChangeTypeJson::Modified,
+            ChangeType::Renamed => ChangeTypeJson::Renamed,
+            ChangeType::TypChanged => ChangeTypeJson::TypeChanged,
+            ChangeType::Unmerged => ChangeTypeJson::Unmerged,
+            ChangeType::Unknown => ChangeTypeJson::Unknown,
+            ChangeType::Broken => ChangeTypeJson::Broken,
+        }
+    }
+}
+
+impl From<DiffMode> for DiffModeJson {
+    fn from(value: DiffMode) -> Self {
+        match value {
+            DiffMode::Commits => DiffModeJson::Commits,
+            DiffMode::Staged => DiffModeJson::Staged,
+            DiffMode::Unstaged => DiffModeJson::Unstaged,
+            DiffMode::WorkingTree => DiffModeJson::WorkingTree,
+        }
+    }
+}
+
+impl From<DiffModeJson> for DiffMode {
+    fn from(value: DiffModeJson) -> Self {
+        match value {
+            DiffModeJson::Commits => DiffMode::Commits,
+            DiffModeJson::Staged => DiffMode::Staged,
+            DiffModeJson::Unstaged => DiffMode::Unstaged,
+            DiffModeJson::WorkingTree => DiffMode::WorkingTree,
+        }
+    }
+}
+
+impl From<&DiffStatus> for DiffStatusJson {
+    fn from(value: &DiffStatus) -> Self {
+        Self {
+            change_type: ChangeTypeJson::from(&value.change_type),
+            added_lines: value.added_lines,
+            removed_lines: value.removed_lines,
+        }
+    }
+}
+
+impl From<&LineRange> for LineRangeJson {
+    fn from(value: &LineRange) -> Self {
+        let side = match value.side {
+            DiffSide::Old => DiffSideJson::Old,
+            DiffSide::New => DiffSideJson::New,
+        };
+        Self {
+            side,
+            start: value.start,
+            end: value.end,
+        }
+    }
+}
+
+impl From<&LineRangeJson> for LineRange {
+    fn from(value: &LineRangeJson) -> Self {
+        let side = match value.side {
+            DiffSideJson::Old => DiffSide::Old,
+            DiffSideJson::New => DiffSide::New,
+        };
+        LineRange::new(side, value.start.max(1), value.end.max(1))
+    }
+}
+
+impl ReviewJson {
+    pub fn new(repository: &Repository, review: &Review, file_diff_map: &FileDiffMap) -> Self {
+        let store = repository.store();
+        let files = review
+            .file_diffs
+            .stores()
+            .into_iter()
+            .sorted_by(|lhs, rhs| lhs.file_path.cmp(&rhs.file_path))
+            .map(|file_diff| FileJson {
+                path: file_diff.file_path.clone(),
+                is_reviewed: file_diff.is_reviewed,
+                reviewed_blob: file_diff.reviewed_blob.clone(),
+                is_changed_since_review: file_diff.is_changed_since_review,
+                reviewed_commits: file_diff.reviewed_commits.clone(),
+                status: file_diff_map.get(file_diff.file_path.to_string_lossy().as_ref()).map(DiffStatusJson::from),
+            })
+            .collect();
+        let notes = review
+            .notes
+            .iter()
+            .sorted_by_key(|(id, _)| (*id).clone())
+            .map(|(_, note)| NoteJson {
+                text: note.text.clone(),
+                context: note.context.clone(),
+                line_range: note.line_range.as_ref().map(LineRangeJson::from),
+                is_outdated: note.is_outdated,
+                is_done: note.is_done,
+            })
+            .collect();
+
+        Self {
+            version: REVIEW_JSON_VERSION,
+            repository: RepositoryJson {
+                name: store.name.as_str().to_string(),
+                path: store.path.clone(),
+                first_commit: store.first_commit.clone(),
+                base_branch: store.base_branch.clone(),
+            },
+            review: ReviewContentJson {
+                name: review.name().as_str().to_string(),
+                diff_range: DiffRangeJson {
+                    start: review.diff_range().start.clone(),
+                    end: review.diff_range().end.clone(),
+                    mode: DiffModeJson::from(review.diff_range().mode),
+                    iterations: review
+                        .diff_range()
+                        .iterations
+                        .iter()
+                        .map(|iteration| IterationJson {
+                            start: iteration.start.clone(),
+                            end: iteration.end.clone(),
+                        })
+                        .collect(),
+                    interdiff_iteration: review.diff_range().interdiff_iteration,
+                },
+                files,
+                notes,
+            },
+        }
+    }
+
+    pub fn from_json(content: &str) -> anyhow::Result<Self> {
+        #[derive(Deserialize)]
+        struct Versioned {
+            version: u32,
+        }
+        let versioned: Versioned = serde_json::from_str(content).map_err(|e| anyhow!("Invalid review JSON: {}", e))?;
+        if versioned.version == 0 || versioned.version > REVIEW_JSON_VERSION {
+            bail!(
+                "Review JSON version {} is not supported, supported versions are 1 to {}",
+                versioned.version,
+                REVIEW_JSON_VERSION
+            );
+        }
+        serde_json::from_str(content).map_err(|e| anyhow!("Invalid review JSON: {}", e))
+    }
+
+    pub fn to_json(&self) -> anyhow::Result<String> {
+        Ok(serde_json::to_string_pretty(self)?)
+    }
+}
+
+/// Queries git for the current status of the files of the review.
+pub fn export_review_json(repository: &Repository, review: &Review) -> anyhow::Result<String> {
+    let (start, end) = review.diff_range().revisions();
+    let file_diff_map = git_utils::diff_git_repo(repository.path(), start, end)?;
+    ReviewJson::new(repository, review, &file_diff_map).to_json()
+}
+
+/// Stores the review in the repository, which has to have the same first commit
+/// as the exported one. The review is only registered, it has to be loaded afterwards.
+pub fn import_review(
+    storage: &dyn ReviewHelperStorage,
+    repository: &mut Repository,
+    review_json: &ReviewJson,
+    review_name: Option<ReviewName>,
+) -> anyhow::Result<(ReviewId, ReviewName)> {
+    if repository.store().first_commit != review_json.repository.first_commit {
+        bail!(
+            "Review belongs to repository '{}' (first commit {}), not to '{}'",
+            review_json.repository.name,
+            review_json.repository.first_commit,
+            repository.name.as_str()
+        );
+    }
+
+    let review_name = review_name.unwrap_or_else(|| ReviewName::from(review_json.review.name.as_str()));
+    let existing_review_names = storage.load_review_names(&repository.name)?;
+    if repository.reviews.has_review_name(&review_name) || existing_review_names.contains(&review_name) {
+        bail!("Review '{}' already exists", review_name.as_str());
+    }
+
+    let content = &review_json.review;
+    let diff_range = DiffRangeStore {
+        start: content.diff_range.start.clone(),
+        end: content.diff_range.end.clone(),
+        mode: DiffMode::from(content.diff_range.mode),
+        iterations: content
+            .diff_range
+            .iterations
+            .iter()
+            .map(|iteration| IterationStore {
+                start: iteration.start.clone(),
+                end: iteration.end.clone(),
+            })
+            .collect(),
+        interdiff_iteration: content.diff_range.interdiff_iteration,
+    };
+    let file_diffs = content
+        .files
+        .iter()
+        .map(|file| FileDiffStore {
+            file_path: file.path.clone(),
+            is_reviewed: file.is_reviewed,
+            reviewed_blob: file.reviewed_blob.clone(),
+            is_changed_since_review: file.is_changed_since_review,
+            reviewed_commits: file.reviewed_commits.clone(),
+        })
+        .collect::<Vec<_>>();
+    let notes = content
+        .notes
+        .iter()
+        .map(|note| NoteStore {
+            text: note.text.clone(),
+            context: note.context.clone(),
+            line_range: note.line_range.as_ref().map(LineRange::from),
+            is_outdated: note.is_outdated,
+            is_done: note.is_done,
+        })
+        .collect::<Vec<_>>();
+
+    storage.save_review_file_diffs(&repository.name, &review_name, &diff_range, &file_diffs.iter().collect::<Vec<_>>())?;
+    storage.save_review_notes(&repository.name, &review_name, &notes.iter().collect::<Vec<_>>())?;
+
+    let review_id = repository.reviews.register_review_name(review_name.clone());
+    Ok((review_id, review_name))
+}
+
+#[cfg(test)]
+mod tests {
+    use serial_test::serial;
+    use std::{env, fs};
+
+    use super::*;
+    use crate::repositories::Repositories;
+    use crate::storage::repository_storage::{RepositoryStore, ReviewStore};
+    use crate::storage::{RepositoryName, StorageBackend, create_storage};
+
+    struct Context(PathBuf);
+    impl Drop for Context {
+        fn drop(&mut self) {
+            let _ = fs::remove_dir_all(&self.0);
+        }
+    }
+
+    fn repository_store() -> RepositoryStore {
+        RepositoryStore {
+            path: PathBuf::from("/home/harry/workspace/review_helper"),
+            name: RepositoryName::from("review_helper"),
+            first_commit: "9f89049".to_string(),
+            base_branch: "main".to_string(),
+        }
+    }
+
+    fn review_store() -> ReviewStore {
+        ReviewStore {
+            diff_range: DiffRangeStore {
+                start: "ed7811b".to_string(),
+                end: "a261b7b".to_string(),
+                iterations: vec![IterationStore {
+                    start: "ed7811b".to_string(),
+                    end: "9f89049".to_string(),
+                }],
+                interdiff_iteration: Some(1),
+                ..Default::default()
+            },
+            file_diff_list: vec![FileDiffStore {
+                file_path: PathBuf::from("src/lib.rs"),
+                is_reviewed: true,
+                reviewed_blob: Some("3b18e512dba79e4c8300dd08aeb37f8e728b8dad".to_string()),
+                reviewed_commits: vec!["9f89049".to_string()],
+                ..Default::default()
+            }],
+            notes: vec![
+                NoteStore {
+                    text: "off-by-one".to_string(),
+                    context: "src/lib.rs".to_string(),
+                    line_range: Some(LineRange::new(DiffSide::Old, 3, 4)),
+                    ..Default::default()
+                },
+                NoteStore {
+                    text: "update docs".to_string(),
+                    is_done: true,
+                    ..Default::default()
+                },
+            ],
+        }
+    }
+
+    #[test]
+    fn test_review_json_version() -> anyhow::Result<()> {
+        let repositories = Repositories::new(vec![repository_store()]);
+        let (_, repository) = repositories.iter().next().expect("repository");
+        let review = Review::new(review_store(), ReviewName::from("fancy_ui"));
+        let file_diff_map = FileDiffMap::from([(
+            "src/lib.rs".to_string(),
+            DiffStatus {
+                added_lines: 10,
+                removed_lines: 2,
+                change_type: ChangeType::Modified,
+            },
+        )]);
+
+        let json = ReviewJson::new(repository, &review, &file_diff_map).to_json()?;
+        assert!(json.contains("\"change_type\": \"modified\""));
+        assert!(json.contains("\"side\": \"old\""));
+
+        let review_json = ReviewJson::from_json(&json)?;
+        assert_eq!(review_json.version, REVIEW_JSON_VERSION);
+        assert_eq!(review_json.review.notes.len(), 2);
+
+        let newer_json = json.replacen("\"version\": 2", "\"version\": 3", 1);
+        assert!(ReviewJson::from_json(&newer_json).is_err());
+
+        let version_1_json = r#"{
+            "version": 1,
+            "repository": { "name": "review_helper", "path": "/tmp/review_helper", "first_commit": "9f89049", "base_branch": "main" },
+            "review": { "name": "fancy_ui", "diff_range": { "start": "ed7811b", "end": "" }, "files": [], "notes": [] }
+        }"#;
+        let diff_range = ReviewJson::from_json(version_1_json)?.review.diff_range;
+        assert_eq!(diff_range.mode, DiffModeJson::Commits);
+        assert!(diff_range.iterations.is_empty() && diff_range.interdiff_iteration.is_none());
+        assert!(ReviewJson::from_json("{}").is_err());
+
+        Ok(())
+    }
+
+    #[serial]
+    #[test]
+    fn test_import_review() -> anyhow::Result<()> {
+        let mut path = env::temp_dir();
+        path.push(format!("{}_review_json_test", std::env!("CARGO_CRATE_NAME")));
+        let context = Context(path);
+        let _ = fs::remove_dir_all(&context.0);
+
+        let storage = create_storage(context.0.clone(), StorageBackend::File)?;
+        storage.save_repository(&repository_store())?;
+
+        let mut repositories = Repositories::new(storage.load_repositories()?.repositories);
+        let repository_id = repositories.repository_id(&RepositoryName::from("review_helper")).expect("repository");
+        let repository = repositories.get_mut(&repository_id).expect("repository");
+
+        let review = Review::new(review_store(), ReviewName::from("fancy_ui"));
+        let review_json = ReviewJson::new(repository, &review, &FileDiffMap::new());
+
+        let (_, review_name) = import_review(storage.as_ref(), repository, &review_json, None)?;
+        assert_eq!(review_name.as_str(), "fancy_ui");
+        assert!(import_review(storage.as_ref(), repository, &review_json, None).is_err());
+
+        let imported = storage.load_review(&repository.name, &review_name)?.expect("imported review");
+        let mut expected = review_store();
+        imported.notes.iter().for_each(|note| assert!(expected.notes.contains(note)));
+        expected.notes.retain(|note| !imported.notes.contains(note));
+        assert!(expected.notes.is_empty());
+        assert_eq!(imported.diff_range, review_store().diff_range);
+        assert_eq!(imported.file_diff_list, review_store().file_diff_list);
+
+        let mut staged_review_store = review_store();
+        staged_review_store.diff_range.mode = DiffMode::Staged;
+        let staged_review = Review::new(staged_review_store, ReviewName::from("staged"));
+        let staged_review_json = ReviewJson::from_json(&ReviewJson::new(repository, &staged_review, &FileDiffMap::new()).to_json()?)?;
+        let (_, review_name) = import_review(storage.as_ref(), repository, &staged_review_json, None)?;
+        let imported = storage.load_review(&repository.name, &review_name)?.expect("imported review");
+        assert_eq!(imported.diff_range.mode, DiffMode::Staged);
+
+        let mut other_repository_json = review_json.clone();
+        other_repository_json.repository.first_commit = "5a99f03".to_string();
+        assert!(import_review(storage.as_ref(), repository, &other_repository_json, Some(ReviewName::from("other"))).is_err());
+        Ok(())
+    }
+}
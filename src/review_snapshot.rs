@@ -0,0 +1,231 @@
+//! Named snapshots of whole reviews: the diff range, the file diff flags and the notes.
+//!
+//! A snapshot is restored by storing its file diffs and notes as the current state of the
+//! review. Before risky operations, e.g. a diff range without any file of the previous one,
+//! a snapshot is taken automatically.
+
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt;
+
+use crate::storage::repository_storage::{DiffRangeStore, NoteStore, ReviewName, ReviewStore, SnapshotStore};
+use crate::storage::{RepositoryName, ReviewHelperStorage, StorageResult};
+
+pub const BEFORE_DIFF_RANGE_CHANGE: &str = "before-diff-range";
+pub const BEFORE_RESTORE: &str = "before-restore";
+
+/// A name like `before-restore-20240301-100000`, automatic snapshots never replace each other
+/// within a second.
+pub fn auto_snapshot_name(reason: &str) -> String {
+    format!("{}-{}", reason, chrono::Local::now().format("%Y%m%d-%H%M%S"))
+}
+
+/// Stores the state of the snapshot as the current state of the review.
+pub fn restore_snapshot(
+    storage: &dyn ReviewHelperStorage,
+    repository_name: &RepositoryName,
+    review_name: &ReviewName,
+    snapshot: &SnapshotStore,
+) -> StorageResult<()> {
+    storage.save_review_file_diffs(
+        repository_name,
+        review_name,
+        &snapshot.review.diff_range,
+        &snapshot.review.file_diff_list.iter().collect::<Vec<_>>(),
+    )?;
+    storage.save_review_notes(repository_name, review_name, &snapshot.review.notes.iter().collect::<Vec<_>>())
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum SnapshotDifference {
+    DiffRange { snapshot: DiffRangeStore, current: DiffRangeStore },
+    FileOnlyInSnapshot(String),
+    FileOnlyInCurrent(String),
+    FileReviewed { file: String, is_reviewed_in_snapshot: bool },
+    NoteOnlyInSnapshot(NoteStore),
+    NoteOnlyInCurrent(NoteStore),
+    NoteChanged { snapshot: NoteStore, current: NoteStore },
+}
+
+fn diff_range_text(diff_range: &DiffRangeStore) -> String {
+    let end = if diff_range.end.is_empty() { "working copy" } else { &diff_range.end };
+    format!("{}..{}", diff_range.start, end)
+}
+
+fn note_state(note: &NoteStore) -> String {
+    let mut state = if note.is_done { "done" } else { "open" }.to_string();
+    if let Some(line_range) = &note.line_range {
+        state.push_str(&format!(", lines {}", line_range));
+    }
+    if note.is_outdated {
+        state.push_str(", outdated");
+    }
+    state
+}
+
+impl fmt::Display for SnapshotDifference {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let in_context = |context: &str| if context.is_empty() { String::new() } else { format!(" in {}", context) };
+        match self {
+            SnapshotDifference::DiffRange { snapshot, current } => {
+                write!(f, "Diff range {} was {}", diff_range_text(current), diff_range_text(snapshot))
+            }
+            SnapshotDifference::FileOnlyInSnapshot(file) => write!(f, "File {} is only in the snapshot", file),
+            SnapshotDifference::FileOnlyInCurrent(file) => write!(f, "File {} was added since the snapshot", file),
+            SnapshotDifference::FileReviewed {
+                file,
+                is_reviewed_in_snapshot: true,
+            } => write!(f, "File {} was reviewed in the snapshot", file),
+            SnapshotDifference::FileReviewed {
+                file,
+                is_reviewed_in_snapshot: false,
+            } => write!(f, "File {} was reviewed since the snapshot", file),
+            SnapshotDifference::NoteOnlyInSnapshot(note) => write!(f, "Note{} is only in the snapshot: {}", in_context(&note.context), note.text),
+            SnapshotDifference::NoteOnlyInCurrent(note) => write!(f, "Note{} was added since the snapshot: {}", in_context(&note.context), note.text),
+            SnapshotDifference::NoteChanged { snapshot, current } => write!(
+                f,
+                "Note{} is {}, was {}: {}",
+                in_context(&current.context),
+                note_state(current),
+                note_state(snapshot),
+                current.text
+            ),
+        }
+    }
+}
+
+/// Notes are matched by their context and text, notes with the same context and text in order.
+fn notes_by_key(notes: &[NoteStore]) -> BTreeMap<(&str, &str), Vec<&NoteStore>> {
+    let mut notes_by_key = BTreeMap::<(&str, &str), Vec<&NoteStore>>::new();
+    for note in notes {
+        notes_by_key.entry((note.context.as_str(), note.text.as_str())).or_default().push(note);
+    }
+    notes_by_key
+}
+
+/// What changed from `snapshot` to `current`, ordered by diff range, files and notes.
+pub fn compare_snapshot(snapshot: &ReviewStore, current: &ReviewStore) -> Vec<SnapshotDifference> {
+    let mut differences = Vec::new();
+    let is_same_range = snapshot.diff_range.start == current.diff_range.start && snapshot.diff_range.end == current.diff_range.end;
+    if !is_same_range {
+        differences.push(SnapshotDifference::DiffRange {
+            snapshot: snapshot.diff_range.clone(),
+            current: current.diff_range.clone(),
+        });
+    }
+
+    let file_map = |review: &ReviewStore| {
+        review
+            .file_diff_list
+            .iter()
+            .map(|file_diff| (file_diff.file_path.to_string_lossy().to_string(), file_diff.is_reviewed))
+            .collect::<BTreeMap<_, _>>()
+    };
+    let snapshot_files = file_map(snapshot);
+    let current_files = file_map(current);
+    let files = snapshot_files.keys().chain(current_files.keys()).collect::<BTreeSet<_>>();
+    for file in files {
+        match (snapshot_files.get(file), current_files.get(file)) {
+            (Some(_), None) => differences.push(SnapshotDifference::FileOnlyInSnapshot(file.clone())),
+            (None, Some(_)) => differences.push(SnapshotDifference::FileOnlyInCurrent(file.clone())),
+            (Some(is_reviewed_in_snapshot), Some(is_reviewed)) if is_reviewed_in_snapshot != is_reviewed => {
+                differences.push(SnapshotDifference::FileReviewed {
+                    file: file.clone(),
+                    is_reviewed_in_snapshot: *is_reviewed_in_snapshot,
+                })
+            }
+            _ => {}
+        }
+    }
+
+    let snapshot_notes = notes_by_key(&snapshot.notes);
+    let current_notes = notes_by_key(&current.notes);
+    let keys = snapshot_notes.keys().chain(current_notes.keys()).collect::<BTreeSet<_>>();
+    for key in keys {
+        let snapshot_notes = snapshot_notes.get(key).map(Vec::as_slice).unwrap_or_default();
+        let current_notes = current_notes.get(key).map(Vec::as_slice).unwrap_or_default();
+        for index in 0..snapshot_notes.len().max(current_notes.len()) {
+            match (snapshot_notes.get(index), current_notes.get(index)) {
+                (Some(snapshot_note), None) => differences.push(SnapshotDifference::NoteOnlyInSnapshot((*snapshot_note).clone())),
+                (None, Some(current_note)) => differences.push(SnapshotDifference::NoteOnlyInCurrent((*current_note).clone())),
+                (Some(snapshot_note), Some(current_note)) if snapshot_note != current_note => differences.push(SnapshotDifference::NoteChanged {
+                    snapshot: (*snapshot_note).clone(),
+                    current: (*current_note).clone(),
+                }),
+                _ => {}
+            }
+        }
+    }
+    differences
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    use super::*;
+    use crate::storage::repository_storage::{DiffSide, FileDiffStore, LineRange};
+
+    fn file_diff(file: &str, is_reviewed: bool) -> FileDiffStore {
+        FileDiffStore {
+            file_path: PathBuf::from(file),
+            is_reviewed,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_compare_snapshot() {
+        let snapshot = ReviewStore {
+            diff_range: DiffRangeStore {
+                start: "ed7811b".to_string(),
+                end: "a261b7b".to_string(),
+                ..Default::default()
+            },
+            file_diff_list: vec![file_diff("README.md", true), file_diff("src/lib.rs", false), file_diff("src/main.rs", true)],
+            notes: vec![
+                NoteStore {
+                    text: "update docs".to_string(),
+                    ..Default::default()
+                },
+                NoteStore {
+                    text: "off-by-one".to_string(),
+                    context: "src/lib.rs".to_string(),
+                    line_range: Some(LineRange::new(DiffSide::Old, 3, 4)),
+                    ..Default::default()
+                },
+            ],
+        };
+        assert!(compare_snapshot(&snapshot, &snapshot).is_empty());
+
+        let mut current = snapshot.clone();
+        current.diff_range.end = String::new();
+        current.file_diff_list = vec![file_diff("src/lib.rs", true), file_diff("src/main.rs", true), file_diff("src/ui.rs", false)];
+        current.notes[1].is_done = true;
+        current.notes.remove(0);
+        current.notes.push(NoteStore {
+            text: "typo".to_string(),
+            ..Default::default()
+        });
+
+        let differences = compare_snapshot(&snapshot, &current).iter().map(ToString::to_string).collect::<Vec<_>>();
+        assert_eq!(
+            differences,
+            vec![
+                "Diff range ed7811b..working copy was ed7811b..a261b7b",
+                "File README.md is only in the snapshot",
+                "File src/lib.rs was reviewed since the snapshot",
+                "File src/ui.rs was added since the snapshot",
+                "Note was added since the snapshot: typo",
+                "Note is only in the snapshot: update docs",
+                "Note in src/lib.rs is done, lines old:3-4, was open, lines old:3-4: off-by-one",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_auto_snapshot_name() {
+        let name = auto_snapshot_name(BEFORE_RESTORE);
+        assert!(name.starts_with("before-restore-20"));
+        assert_eq!(name.len(), "before-restore-20240301-100000".len());
+    }
+}
@@ -0,0 +1,98 @@
+//! Versions of the TOML files written by `ReviewHelperFileStorage`.
+//!
+//! * 1: files without `version` key. Reviews may still carry `repo_path` and `first_commit`
+//!   of the old single-file layout, see `docs/example_review.toml`.
+//! * 2: `version` key in repository and review files, repository data only in the repository file.
+
+use toml::{Table, Value};
+
+use crate::storage::StorageResult;
+use crate::storage::repository_storage::StorageError;
+
+pub const FORMAT_VERSION: i64 = 2;
+pub const VERSION_KEY: &str = "version";
+
+const LEGACY_REPOSITORY_PATH_KEY: &str = "repo_path";
+const LEGACY_FIRST_COMMIT_KEY: &str = "first_commit";
+
+/// Version of the file, files written before versioning are version 1.
+pub fn format_version(table: &Table) -> StorageResult<i64> {
+    match table.get(VERSION_KEY) {
+        None => Ok(1),
+        Some(Value::Integer(version)) if (1..=FORMAT_VERSION).contains(version) => Ok(*version),
+        Some(version) => Err(StorageError::UnsupportedVersion(version.to_string())),
+    }
+}
+
+pub fn set_format_version(table: &mut Table) {
+    table.insert(VERSION_KEY.to_string(), Value::Integer(FORMAT_VERSION));
+}
+
+/// Repository files only gain the version key.
+pub fn migrate_repository_table(table: &mut Table) -> StorageResult<bool> {
+    if format_version(table)? == FORMAT_VERSION {
+        return Ok(false);
+    }
+    set_format_version(table);
+    Ok(true)
+}
+
+/// Moves the repository data of old review files into `repository_table`, where it is
+/// only taken over if the repository file doesn't know it yet.
+pub fn migrate_review_table(table: &mut Table, repository_table: &mut Table) -> StorageResult<bool> {
+    if format_version(table)? == FORMAT_VERSION {
+        return Ok(false);
+    }
+    for (legacy_key, key) in [(LEGACY_REPOSITORY_PATH_KEY, "path"), (LEGACY_FIRST_COMMIT_KEY, "first_commit")] {
+        if let Some(value) = table.remove(legacy_key) {
+            let is_known = repository_table.get(key).and_then(Value::as_str).is_some_and(|v| !v.is_empty());
+            if !is_known {
+                repository_table.insert(key.to_string(), value);
+            }
+        }
+    }
+    set_format_version(table);
+    Ok(true)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_migrate_review_table() -> anyhow::Result<()> {
+        let mut review_table: Table = toml::from_str(
+            r#"version = 1
+start_diff = "c134268"
+end_diff = "98bad35"
+first_commit = "9f89049"
+repo_path = "/home/harry/workspace/review_helper"
+"#,
+        )?;
+        let mut repository_table: Table = toml::from_str(
+            r#"name = "review_helper"
+path = "/home/harry/review_helper"
+"#,
+        )?;
+
+        assert!(migrate_review_table(&mut review_table, &mut repository_table)?);
+        assert!(!migrate_review_table(&mut review_table, &mut repository_table)?);
+
+        let expected_review_table: Table = toml::from_str(
+            r#"version = 2
+start_diff = "c134268"
+end_diff = "98bad35"
+"#,
+        )?;
+        assert_eq!(review_table, expected_review_table);
+        assert_eq!(repository_table["path"].as_str(), Some("/home/harry/review_helper"));
+        assert_eq!(repository_table["first_commit"].as_str(), Some("9f89049"));
+
+        assert!(migrate_repository_table(&mut repository_table)?);
+        assert_eq!(format_version(&repository_table)?, FORMAT_VERSION);
+
+        let newer_table: Table = toml::from_str("version = 3")?;
+        assert!(format_version(&newer_table).is_err());
+        Ok(())
+    }
+}
@@ -0,0 +1,113 @@
+//! Protects the files of `ReviewHelperFileStorage` against other instances working on the
+//! same data directory, e.g. a second window or the command line interface.
+
+use std::collections::HashMap;
+use std::fs::{self, File};
+use std::hash::{DefaultHasher, Hash, Hasher};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+use crate::storage::StorageResult;
+use crate::storage::repository_storage::StorageError;
+
+const LOCK_FILE_NAME: &str = "review_helper.lock";
+
+/// Advisory lock of the whole storage, released when dropped.
+pub struct StorageLock(File);
+
+impl StorageLock {
+    pub fn lock(storage_path: &Path) -> StorageResult<Self> {
+        fs::create_dir_all(storage_path)?;
+        let file = File::options()
+            .create(true)
+            .truncate(false)
+            .write(true)
+            .open(storage_path.join(LOCK_FILE_NAME))?;
+        file.lock()?;
+        Ok(Self(file))
+    }
+}
+
+impl Drop for StorageLock {
+    fn drop(&mut self) {
+        let _ = self.0.unlock();
+    }
+}
+
+/// Remembers the contents of every file as it was last read or written by this instance,
+/// so that changes of other instances are detected before they are overwritten.
+#[derive(Debug, Default)]
+pub struct ChangeTracker {
+    content_hashes: Mutex<HashMap<PathBuf, Option<u64>>>,
+}
+
+fn content_hash(path: &Path) -> Option<u64> {
+    let contents = fs::read(path).ok()?;
+    let mut hasher = DefaultHasher::new();
+    contents.hash(&mut hasher);
+    Some(hasher.finish())
+}
+
+impl ChangeTracker {
+    /// Records the current contents of `path`, a missing file is recorded as well.
+    pub fn record(&self, path: &Path) {
+        let hash = content_hash(path);
+        self.hashes().insert(path.to_path_buf(), hash);
+    }
+
+    /// Whether `path` differs from its recorded contents. Files that were never read are unchanged.
+    pub fn is_changed(&self, path: &Path) -> bool {
+        self.hashes().get(path).is_some_and(|hash| *hash != content_hash(path))
+    }
+
+    pub fn check(&self, path: &Path) -> StorageResult<()> {
+        if self.is_changed(path) {
+            return Err(StorageError::Conflict(path.display().to_string()));
+        }
+        Ok(())
+    }
+
+    /// Forgets all files in `directory`, e.g. after it was renamed or deleted.
+    pub fn forget(&self, directory: &Path) {
+        self.hashes().retain(|path, _| !path.starts_with(directory));
+    }
+
+    fn hashes(&self) -> std::sync::MutexGuard<'_, HashMap<PathBuf, Option<u64>>> {
+        // The map stays consistent even if another thread panicked while holding the lock.
+        self.content_hashes.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{env, fs::TryLockError};
+
+    use super::*;
+
+    #[test]
+    fn test_storage_lock() -> anyhow::Result<()> {
+        let mut path = env::temp_dir();
+        path.push(format!("{}_file_guard_test", std::env!("CARGO_CRATE_NAME")));
+
+        let lock = StorageLock::lock(&path)?;
+        let other_file = File::options().write(true).open(path.join(LOCK_FILE_NAME))?;
+        assert!(matches!(other_file.try_lock(), Err(TryLockError::WouldBlock)));
+        drop(lock);
+        other_file.try_lock()?;
+
+        let tracker = ChangeTracker::default();
+        let file_path = path.join("notes.md");
+        tracker.check(&file_path)?;
+        tracker.record(&file_path);
+        fs::write(&file_path, "* [ ] note")?;
+        assert!(matches!(tracker.check(&file_path), Err(StorageError::Conflict(_))));
+        tracker.record(&file_path);
+        tracker.check(&file_path)?;
+        tracker.forget(&path);
+        fs::write(&file_path, "* [x] note")?;
+        tracker.check(&file_path)?;
+
+        fs::remove_dir_all(&path)?;
+        Ok(())
+    }
+}
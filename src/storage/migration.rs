@@ -0,0 +1,362 @@
+use std::collections::HashSet;
+
+use crate::storage::repository_storage::{ReviewName, ReviewStore, SnapshotStore, StorageError};
+use crate::storage::{RepositoryName, RepositoryStore, ReviewHelperStorage};
+
+#[derive(thiserror::Error, Debug)]
+pub enum MigrationError {
+    #[error(transparent)]
+    Storage(#[from] StorageError),
+    #[error("Repository '{0}' already exists in the target storage!")]
+    RepositoryExists(String),
+    #[error("Source storage is broken:\n{}", .0.join("\n"))]
+    BrokenSource(Vec<String>),
+    #[error("Verification failed:\n{}", .0.join("\n"))]
+    Verification(Vec<String>),
+}
+
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct MigrationSummary {
+    pub repositories: usize,
+    pub reviews: usize,
+    pub file_diffs: usize,
+    pub notes: usize,
+}
+
+/// Copies all repositories and reviews with their activity logs and snapshots from `source` into `target` and verifies the result.
+/// Repositories that already exist in `target` are not overwritten, the migration is refused instead.
+/// A source with broken repository files is refused as well, they would be lost silently.
+/// If copying or the verification fails, the repositories written so far are removed from `target` again, so the migration can be retried.
+pub fn migrate_storage(source: &dyn ReviewHelperStorage, target: &dyn ReviewHelperStorage) -> Result<MigrationSummary, MigrationError> {
+    let loaded_repositories = source.load_repositories()?;
+    if !loaded_repositories.errors.is_empty() {
+        return Err(MigrationError::BrokenSource(
+            loaded_repositories.errors.iter().map(ToString::to_string).collect(),
+        ));
+    }
+    let repositories = loaded_repositories.repositories;
+    let target_repository_names = target
+        .load_repositories()?
+        .repositories
+        .into_iter()
+        .map(|repository| repository.name)
+        .collect::<HashSet<_>>();
+    if let Some(repository) = repositories.iter().find(|repository| target_repository_names.contains(&repository.name)) {
+        return Err(MigrationError::RepositoryExists(repository.name.as_str().to_string()));
+    }
+
+    let mut written_repositories = Vec::new();
+    let result = copy_repositories(source, target, &repositories, &mut written_repositories)
+        .and_then(|summary| verify_storage(source, target, &repositories).map(|_| summary));
+    if result.is_err() {
+        for repository_name in written_repositories {
+            if let Err(e) = target.delete_repository(repository_name) {
+                log::warn!("Could not remove {} from the target storage: {}", repository_name.as_str(), e);
+            }
+        }
+    }
+    result
+}
+
+/// Pushes every repository to `written_repositories` before it is written.
+fn copy_repositories<'a>(
+    source: &dyn ReviewHelperStorage,
+    target: &dyn ReviewHelperStorage,
+    repositories: &'a [RepositoryStore],
+    written_repositories: &mut Vec<&'a RepositoryName>,
+) -> Result<MigrationSummary, MigrationError> {
+    let mut summary = MigrationSummary::default();
+    for repository in repositories {
+        written_repositories.push(&repository.name);
+        target.save_repository(repository)?;
+        summary.repositories += 1;
+
+        for review_name in source.load_review_names(&repository.name)? {
+            let review = load_review(source, &repository.name, &review_name)?;
+            target.save_review_file_diffs(
+                &repository.name,
+                &review_name,
+                &review.diff_range,
+                &review.file_diff_list.iter().collect::<Vec<_>>(),
+            )?;
+            target.save_review_notes(&repository.name, &review_name, &review.notes.iter().collect::<Vec<_>>())?;
+            target.append_review_events(&repository.name, &review_name, &source.load_review_events(&repository.name, &review_name)?)?;
+            for snapshot in source.load_review_snapshots(&repository.name, &review_name)? {
+                target.save_review_snapshot(&repository.name, &review_name, &snapshot)?;
+            }
+
+            summary.reviews += 1;
+            summary.file_diffs += review.file_diff_list.len();
+            summary.notes += review.notes.len();
+        }
+    }
+    Ok(summary)
+}
+
+/// Compares every repository of `repositories` between both storages. File diffs are compared
+/// independent of their order, notes keep their order in both storages.
+pub fn verify_storage(source: &dyn ReviewHelperStorage, target: &dyn ReviewHelperStorage, repositories: &[RepositoryStore]) -> Result<(), MigrationError> {
+    let mut differences = Vec::new();
+    let target_repositories = target.load_repositories()?.repositories;
+
+    for repository in repositories {
+        let name = repository.name.as_str();
+        match target_repositories.iter().find(|r| r.name == repository.name) {
+            None => {
+                differences.push(format!("Repository '{}' is missing", name));
+                continue;
+            }
+            Some(target_repository) if target_repository != repository => {
+                differences.push(format!("Repository '{}' differs: {:?} != {:?}", name, repository, target_repository));
+            }
+            Some(_) => {}
+        }
+
+        let review_names = sorted_review_names(source.load_review_names(&repository.name)?);
+        let target_review_names = sorted_review_names(target.load_review_names(&repository.name)?);
+        if review_names != target_review_names {
+            differences.push(format!("Reviews of '{}' differ: {:?} != {:?}", name, review_names, target_review_names));
+        }
+
+        for review_name in review_names.iter().filter(|review_name| target_review_names.contains(review_name)) {
+            let review = load_review(source, &repository.name, review_name)?;
+            let target_review = load_review(target, &repository.name, review_name)?;
+            let location = format!("{}/{}", name, review_name.as_str());
+
+            if review.diff_range != target_review.diff_range {
+                differences.push(format!("Diff range of '{}' differs", location));
+            }
+            if sorted_file_diffs(&review) != sorted_file_diffs(&target_review) {
+                differences.push(format!("File diffs of '{}' differ", location));
+            }
+            if review.notes != target_review.notes {
+                differences.push(format!("Notes of '{}' differ", location));
+            }
+            if source.load_review_events(&repository.name, review_name)? != target.load_review_events(&repository.name, review_name)? {
+                differences.push(format!("Activity log of '{}' differs", location));
+            }
+            let snapshots = sorted_snapshots(&source.load_review_snapshots(&repository.name, review_name)?);
+            if snapshots != sorted_snapshots(&target.load_review_snapshots(&repository.name, review_name)?) {
+                differences.push(format!("Snapshots of '{}' differ", location));
+            }
+        }
+    }
+
+    if differences.is_empty() {
+        Ok(())
+    } else {
+        Err(MigrationError::Verification(differences))
+    }
+}
+
+fn load_review(storage: &dyn ReviewHelperStorage, repository_name: &RepositoryName, review_name: &ReviewName) -> Result<ReviewStore, MigrationError> {
+    storage
+        .load_review(repository_name, review_name)?
+        .ok_or_else(|| MigrationError::Storage(StorageError::ReviewNotFound(format!("{}/{}", repository_name.as_str(), review_name.as_str()))))
+}
+
+fn sorted_review_names(mut review_names: Vec<ReviewName>) -> Vec<ReviewName> {
+    review_names.sort_by(|lhs, rhs| lhs.as_str().cmp(rhs.as_str()));
+    review_names
+}
+
+fn sorted_file_diffs(review: &ReviewStore) -> Vec<String> {
+    let mut file_diffs = review.file_diff_list.iter().map(|file_diff| format!("{:?}", file_diff)).collect::<Vec<_>>();
+    file_diffs.sort();
+    file_diffs
+}
+
+fn sorted_snapshots(snapshots: &[SnapshotStore]) -> Vec<String> {
+    let mut snapshots = snapshots
+        .iter()
+        .map(|snapshot| {
+            format!(
+                "{} {} {:?} {:?} {:?}",
+                snapshot.name,
+                snapshot.time,
+                snapshot.review.diff_range,
+                sorted_file_diffs(&snapshot.review),
+                snapshot.review.notes
+            )
+        })
+        .collect::<Vec<_>>();
+    snapshots.sort();
+    snapshots
+}
+
+#[cfg(test)]
+mod tests {
+    use serial_test::serial;
+    use std::{env, fs, path::PathBuf};
+
+    use super::*;
+    use crate::storage::repository_storage::{
+        DiffMode, DiffRangeStore, DiffSide, FileDiffStore, IterationStore, LineRange, NoteStore, ReviewEvent, ReviewEventStore, SnapshotStore,
+    };
+    use crate::storage::{ReviewHelperFileStorage, ReviewHelperSqliteStorage};
+
+    struct Context(PathBuf);
+    impl Drop for Context {
+        fn drop(&mut self) {
+            let _ = fs::remove_dir_all(&self.0);
+        }
+    }
+
+    fn fill_storage(storage: &dyn ReviewHelperStorage) -> anyhow::Result<()> {
+        let repository_name = RepositoryName::from("review_helper");
+        storage.save_repository(&RepositoryStore {
+            path: PathBuf::from("/home/harry/workspace/review_helper"),
+            name: repository_name.clone(),
+            first_commit: "9f89049b7f99682c48474d421ac126316adaed15".to_string(),
+            base_branch: "develop".to_string(),
+        })?;
+        storage.save_repository(&RepositoryStore {
+            path: PathBuf::from("/home/harry/workspace/trackme"),
+            name: RepositoryName::from("trackme"),
+            first_commit: "5a99f0351a9dcbe5f2414e84e6f5bb9f617af33a".to_string(),
+            base_branch: "main".to_string(),
+        })?;
+
+        let review_name = ReviewName::from("fancy_ui");
+        let file_diffs = [
+            FileDiffStore {
+                file_path: PathBuf::from("src/main.rs"),
+                is_reviewed: true,
+                reviewed_blob: Some("8ab686eafeb1f44702738c8b0f24f2567c36da6d".to_string()),
+                ..Default::default()
+            },
+            FileDiffStore {
+                file_path: PathBuf::from("README.md"),
+                is_reviewed: false,
+                ..Default::default()
+            },
+        ];
+        let diff_range = DiffRangeStore {
+            start: "ed7811b".to_string(),
+            end: "a261b7b".to_string(),
+            iterations: vec![IterationStore {
+                start: "ed7811b".to_string(),
+                end: "9f89049".to_string(),
+            }],
+            interdiff_iteration: Some(1),
+            mode: DiffMode::Staged,
+        };
+        storage.save_review_file_diffs(&repository_name, &review_name, &diff_range, &file_diffs.iter().collect::<Vec<_>>())?;
+        let notes = [
+            NoteStore {
+                context: "src/main.rs".to_string(),
+                line_range: Some(LineRange::new(DiffSide::Old, 3, 4)),
+                text: "off-by-one".to_string(),
+                ..Default::default()
+            },
+            NoteStore {
+                text: "update docs".to_string(),
+                is_done: true,
+                ..Default::default()
+            },
+        ];
+        storage.save_review_notes(&repository_name, &review_name, &notes.iter().collect::<Vec<_>>())?;
+        storage.append_review_events(
+            &repository_name,
+            &review_name,
+            &[ReviewEventStore {
+                time: "2024-03-01T10:00:00+01:00".to_string(),
+                author: "harry".to_string(),
+                event: ReviewEvent::NoteResolved {
+                    context: String::new(),
+                    text: "update docs".to_string(),
+                    is_done: true,
+                },
+            }],
+        )?;
+        storage.save_review_snapshot(
+            &repository_name,
+            &review_name,
+            &SnapshotStore {
+                name: "initial".to_string(),
+                time: "2024-03-01T09:00:00+01:00".to_string(),
+                review: ReviewStore {
+                    diff_range,
+                    file_diff_list: file_diffs.to_vec(),
+                    notes: notes.to_vec(),
+                },
+            },
+        )?;
+        storage.save_review_file_diffs(&repository_name, &ReviewName::from("empty"), &DiffRangeStore::default(), &[])?;
+        Ok(())
+    }
+
+    #[serial]
+    #[test]
+    fn test_migrate_storage() -> anyhow::Result<()> {
+        let mut path = env::temp_dir();
+        path.push(format!("{}_migration_test", std::env!("CARGO_CRATE_NAME")));
+        let context = Context(path);
+        let _ = fs::remove_dir_all(&context.0);
+
+        let file_storage = ReviewHelperFileStorage::new(context.0.join("file"));
+        let sqlite_storage = ReviewHelperSqliteStorage::new(context.0.join("sqlite"))?;
+        let file_storage_copy = ReviewHelperFileStorage::new(context.0.join("file_copy"));
+        fill_storage(&file_storage)?;
+
+        let expected_summary = MigrationSummary {
+            repositories: 2,
+            reviews: 2,
+            file_diffs: 2,
+            notes: 2,
+        };
+        assert_eq!(migrate_storage(&file_storage, &sqlite_storage)?, expected_summary);
+        assert_eq!(migrate_storage(&sqlite_storage, &file_storage_copy)?, expected_summary);
+        assert!(matches!(
+            migrate_storage(&file_storage, &sqlite_storage),
+            Err(MigrationError::RepositoryExists(_))
+        ));
+
+        let repositories = file_storage.load_repositories()?.repositories;
+        verify_storage(&file_storage, &file_storage_copy, &repositories)?;
+        assert_eq!(
+            file_storage_copy
+                .load_review_events(&RepositoryName::from("review_helper"), &ReviewName::from("fancy_ui"))?
+                .len(),
+            1
+        );
+        assert_eq!(
+            file_storage_copy
+                .load_review_snapshots(&RepositoryName::from("review_helper"), &ReviewName::from("fancy_ui"))?
+                .len(),
+            1
+        );
+
+        sqlite_storage.save_review_notes(&RepositoryName::from("review_helper"), &ReviewName::from("fancy_ui"), &[])?;
+        match verify_storage(&file_storage, &sqlite_storage, &repositories) {
+            Err(MigrationError::Verification(differences)) => assert_eq!(differences, vec!["Notes of 'review_helper/fancy_ui' differ"]),
+            result => panic!("Unexpected verification result {:?}", result),
+        }
+        Ok(())
+    }
+
+    #[serial]
+    #[test]
+    fn test_retrying_failed_migration() -> anyhow::Result<()> {
+        let mut path = env::temp_dir();
+        path.push(format!("{}_failed_migration_test", std::env!("CARGO_CRATE_NAME")));
+        let context = Context(path);
+        let _ = fs::remove_dir_all(&context.0);
+
+        // The SQLite storage returns the repositories by name, so review_helper is written before trackme fails.
+        let sqlite_storage = ReviewHelperSqliteStorage::new(context.0.join("sqlite"))?;
+        fill_storage(&sqlite_storage)?;
+        let file_storage = ReviewHelperFileStorage::new(context.0.join("file"));
+        fs::create_dir_all(context.0.join("file"))?;
+        let blocking_file = context.0.join("file").join("trackme");
+        fs::write(&blocking_file, "")?;
+
+        assert!(matches!(migrate_storage(&sqlite_storage, &file_storage), Err(MigrationError::Storage(_))));
+        assert!(file_storage.load_repositories()?.repositories.is_empty());
+        assert!(!context.0.join("file").join("review_helper").exists());
+
+        fs::remove_file(&blocking_file)?;
+        assert_eq!(migrate_storage(&sqlite_storage, &file_storage)?.repositories, 2);
+        Ok(())
+    }
+}
@@ -1,14 +1,74 @@
-use std::path::PathBuf;
+use std::{fmt, fs, path::PathBuf, str::FromStr};
 
+use serde_derive::{Deserialize, Serialize};
+
+pub mod file_format;
+pub mod file_guard;
+pub mod migration;
+pub mod notes_markdown;
 pub mod repository_file_storage;
+pub mod repository_sqlite_storage;
 pub mod repository_storage;
 
 pub use repository_file_storage::ReviewHelperFileStorage;
+pub use repository_sqlite_storage::ReviewHelperSqliteStorage;
+pub use repository_storage::LoadedRepositories;
 pub use repository_storage::RepositoryName;
 pub use repository_storage::RepositoryStore;
 pub use repository_storage::ReviewHelperStorage;
 pub use repository_storage::StorageResult;
 
-pub fn create_storage(path: PathBuf) -> Box<dyn ReviewHelperStorage> {
-    Box::new(ReviewHelperFileStorage::new(path))
+/// Selects the `ReviewHelperStorage` implementation, both keep their data below the app data path.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, clap::ValueEnum)]
+#[serde(rename_all = "lowercase")]
+pub enum StorageBackend {
+    /// One directory per repository and review with TOML and Markdown files.
+    #[default]
+    File,
+    /// One SQLite database.
+    Sqlite,
+}
+
+impl StorageBackend {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            StorageBackend::File => "file",
+            StorageBackend::Sqlite => "sqlite",
+        }
+    }
+}
+
+impl fmt::Display for StorageBackend {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.as_str())
+    }
+}
+
+impl FromStr for StorageBackend {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "file" => Ok(StorageBackend::File),
+            "sqlite" => Ok(StorageBackend::Sqlite),
+            _ => Err(format!("Unknown storage backend '{}'", s)),
+        }
+    }
+}
+
+pub fn create_storage(path: PathBuf, backend: StorageBackend) -> StorageResult<Box<dyn ReviewHelperStorage>> {
+    match backend {
+        StorageBackend::File => Ok(Box::new(ReviewHelperFileStorage::new(path))),
+        StorageBackend::Sqlite => Ok(Box::new(ReviewHelperSqliteStorage::new(path)?)),
+    }
+}
+
+pub fn prepare_app_data_path() -> PathBuf {
+    let mut app_data_path = dirs::data_local_dir().expect("Could not find OS specific dirs!");
+    app_data_path.push(std::env!("CARGO_CRATE_NAME"));
+    if !app_data_path.exists() {
+        let result = fs::create_dir_all(&app_data_path);
+        assert!(result.is_ok());
+    }
+    app_data_path
 }
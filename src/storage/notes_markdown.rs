@@ -0,0 +1,243 @@
+//! The Markdown dialect of `notes.md`, see `docs/notes.md`:
+//!
+//! * The first line is the marker `<!-- notes.md escaped -->`, files without it are read unescaped.
+//! * Notes keep their order, consecutive notes of one context share a `# Notes of '<context>'`
+//!   section. General notes come without section if they are first, otherwise below `# Notes of ''`.
+//! * Every note is one line, `* [] text` for open and `* [x] text` for done notes. Anchored notes
+//!   start with `` `lines old:3-4` `` or `` `lines 3-4 (outdated)` ``, outdated notes without
+//!   anchor with `` `outdated` ``.
+//! * Texts and contexts are escaped, so that every note fits into one line and survives trimming:
+//!   `\\`, `\n`, `\r`, `\t`, `` \` `` at the start and `\u{..}` for other control characters and
+//!   whitespace at the start or end. Unknown escapes are kept as they are.
+
+use std::fmt::Write;
+
+use crate::storage::repository_storage::{LineRange, NoteStore};
+
+const LINE_RANGE_PREFIX: &str = "`lines ";
+const OUTDATED_SUFFIX: &str = " (outdated)";
+const OUTDATED_MARKER: &str = "`outdated`";
+/// Marks files with escaped texts, invisible when rendered and ignored by older versions.
+const ESCAPED_MARKER: &str = "<!-- notes.md escaped -->";
+
+fn escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for (index, c) in text.char_indices() {
+        let is_edge = index == 0 || index + c.len_utf8() == text.len();
+        match c {
+            '\\' => escaped.push_str("\\\\"),
+            '\n' => escaped.push_str("\\n"),
+            '\r' => escaped.push_str("\\r"),
+            '\t' => escaped.push_str("\\t"),
+            '`' if index == 0 => escaped.push_str("\\`"),
+            c if c.is_control() || (is_edge && c.is_whitespace()) => {
+                let _ = write!(escaped, "\\u{{{:x}}}", c as u32);
+            }
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+fn unescape(text: &str) -> String {
+    let mut unescaped = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(pos) = rest.find('\\') {
+        unescaped.push_str(&rest[..pos]);
+        rest = &rest[pos + 1..];
+        let (c, escape_len) = match rest.chars().next() {
+            Some('\\') => ('\\', 1),
+            Some('n') => ('\n', 1),
+            Some('r') => ('\r', 1),
+            Some('t') => ('\t', 1),
+            Some('`') => ('`', 1),
+            Some('u') => unescape_code_point(rest).unwrap_or(('\\', 0)),
+            // Unknown escapes, e.g. of files written before escaping, stay as they are.
+            _ => ('\\', 0),
+        };
+        unescaped.push(c);
+        rest = &rest[escape_len..];
+    }
+    unescaped.push_str(rest);
+    unescaped
+}
+
+/// Returns the char of `u{20}` and the length of the escape.
+fn unescape_code_point(escape: &str) -> Option<(char, usize)> {
+    let (code, _) = escape.strip_prefix("u{")?.split_once('}')?;
+    let c = char::from_u32(u32::from_str_radix(code, 16).ok()?)?;
+    Some((c, code.len() + 3))
+}
+
+fn note_to_line(note: &NoteStore) -> String {
+    let is_done = if note.is_done { "x" } else { "" };
+    let outdated = if note.is_outdated { OUTDATED_SUFFIX } else { "" };
+    match &note.line_range {
+        Some(line_range) => format!("* [{}] {}{}{}` {}", is_done, LINE_RANGE_PREFIX, line_range, outdated, escape(&note.text)),
+        None if note.is_outdated => format!("* [{}] {} {}", is_done, OUTDATED_MARKER, escape(&note.text)),
+        None => format!("* [{}] {}", is_done, escape(&note.text)),
+    }
+}
+
+/// Texts of files written before escaping are taken as they are.
+fn unescape_if(text: &str, is_escaped: bool) -> String {
+    if is_escaped { unescape(text) } else { text.to_string() }
+}
+
+/// Parses `* [] text`, `* [ ] text` or `* [x] text` with optional anchor.
+fn line_to_note(line: &str, context: &str, is_escaped: bool) -> Option<NoteStore> {
+    let rest = line.strip_prefix('*')?.trim_start().strip_prefix('[')?;
+    let (is_done, rest) = if let Some(rest) = rest.strip_prefix(']').or_else(|| rest.strip_prefix(" ]")) {
+        (false, rest)
+    } else {
+        (true, rest.strip_prefix("x]").or_else(|| rest.strip_prefix("X]"))?)
+    };
+    let text = rest.strip_prefix(' ').unwrap_or(rest);
+
+    let (line_range, is_outdated, text) = match text.strip_prefix(LINE_RANGE_PREFIX).and_then(|anchor| anchor.split_once('`')) {
+        Some((line_range, rest)) => {
+            let (line_range, is_outdated) = match line_range.strip_suffix(OUTDATED_SUFFIX) {
+                Some(line_range) => (line_range, true),
+                None => (line_range, false),
+            };
+            match line_range.parse::<LineRange>() {
+                Ok(line_range) => (Some(line_range), is_outdated, rest.strip_prefix(' ').unwrap_or(rest)),
+                Err(_) => (None, false, text),
+            }
+        }
+        None => match text.strip_prefix(OUTDATED_MARKER) {
+            Some(rest) => (None, true, rest.strip_prefix(' ').unwrap_or(rest)),
+            None => (None, false, text),
+        },
+    };
+
+    Some(NoteStore {
+        text: unescape_if(text, is_escaped),
+        context: context.to_string(),
+        line_range,
+        is_outdated,
+        is_done,
+    })
+}
+
+/// Parses `# Notes of '<context>'`, the context itself may contain quotes.
+fn line_to_context(line: &str, is_escaped: bool) -> Option<String> {
+    let start = line.find('\'')? + 1;
+    let end = line.rfind('\'')?;
+    Some(unescape_if(line.get(start..end)?, is_escaped))
+}
+
+pub fn notes_to_markdown(notes: &[&NoteStore]) -> String {
+    let mut markdown = String::new();
+    let _ = writeln!(markdown, "{}", ESCAPED_MARKER);
+
+    let mut context = "";
+    for (index, note) in notes.iter().enumerate() {
+        if note.context != context || (index == 0 && !note.context.is_empty()) {
+            let _ = writeln!(markdown, "\n# Notes of '{}'", escape(&note.context));
+            context = &note.context;
+        }
+        let _ = writeln!(markdown, "{}", note_to_line(note));
+    }
+    markdown
+}
+
+/// Reads the notes in the order of `notes_to_markdown`, lines that are neither note nor section
+/// are ignored.
+pub fn notes_from_markdown(markdown: &str) -> Result<Vec<NoteStore>, String> {
+    let mut notes = Vec::new();
+    let mut context = String::new();
+    let is_escaped = markdown.lines().next().is_some_and(|line| line.trim() == ESCAPED_MARKER);
+
+    for (index, line) in markdown.lines().enumerate() {
+        let line = line.trim();
+        if line.starts_with('#') {
+            context = line_to_context(line, is_escaped).ok_or_else(|| format!("Could not parse file context in line {}!", index + 1))?;
+        } else if line.starts_with('*') {
+            notes.push(line_to_note(line, &context, is_escaped).ok_or_else(|| format!("Could not parse list item in line {}!", index + 1))?);
+        }
+    }
+    Ok(notes)
+}
+
+#[cfg(test)]
+mod tests {
+    use proptest::prelude::*;
+
+    use super::*;
+    use crate::storage::repository_storage::DiffSide;
+
+    fn note_strategy() -> impl Strategy<Value = NoteStore> {
+        let context = prop_oneof![Just(String::new()), Just("src/main.rs".to_string()), any::<String>()];
+        let line_range = proptest::option::of((any::<bool>(), 1..100_000u32, 1..100_000u32));
+        (any::<String>(), context, line_range, any::<bool>(), any::<bool>()).prop_map(|(text, context, line_range, is_outdated, is_done)| NoteStore {
+            text,
+            context,
+            line_range: line_range.map(|(is_old, start, end)| LineRange::new(if is_old { DiffSide::Old } else { DiffSide::New }, start, end)),
+            is_outdated,
+            is_done,
+        })
+    }
+
+    proptest! {
+        #[test]
+        fn test_notes_round_trip(notes in proptest::collection::vec(note_strategy(), 0..8)) {
+            let markdown = notes_to_markdown(&notes.iter().collect::<Vec<_>>());
+            prop_assert_eq!(notes_from_markdown(&markdown), Ok(notes));
+        }
+    }
+
+    #[test]
+    fn test_notes_from_markdown() {
+        let notes = [
+            NoteStore {
+                text: "first line\n* [x] second line\n# third line".to_string(),
+                ..Default::default()
+            },
+            NoteStore {
+                text: "] [brackets] and `code` ".to_string(),
+                context: "src/it's.rs".to_string(),
+                is_done: true,
+                ..Default::default()
+            },
+            NoteStore {
+                text: "`lines 3` isn't an anchor".to_string(),
+                context: "src/it's.rs".to_string(),
+                ..Default::default()
+            },
+            NoteStore {
+                text: "general note".to_string(),
+                ..Default::default()
+            },
+        ];
+        let markdown = notes_to_markdown(&notes.iter().collect::<Vec<_>>());
+        assert_eq!(
+            markdown,
+            "<!-- notes.md escaped -->\n\
+             * [] first line\\n* [x] second line\\n# third line\n\
+             \n# Notes of 'src/it's.rs'\n\
+             * [x] ] [brackets] and `code`\\u{20}\n\
+             * [] \\`lines 3` isn't an anchor\n\
+             \n# Notes of ''\n\
+             * [] general note\n"
+        );
+        assert_eq!(notes_from_markdown(&markdown), Ok(notes.to_vec()));
+
+        // Files of older versions and hand written task lists are not escaped.
+        let notes = notes_from_markdown(
+            "* [] C:\\new\\table \\\\server \\u{20}\n* [ ] open  \n* [X] `lines old:3-4 (outdated)` done\nsome text\n# Notes of 'a\\tb'\n* [] \\d+\n",
+        )
+        .expect("Should parse");
+        assert_eq!(notes[0].text, "C:\\new\\table \\\\server \\u{20}");
+        assert_eq!(notes[1].text, "open");
+        assert!(notes[2].is_done && notes[2].is_outdated);
+        assert_eq!(notes[2].line_range, Some(LineRange::new(DiffSide::Old, 3, 4)));
+        assert_eq!(notes[3].context, "a\\tb");
+        assert_eq!(notes[3].text, "\\d+");
+
+        assert_eq!(
+            notes_from_markdown("# Notes of src\n"),
+            Err("Could not parse file context in line 1!".to_string())
+        );
+    }
+}
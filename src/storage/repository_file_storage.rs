@@ -1,24 +1,91 @@
-use std::collections::BTreeMap;
-use std::fs::{self, File, OpenOptions};
-use std::io::Write;
+use std::ffi::OsStr;
+use std::fs::{self, File};
+use std::io::{ErrorKind, Write};
 use std::path::{Path, PathBuf};
+use std::sync::Arc;
 
+use serde_derive::{Deserialize, Serialize};
 use toml::{Table, Value};
 
-use crate::storage::repository_storage::{DiffRangeStore, FileDiffStore, NoteStore, ReviewName, ReviewStore, StorageError};
+use crate::storage::file_format::{self, FORMAT_VERSION};
+use crate::storage::file_guard::{ChangeTracker, StorageLock};
+use crate::storage::notes_markdown;
+use crate::storage::repository_storage::{
+    DiffMode, DiffRangeStore, FileDiffStore, IterationStore, LoadedRepositories, NoteStore, ReviewEventStore, ReviewName, ReviewStore, SnapshotStore,
+    StorageError,
+};
 use crate::storage::{RepositoryName, RepositoryStore, ReviewHelperStorage, StorageResult};
 
 const NOTE_FILE_NAME: &str = "notes.md";
-
+/// The activity log, one `[[events]]` table per event below the `version` key.
+const ACTIVITY_FILE_NAME: &str = "activity.toml";
+/// Every snapshot is a directory like a review, `snapshots/<snapshot>/<snapshot>.toml` and `notes.md`.
+const SNAPSHOT_DIR_NAME: &str = "snapshots";
+const TEMP_SUFFIX: &str = ".tmp";
+const BACKUP_SUFFIX: &str = ".bak";
+const BROKEN_SUFFIX: &str = ".broken";
+
+/// Every operation holds the storage lock and files changed by another instance since they were
+/// loaded are not overwritten, `StorageError::Conflict` is returned instead.
 #[derive(Debug, Default, Clone)]
 pub struct ReviewHelperFileStorage {
     storage_path: PathBuf,
+    change_tracker: Arc<ChangeTracker>,
 }
 
 impl ReviewHelperFileStorage {
     pub fn new(path: PathBuf) -> Self {
-        Self { storage_path: path }
+        Self {
+            storage_path: path,
+            change_tracker: Arc::default(),
+        }
     }
+
+    /// Migrates the review files of a repository before its repository file is loaded, so the
+    /// repository data they carry ends up in the loaded `RepositoryStore`.
+    fn migrate_review_files(&self, repository_file_path: &Path) {
+        let Some(review_file_paths) = repository_file_path.parent().and_then(|dir| fs::read_dir(dir).ok()).map(|entries| {
+            entries
+                .filter_map(|entry| {
+                    let review_dir_path = entry.ok()?.path();
+                    let review_file_path = review_dir_path.join(format!("{}.toml", review_dir_path.file_name()?.to_str()?));
+                    if review_file_path.is_file() { Some(review_file_path) } else { None }
+                })
+                .collect::<Vec<_>>()
+        }) else {
+            return;
+        };
+        for review_file_path in review_file_paths {
+            let migrated = read_table(&review_file_path)
+                .and_then(|mut table| migrate_review_file(&review_file_path, &mut table, repository_file_path, &self.change_tracker));
+            if let Err(e) = migrated {
+                log::warn!("Could not migrate {}: {}", review_file_path.display(), e);
+            }
+        }
+    }
+
+    fn lock(&self) -> StorageResult<StorageLock> {
+        StorageLock::lock(&self.storage_path)
+    }
+
+    /// The directory of an existing review.
+    fn review_dir_path(&self, repository_name: &RepositoryName, review_name: &ReviewName) -> StorageResult<PathBuf> {
+        let repository_path = self.storage_path.join(repository_name.as_str());
+        if !repository_path.exists() {
+            return Err(StorageError::RepositoryNotFound(repository_path.to_string_lossy().to_string()));
+        }
+        let review_dir_path = repository_path.join(review_name.as_str());
+        if !review_dir_path.exists() {
+            return Err(StorageError::ReviewNotFound(review_dir_path.to_string_lossy().to_string()));
+        }
+        Ok(review_dir_path)
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+struct ActivityFile {
+    #[serde(default)]
+    events: Vec<ReviewEventStore>,
 }
 
 fn is_toml(path: &Path) -> bool {
@@ -28,11 +95,198 @@ fn is_toml(path: &Path) -> bool {
     }
 }
 
+fn read_table(path: &Path) -> StorageResult<Table> {
+    let contents = fs::read_to_string(path)?;
+    toml::from_str(&contents).map_err(|e| StorageError::Deserialize(e.to_string()))
+}
+
+fn file_error(path: &Path) -> impl FnOnce(StorageError) -> StorageError + '_ {
+    move |e| StorageError::File(path.display().to_string(), Box::new(e))
+}
+
+fn required_str<'a>(table: &'a Table, prefix: &str, key: &str) -> StorageResult<&'a str> {
+    let value = table.get(key).ok_or_else(|| StorageError::MissingKey(format!("{}{}", prefix, key)))?;
+    value.as_str().ok_or_else(|| StorageError::InvalidValue(format!("{}{}", prefix, key), "string"))
+}
+
+fn optional_str<'a>(table: &'a Table, key: &str) -> StorageResult<&'a str> {
+    match table.get(key) {
+        None => Ok(""),
+        Some(value) => value.as_str().ok_or_else(|| StorageError::InvalidValue(key.to_string(), "string")),
+    }
+}
+
+fn required_bool(table: &Table, prefix: &str, key: &str) -> StorageResult<bool> {
+    let value = table.get(key).ok_or_else(|| StorageError::MissingKey(format!("{}{}", prefix, key)))?;
+    value
+        .as_bool()
+        .ok_or_else(|| StorageError::InvalidValue(format!("{}{}", prefix, key), "boolean"))
+}
+
+fn optional_string(table: &Table, prefix: &str, key: &str) -> StorageResult<Option<String>> {
+    table
+        .get(key)
+        .map(|value| {
+            value
+                .as_str()
+                .map(str::to_string)
+                .ok_or_else(|| StorageError::InvalidValue(format!("{}{}", prefix, key), "string"))
+        })
+        .transpose()
+}
+
+fn optional_string_list(table: &Table, prefix: &str, key: &str) -> StorageResult<Vec<String>> {
+    let Some(value) = table.get(key) else {
+        return Ok(Vec::new());
+    };
+    let invalid = || StorageError::InvalidValue(format!("{}{}", prefix, key), "array of strings");
+    value
+        .as_array()
+        .ok_or_else(invalid)?
+        .iter()
+        .map(|item| item.as_str().map(str::to_string).ok_or_else(invalid))
+        .collect()
+}
+
+fn optional_bool(table: &Table, prefix: &str, key: &str) -> StorageResult<bool> {
+    match table.get(key) {
+        None => Ok(false),
+        Some(value) => value
+            .as_bool()
+            .ok_or_else(|| StorageError::InvalidValue(format!("{}{}", prefix, key), "boolean")),
+    }
+}
+
+fn write_table(path: &Path, table: &Table) -> StorageResult<()> {
+    let contents = toml::to_string_pretty(table).map_err(|e| StorageError::Serialize(e.to_string()))?;
+    write_file_atomic(path, contents.as_bytes())
+}
+
+fn suffixed_path(path: &Path, suffix: impl AsRef<OsStr>) -> PathBuf {
+    let mut suffixed_path = path.as_os_str().to_owned();
+    suffixed_path.push(suffix);
+    PathBuf::from(suffixed_path)
+}
+
+/// Saves are written with the previous generation kept as `<file>.bak`, migrations keep their own backup.
+fn save_table(path: &Path, table: &Table) -> StorageResult<()> {
+    keep_previous_generation(path)?;
+    write_table(path, table)
+}
+
+fn keep_previous_generation(path: &Path) -> StorageResult<()> {
+    if !path.exists() {
+        return Ok(());
+    }
+    let backup_path = suffixed_path(path, BACKUP_SUFFIX);
+    if backup_path.exists() {
+        fs::remove_file(&backup_path)?;
+    }
+    // A hard link keeps the previous generation without copying it, the atomic write replaces only `path`.
+    if fs::hard_link(path, &backup_path).is_err() {
+        fs::copy(path, &backup_path)?;
+    }
+    Ok(())
+}
+
+/// Writes `contents` into a synced temporary file and renames it to `path`, so that `path` always
+/// holds either the previous or the new contents.
+fn write_file_atomic(path: &Path, contents: &[u8]) -> StorageResult<()> {
+    let temp_path = suffixed_path(path, TEMP_SUFFIX);
+    {
+        let mut file = File::create(&temp_path)?;
+        file.write_all(contents)?;
+        file.sync_all()?;
+    }
+    fs::rename(&temp_path, path)?;
+
+    // Persists the rename, directories can't be opened on every platform.
+    if let Some(directory) = path.parent()
+        && let Ok(directory) = File::open(directory)
+    {
+        let _ = directory.sync_all();
+    }
+    Ok(())
+}
+
+/// Files that were only partially written, e.g. by a crash of an older version.
+fn is_corrupt(error: &StorageError) -> bool {
+    match error {
+        StorageError::Deserialize(_) | StorageError::MissingKey(_) | StorageError::InvalidValue(_, _) => true,
+        StorageError::Io(e) => e.kind() == ErrorKind::InvalidData,
+        _ => false,
+    }
+}
+
+/// Loads `path` and falls back to its previous generation if it is corrupt. The backup takes the
+/// place of the corrupt file, which is kept as `<file>.broken`, so that the next save doesn't
+/// replace the backup with the corrupt file.
+fn load_with_backup<T>(path: &Path, load: impl Fn(&Path) -> StorageResult<T>) -> StorageResult<T> {
+    let error = match load(path) {
+        Ok(loaded) => return Ok(loaded),
+        Err(e) => e,
+    };
+    let backup_path = suffixed_path(path, BACKUP_SUFFIX);
+    if !is_corrupt(&error) || !backup_path.is_file() {
+        return Err(error);
+    }
+
+    log::warn!("Could not load {}: {}, restore {}", path.display(), error, backup_path.display());
+    fs::rename(path, suffixed_path(path, BROKEN_SUFFIX))?;
+    fs::copy(&backup_path, path)?;
+    load(path).map_err(|_| error)
+}
+
+/// Keeps the file as it was before a migration, e.g. `fancy_ui.toml.v1.bak`.
+fn backup_file(path: &Path, version: i64) -> StorageResult<()> {
+    let mut backup_path = path.as_os_str().to_owned();
+    backup_path.push(format!(".v{}.bak", version));
+    fs::copy(path, backup_path)?;
+    Ok(())
+}
+
+fn migrate_repository_file(path: &Path, table: &mut Table) -> StorageResult<()> {
+    let version = file_format::format_version(table)?;
+    if file_format::migrate_repository_table(table)? {
+        log::info!("Migrate {} from version {} to {}", path.display(), version, FORMAT_VERSION);
+        backup_file(path, version)?;
+        write_table(path, table)?;
+    }
+    Ok(())
+}
+
+/// Review files of older versions may carry data of the repository file, so both are migrated together.
+/// The rewritten repository file is recorded, it is no change of another instance.
+fn migrate_review_file(path: &Path, table: &mut Table, repository_file_path: &Path, change_tracker: &ChangeTracker) -> StorageResult<()> {
+    let version = file_format::format_version(table)?;
+    if version == FORMAT_VERSION {
+        return Ok(());
+    }
+    log::info!("Migrate {} from version {} to {}", path.display(), version, FORMAT_VERSION);
+
+    let mut repository_table = if repository_file_path.exists() {
+        read_table(repository_file_path)?
+    } else {
+        Table::new()
+    };
+    let previous_repository_table = repository_table.clone();
+    file_format::migrate_review_table(table, &mut repository_table)?;
+
+    if repository_file_path.exists() && repository_table != previous_repository_table {
+        backup_file(repository_file_path, file_format::format_version(&previous_repository_table)?)?;
+        write_table(repository_file_path, &repository_table)?;
+        change_tracker.record(repository_file_path);
+    }
+    backup_file(path, version)?;
+    write_table(path, table)
+}
+
 impl ReviewHelperStorage for ReviewHelperFileStorage {
-    fn load_repositories(&self) -> StorageResult<Vec<RepositoryStore>> {
+    fn load_repositories(&self) -> StorageResult<LoadedRepositories> {
         if !self.storage_path.exists() {
-            return Ok(Vec::new());
+            return Ok(LoadedRepositories::default());
         }
+        let _lock = self.lock()?;
 
         let nested_directories = fs::read_dir(&self.storage_path)?
             .filter_map(|entry| {
@@ -51,35 +305,25 @@ impl ReviewHelperStorage for ReviewHelperFileStorage {
             })
             .collect::<Vec<_>>();
 
-        let repositories = tomls
-            .into_iter()
-            .filter_map(|toml| {
-                let contents = fs::read_to_string(&toml).ok()?;
-                let table = contents.parse::<Table>().ok()?;
-                let mut repository_store = RepositoryStore::default();
-                if let Some(path) = table["path"].as_str() {
-                    repository_store.path = PathBuf::from(path);
-                }
-                if let Some(first_commit) = table["first_commit"].as_str() {
-                    repository_store.first_commit = first_commit.to_string();
+        let mut loaded_repositories = LoadedRepositories::default();
+        for toml in tomls {
+            self.migrate_review_files(&toml);
+            match load_with_backup(&toml, load_repository_file).map_err(file_error(&toml)) {
+                Ok(repository_store) => {
+                    self.change_tracker.record(&toml);
+                    loaded_repositories.repositories.push(repository_store);
                 }
-                if let Some(name) = table["name"].as_str() {
-                    repository_store.name = name.into();
+                Err(e) => {
+                    log::error!("Skip repository: {}", e);
+                    loaded_repositories.errors.push(e);
                 }
-                if let Some(base_branch) = table["base_branch"].as_str() {
-                    repository_store.base_branch = base_branch.to_string();
-                }
-                Some(repository_store)
-            })
-            .collect::<Vec<_>>();
-
-        Ok(repositories)
+            }
+        }
+        Ok(loaded_repositories)
     }
 
     fn save_repository(&self, repository_store: &RepositoryStore) -> StorageResult<()> {
-        if !self.storage_path.exists() {
-            fs::create_dir_all(&self.storage_path)?;
-        }
+        let _lock = self.lock()?;
 
         let mut repository_sub_dir = self.storage_path.join(repository_store.name.as_str());
 
@@ -91,28 +335,31 @@ impl ReviewHelperStorage for ReviewHelperFileStorage {
         repository_sub_dir.set_extension("toml");
 
         let mut table = Table::new();
+        file_format::set_format_version(&mut table);
         table.insert("path".to_string(), Value::String(repository_store.path.to_str().unwrap_or_default().into()));
         table.insert("first_commit".to_string(), Value::String(repository_store.first_commit.clone()));
         table.insert("name".to_string(), Value::String(String::from(repository_store.name.as_str())));
         table.insert("base_branch".to_string(), Value::String(String::from(repository_store.base_branch.as_str())));
 
-        let mut file = File::create(&repository_sub_dir)?;
-
-        let contents = toml::to_string_pretty(&table).map_err(|e| StorageError::Serialize(e.to_string()))?;
-        file.write_all(contents.as_bytes())?;
+        self.change_tracker.check(&repository_sub_dir)?;
+        save_table(&repository_sub_dir, &table)?;
+        self.change_tracker.record(&repository_sub_dir);
         Ok(())
     }
 
     fn delete_repository(&self, repository_name: &RepositoryName) -> StorageResult<()> {
+        let _lock = self.lock()?;
         let repository_path = self.storage_path.join(repository_name.as_str());
         if !repository_path.exists() {
             return Err(StorageError::RepositoryNotFound(repository_path.to_string_lossy().to_string()));
         }
-        fs::remove_dir_all(repository_path)?;
+        fs::remove_dir_all(&repository_path)?;
+        self.change_tracker.forget(&repository_path);
         Ok(())
     }
 
     fn load_review_names(&self, repository_name: &RepositoryName) -> StorageResult<Vec<ReviewName>> {
+        let _lock = self.lock()?;
         let repository_path = self.storage_path.join(PathBuf::from(String::from(repository_name)));
 
         if !repository_path.exists() {
@@ -136,6 +383,7 @@ impl ReviewHelperStorage for ReviewHelperFileStorage {
     }
 
     fn load_review(&self, repository_name: &RepositoryName, review_name: &ReviewName) -> StorageResult<Option<ReviewStore>> {
+        let _lock = self.lock()?;
         let file_name = PathBuf::from(format!("{}.toml", review_name.as_str()));
         let repository_dir_path = self.storage_path.join(repository_name.as_str());
         if !repository_dir_path.exists() {
@@ -146,47 +394,29 @@ impl ReviewHelperStorage for ReviewHelperFileStorage {
         if !review_file_path.exists() {
             return Ok(None);
         }
-        let contents = fs::read_to_string(review_file_path)?;
-        let table: Table = toml::from_str(&contents).map_err(|e| StorageError::Deserialize(e.to_string()))?;
+        let repository_file_path = repository_dir_path.join(format!("{}.toml", repository_name.as_str()));
+        let mut review_store = load_with_backup(&review_file_path, |path| load_review_file(path, &repository_file_path, &self.change_tracker))
+            .map_err(file_error(&review_file_path))?;
 
-        let mut diff_range = DiffRangeStore::default();
-        if let Some(start) = table["start_diff"].as_str() {
-            diff_range.start = start.to_string();
-        }
-        if let Some(end) = table["end_diff"].as_str() {
-            diff_range.end = end.to_string();
-        }
-
-        let mut review_store = ReviewStore {
-            diff_range,
-            ..Default::default()
-        };
-
-        if table.contains_key("diff_files")
-            && let Some(diff_files) = table["diff_files"].as_array()
-        {
-            for diff_file in diff_files {
-                if let Some(diff_file_table) = diff_file.as_table() {
-                    let mut file_diff_item = FileDiffStore::default();
-                    if let Some(file_name) = diff_file_table["file_name"].as_str() {
-                        file_diff_item.file_path = PathBuf::from(file_name);
-                    }
-                    if let Some(is_reviewed) = diff_file_table["is_reviewed"].as_bool() {
-                        file_diff_item.is_reviewed = is_reviewed;
-                    }
-                    review_store.file_diff_list.push(file_diff_item);
-                }
-            }
-        }
         let note_file = review_dir_path.join(NOTE_FILE_NAME);
         if note_file.exists() {
-            review_store.notes = load_notes(note_file)?;
+            review_store.notes = load_with_backup(&note_file, load_notes).map_err(file_error(&note_file))?;
         }
+        self.change_tracker.record(&review_file_path);
+        self.change_tracker.record(&note_file);
 
         Ok(Some(review_store))
     }
 
+    fn is_review_changed(&self, repository_name: &RepositoryName, review_name: &ReviewName) -> StorageResult<bool> {
+        let _lock = self.lock()?;
+        let review_dir_path = self.storage_path.join(repository_name.as_str()).join(review_name.as_str());
+        let review_file_path = review_dir_path.join(format!("{}.toml", review_name.as_str()));
+        Ok(self.change_tracker.is_changed(&review_file_path) || self.change_tracker.is_changed(&review_dir_path.join(NOTE_FILE_NAME)))
+    }
+
     fn delete_review(&self, repository_name: &RepositoryName, review_name: &ReviewName) -> StorageResult<()> {
+        let _lock = self.lock()?;
         let repository_path = self.storage_path.join(repository_name.as_str());
         if !repository_path.exists() {
             return Err(StorageError::RepositoryNotFound(repository_path.to_string_lossy().to_string()));
@@ -196,11 +426,13 @@ impl ReviewHelperStorage for ReviewHelperFileStorage {
             return Err(StorageError::ReviewNotFound(review_dir_path.to_string_lossy().to_string()));
         }
 
-        fs::remove_dir_all(review_dir_path)?;
+        fs::remove_dir_all(&review_dir_path)?;
+        self.change_tracker.forget(&review_dir_path);
         Ok(())
     }
 
     fn rename_review(&self, repository_name: &RepositoryName, old_review_name: &ReviewName, new_review_name: &ReviewName) -> StorageResult<()> {
+        let _lock = self.lock()?;
         let repository_path = self.storage_path.join(repository_name.as_str());
         if !repository_path.exists() {
             return Err(StorageError::RepositoryNotFound(repository_path.to_string_lossy().to_string()));
@@ -215,13 +447,20 @@ impl ReviewHelperStorage for ReviewHelperFileStorage {
         let new_review_dir_path = repository_path.join(new_review_name.as_str());
         let new_file_name = PathBuf::from(format!("{}.toml", new_review_name.as_str()));
 
-        fs::rename(&old_review_file_path, old_review_dir_path.join(new_file_name))?;
+        let new_review_file_path = old_review_dir_path.join(new_file_name);
+        let old_backup_path = suffixed_path(&old_review_file_path, BACKUP_SUFFIX);
+        if old_backup_path.exists() {
+            fs::rename(&old_backup_path, suffixed_path(&new_review_file_path, BACKUP_SUFFIX))?;
+        }
+        fs::rename(&old_review_file_path, new_review_file_path)?;
         fs::rename(&old_review_dir_path, &new_review_dir_path)?;
+        self.change_tracker.forget(&old_review_dir_path);
 
         Ok(())
     }
 
     fn save_review_notes(&self, repository_name: &RepositoryName, review_name: &ReviewName, notes: &[&NoteStore]) -> StorageResult<()> {
+        let _lock = self.lock()?;
         let repository_path = self.storage_path.join(repository_name.as_str());
         if !repository_path.exists() {
             return Err(StorageError::RepositoryNotFound(repository_path.to_string_lossy().to_string()));
@@ -232,7 +471,10 @@ impl ReviewHelperStorage for ReviewHelperFileStorage {
         }
 
         let note_file = review_dir_path.join(NOTE_FILE_NAME);
-        save_notes(notes, note_file)
+        self.change_tracker.check(&note_file)?;
+        save_notes(notes, &note_file)?;
+        self.change_tracker.record(&note_file);
+        Ok(())
     }
 
     fn save_review_file_diffs(
@@ -242,6 +484,7 @@ impl ReviewHelperStorage for ReviewHelperFileStorage {
         diff_range: &DiffRangeStore,
         file_diffs: &[&FileDiffStore],
     ) -> StorageResult<()> {
+        let _lock = self.lock()?;
         let file_name = PathBuf::from(format!("{}.toml", review_name.as_str()));
         let repository_path = self.storage_path.join(repository_name.as_str());
         if !repository_path.exists() {
@@ -252,100 +495,278 @@ impl ReviewHelperStorage for ReviewHelperFileStorage {
             fs::create_dir(&review_dir_path)?;
         }
         let review_file_path = review_dir_path.join(file_name);
+        let table = review_table(diff_range, file_diffs);
 
-        let mut table = Table::new();
-        table.insert("start_diff".to_string(), Value::String(diff_range.start.clone()));
-        table.insert("end_diff".to_string(), Value::String(diff_range.end.clone()));
+        self.change_tracker.check(&review_file_path)?;
+        save_table(&review_file_path, &table)?;
+        self.change_tracker.record(&review_file_path);
+        Ok(())
+    }
+
+    fn append_review_events(&self, repository_name: &RepositoryName, review_name: &ReviewName, events: &[ReviewEventStore]) -> StorageResult<()> {
+        let _lock = self.lock()?;
+        let activity_file = self.review_dir_path(repository_name, review_name)?.join(ACTIVITY_FILE_NAME);
+        append_events(&activity_file, events).map_err(file_error(&activity_file))
+    }
+
+    fn load_review_events(&self, repository_name: &RepositoryName, review_name: &ReviewName) -> StorageResult<Vec<ReviewEventStore>> {
+        let _lock = self.lock()?;
+        let repository_path = self.storage_path.join(repository_name.as_str());
+        if !repository_path.exists() {
+            return Err(StorageError::RepositoryNotFound(repository_path.to_string_lossy().to_string()));
+        }
+        let activity_file = repository_path.join(review_name.as_str()).join(ACTIVITY_FILE_NAME);
+        if !activity_file.exists() {
+            return Ok(Vec::new());
+        }
+        load_events(&activity_file).map_err(file_error(&activity_file))
+    }
+
+    fn save_review_snapshot(&self, repository_name: &RepositoryName, review_name: &ReviewName, snapshot: &SnapshotStore) -> StorageResult<()> {
+        let _lock = self.lock()?;
+        let review_dir_path = self.review_dir_path(repository_name, review_name)?;
+        let snapshot_dir_path = review_dir_path.join(SNAPSHOT_DIR_NAME).join(&snapshot.name);
+        fs::create_dir_all(&snapshot_dir_path)?;
+
+        let mut table = review_table(&snapshot.review.diff_range, &snapshot.review.file_diff_list.iter().collect::<Vec<_>>());
+        table.insert("time".to_string(), Value::String(snapshot.time.clone()));
+        let snapshot_file = snapshot_dir_path.join(format!("{}.toml", snapshot.name));
+        write_table(&snapshot_file, &table).map_err(file_error(&snapshot_file))?;
+
+        let note_file = snapshot_dir_path.join(NOTE_FILE_NAME);
+        let notes = notes_markdown::notes_to_markdown(&snapshot.review.notes.iter().collect::<Vec<_>>());
+        write_file_atomic(&note_file, notes.as_bytes()).map_err(file_error(&note_file))
+    }
 
-        let file_diff_list: Vec<Value> = file_diffs
+    fn load_review_snapshots(&self, repository_name: &RepositoryName, review_name: &ReviewName) -> StorageResult<Vec<SnapshotStore>> {
+        let _lock = self.lock()?;
+        let snapshots_path = self.review_dir_path(repository_name, review_name)?.join(SNAPSHOT_DIR_NAME);
+        if !snapshots_path.exists() {
+            return Ok(Vec::new());
+        }
+
+        let mut snapshots = Vec::new();
+        for entry in fs::read_dir(&snapshots_path)? {
+            let snapshot_dir_path = entry?.path();
+            let Some(name) = snapshot_dir_path.file_name().and_then(OsStr::to_str).map(str::to_string) else {
+                continue;
+            };
+            let snapshot_file = snapshot_dir_path.join(format!("{}.toml", name));
+            if !snapshot_file.is_file() {
+                continue;
+            }
+            snapshots.push(load_snapshot(&snapshot_dir_path, &snapshot_file, name).map_err(file_error(&snapshot_file))?);
+        }
+        snapshots.sort_by(|lhs, rhs| (&lhs.time, &lhs.name).cmp(&(&rhs.time, &rhs.name)));
+        Ok(snapshots)
+    }
+
+    fn delete_review_snapshot(&self, repository_name: &RepositoryName, review_name: &ReviewName, snapshot_name: &str) -> StorageResult<()> {
+        let _lock = self.lock()?;
+        let snapshot_dir_path = self.review_dir_path(repository_name, review_name)?.join(SNAPSHOT_DIR_NAME).join(snapshot_name);
+        if !snapshot_dir_path.exists() {
+            return Err(StorageError::SnapshotNotFound(snapshot_name.to_string()));
+        }
+        fs::remove_dir_all(&snapshot_dir_path)?;
+        Ok(())
+    }
+}
+
+fn load_repository_file(path: &Path) -> StorageResult<RepositoryStore> {
+    let mut table = read_table(path)?;
+    migrate_repository_file(path, &mut table)?;
+    Ok(RepositoryStore {
+        path: PathBuf::from(required_str(&table, "", "path")?),
+        name: required_str(&table, "", "name")?.into(),
+        first_commit: optional_str(&table, "first_commit")?.to_string(),
+        base_branch: optional_str(&table, "base_branch")?.to_string(),
+    })
+}
+
+fn review_table(diff_range: &DiffRangeStore, file_diffs: &[&FileDiffStore]) -> Table {
+    let mut table = Table::new();
+    file_format::set_format_version(&mut table);
+    table.insert("start_diff".to_string(), Value::String(diff_range.start.clone()));
+    table.insert("end_diff".to_string(), Value::String(diff_range.end.clone()));
+    if diff_range.mode != DiffMode::Commits {
+        table.insert("diff_mode".to_string(), Value::String(diff_range.mode.to_string()));
+    }
+    if let Some(interdiff_iteration) = diff_range.interdiff_iteration {
+        table.insert("interdiff_iteration".to_string(), Value::Integer(interdiff_iteration as i64));
+    }
+    if !diff_range.iterations.is_empty() {
+        let iterations = diff_range
+            .iterations
             .iter()
-            .map(|file_diff_item| {
+            .map(|iteration| {
                 let mut table = Table::new();
-                table.insert("file_name".to_string(), Value::String(file_diff_item.file_path.to_string_lossy().to_string()));
-                table.insert("is_reviewed".to_string(), Value::Boolean(file_diff_item.is_reviewed));
+                table.insert("start_diff".to_string(), Value::String(iteration.start.clone()));
+                table.insert("end_diff".to_string(), Value::String(iteration.end.clone()));
                 Value::Table(table)
             })
             .collect();
-        table.insert("diff_files".to_string(), Value::Array(file_diff_list));
-
-        let mut file = File::create(&review_file_path)?;
+        table.insert("iterations".to_string(), Value::Array(iterations));
+    }
 
-        let contents = toml::to_string_pretty(&table).map_err(|e| StorageError::Deserialize(e.to_string()))?;
-        file.write_all(contents.as_bytes())?;
+    let file_diff_list: Vec<Value> = file_diffs
+        .iter()
+        .map(|file_diff_item| {
+            let mut table = Table::new();
+            table.insert("file_name".to_string(), Value::String(file_diff_item.file_path.to_string_lossy().to_string()));
+            table.insert("is_reviewed".to_string(), Value::Boolean(file_diff_item.is_reviewed));
+            if let Some(reviewed_blob) = &file_diff_item.reviewed_blob {
+                table.insert("reviewed_blob".to_string(), Value::String(reviewed_blob.clone()));
+            }
+            if file_diff_item.is_changed_since_review {
+                table.insert("is_changed_since_review".to_string(), Value::Boolean(true));
+            }
+            if !file_diff_item.reviewed_commits.is_empty() {
+                let reviewed_commits = file_diff_item.reviewed_commits.iter().map(|commit| Value::String(commit.clone())).collect();
+                table.insert("reviewed_commits".to_string(), Value::Array(reviewed_commits));
+            }
+            Value::Table(table)
+        })
+        .collect();
+    table.insert("diff_files".to_string(), Value::Array(file_diff_list));
+    table
+}
 
-        Ok(())
-    }
+fn load_review_file(path: &Path, repository_file_path: &Path, change_tracker: &ChangeTracker) -> StorageResult<ReviewStore> {
+    let mut table = read_table(path)?;
+    migrate_review_file(path, &mut table, repository_file_path, change_tracker)?;
+    review_from_table(&table)
 }
 
-fn load_notes(note_file: PathBuf) -> StorageResult<Vec<NoteStore>> {
-    let to_note = |line: &str| -> Option<(bool, String)> {
-        let pos = line.find("[")?;
-        let is_done = !line.get(pos + 1..)?.starts_with("]");
-        let text: String = if is_done {
-            let pos = line.find("]")?;
-            line.get(pos + 1..)?.trim().to_string()
-        } else {
-            line.get(pos + 2..)?.trim().to_string()
-        };
-        Some((is_done, text))
+fn review_from_table(table: &Table) -> StorageResult<ReviewStore> {
+    let mut iterations = Vec::new();
+    if let Some(iteration_values) = table.get("iterations") {
+        let iteration_values = iteration_values
+            .as_array()
+            .ok_or_else(|| StorageError::InvalidValue("iterations".to_string(), "array of tables"))?;
+        for (index, iteration) in iteration_values.iter().enumerate() {
+            let prefix = format!("iterations[{}].", index);
+            let iteration_table = iteration
+                .as_table()
+                .ok_or_else(|| StorageError::InvalidValue(format!("iterations[{}]", index), "table"))?;
+            iterations.push(IterationStore {
+                start: required_str(iteration_table, &prefix, "start_diff")?.to_string(),
+                end: required_str(iteration_table, &prefix, "end_diff")?.to_string(),
+            });
+        }
+    }
+    let interdiff_iteration = match table.get("interdiff_iteration") {
+        None => None,
+        Some(value) => Some(
+            value
+                .as_integer()
+                .and_then(|number| usize::try_from(number).ok())
+                .ok_or_else(|| StorageError::InvalidValue("interdiff_iteration".to_string(), "positive integer"))?,
+        ),
+    };
+    let mode = match optional_string(table, "", "diff_mode")? {
+        None => DiffMode::Commits,
+        Some(mode) => mode
+            .parse()
+            .map_err(|_| StorageError::InvalidValue("diff_mode".to_string(), "commits, staged, unstaged or working_tree"))?,
     };
-    let to_file = |line: &str| -> Option<String> {
-        let start = line.find("'")? + 1;
-        let end = line.rfind("'")?;
-        Some(line.get(start..end)?.to_string())
+    let diff_range = DiffRangeStore {
+        start: required_str(table, "", "start_diff")?.to_string(),
+        end: required_str(table, "", "end_diff")?.to_string(),
+        mode,
+        iterations,
+        interdiff_iteration,
     };
-    let buffer = fs::read_to_string(note_file)?;
-    let mut notes = Vec::new();
-    let iter = buffer.lines();
-    let mut context = String::new();
-
-    for line in iter {
-        let line = line.trim();
-        if line.starts_with("#") {
-            context = to_file(line).ok_or_else(|| StorageError::Deserialize("Could not parse file context!".to_string()))?;
-        } else if line.starts_with("*") {
-            let (is_done, text) = to_note(line).ok_or_else(|| StorageError::Deserialize("Could not parse list item!".to_string()))?;
-            notes.push(NoteStore {
-                text,
-                context: context.clone(),
-                is_done,
+
+    let mut file_diff_list = Vec::new();
+    if let Some(diff_files) = table.get("diff_files") {
+        let diff_files = diff_files
+            .as_array()
+            .ok_or_else(|| StorageError::InvalidValue("diff_files".to_string(), "array of tables"))?;
+        for (index, diff_file) in diff_files.iter().enumerate() {
+            let prefix = format!("diff_files[{}].", index);
+            let diff_file_table = diff_file
+                .as_table()
+                .ok_or_else(|| StorageError::InvalidValue(format!("diff_files[{}]", index), "table"))?;
+            file_diff_list.push(FileDiffStore {
+                file_path: PathBuf::from(required_str(diff_file_table, &prefix, "file_name")?),
+                is_reviewed: required_bool(diff_file_table, &prefix, "is_reviewed")?,
+                reviewed_blob: optional_string(diff_file_table, &prefix, "reviewed_blob")?,
+                is_changed_since_review: optional_bool(diff_file_table, &prefix, "is_changed_since_review")?,
+                reviewed_commits: optional_string_list(diff_file_table, &prefix, "reviewed_commits")?,
             });
         }
     }
-    Ok(notes)
+
+    Ok(ReviewStore {
+        diff_range,
+        file_diff_list,
+        ..Default::default()
+    })
 }
 
-fn save_notes(notes: &[&NoteStore], note_file: PathBuf) -> StorageResult<()> {
-    let mut general_notes = Vec::<String>::new();
-    let mut file_notes = BTreeMap::<String, Vec<String>>::new();
+fn load_snapshot(snapshot_dir_path: &Path, snapshot_file: &Path, name: String) -> StorageResult<SnapshotStore> {
+    let table = read_table(snapshot_file)?;
+    file_format::format_version(&table)?;
+    let mut review = review_from_table(&table)?;
+    let note_file = snapshot_dir_path.join(NOTE_FILE_NAME);
+    if note_file.exists() {
+        review.notes = load_notes(&note_file)?;
+    }
+    Ok(SnapshotStore {
+        name,
+        time: optional_str(&table, "time")?.to_string(),
+        review,
+    })
+}
 
-    let note_item_to_string = |item: &NoteStore| -> String { format!("* [{}] {}", if item.is_done { "x" } else { "" }, item.text) };
+fn load_notes(note_file: &Path) -> StorageResult<Vec<NoteStore>> {
+    let markdown = fs::read_to_string(note_file)?;
+    notes_markdown::notes_from_markdown(&markdown).map_err(StorageError::Deserialize)
+}
 
-    for item in notes {
-        let notes: &mut Vec<String> = if item.context.is_empty() {
-            &mut general_notes
-        } else {
-            file_notes.entry(item.context.to_string()).or_default()
-        };
-        notes.push(note_item_to_string(item));
-    }
-    let mut file = OpenOptions::new().create(true).truncate(true).write(true).open(note_file)?;
+fn save_notes(notes: &[&NoteStore], note_file: &Path) -> StorageResult<()> {
+    keep_previous_generation(note_file)?;
+    write_file_atomic(note_file, notes_markdown::notes_to_markdown(notes).as_bytes())
+}
 
-    for note in general_notes {
-        writeln!(file, "{}", note)?;
+/// Rewrites the log like every other file, appending in place could leave a broken table behind.
+fn append_events(activity_file: &Path, events: &[ReviewEventStore]) -> StorageResult<()> {
+    if events.is_empty() {
+        return Ok(());
     }
+    let mut activity = ActivityFile {
+        events: if activity_file.exists() { load_events(activity_file)? } else { Vec::new() },
+    };
+    activity.events.extend_from_slice(events);
 
-    writeln!(file)?;
+    let mut contents = format!("{} = {}\n\n", file_format::VERSION_KEY, FORMAT_VERSION);
+    contents.push_str(&toml::to_string(&activity).map_err(|e| StorageError::Serialize(e.to_string()))?);
+    keep_previous_generation(activity_file)?;
+    write_file_atomic(activity_file, contents.as_bytes())
+}
 
-    for (file_name, notes) in file_notes {
-        writeln!(file, "# Notes of '{}'", file_name)?;
-        for note in notes {
-            writeln!(file, "{}", note)?;
+/// Logs of older versions were appended to in place and may end with a table cut off by a crash,
+/// which is dropped.
+fn load_events(activity_file: &Path) -> StorageResult<Vec<ReviewEventStore>> {
+    let contents = fs::read_to_string(activity_file)?;
+    match events_from_toml(&contents) {
+        Ok(events) => Ok(events),
+        Err(e) => {
+            let Some(last_table) = contents.rfind("\n[[events]]") else {
+                return Err(e);
+            };
+            let events = events_from_toml(&contents[..last_table]).map_err(|_| e)?;
+            log::warn!("Skip the broken last event of {}", activity_file.display());
+            Ok(events)
         }
-        writeln!(file)?;
     }
+}
 
-    Ok(())
+fn events_from_toml(contents: &str) -> StorageResult<Vec<ReviewEventStore>> {
+    let table: Table = toml::from_str(contents).map_err(|e| StorageError::Deserialize(e.to_string()))?;
+    file_format::format_version(&table)?;
+    let activity: ActivityFile = table.try_into().map_err(|e: toml::de::Error| StorageError::Deserialize(e.to_string()))?;
+    Ok(activity.events)
 }
 
 #[cfg(test)]
@@ -353,7 +774,7 @@ mod tests {
     use anyhow::Ok;
     use serial_test::serial;
 
-    use crate::storage::repository_storage::{DiffRangeStore, FileDiffStore, RepositoryStore, ReviewName};
+    use crate::storage::repository_storage::{DiffRangeStore, DiffSide, FileDiffStore, IterationStore, LineRange, RepositoryStore, ReviewEvent, ReviewName};
 
     use super::*;
     use std::{
@@ -383,7 +804,7 @@ mod tests {
         }
         if !notes.is_empty() {
             let note_file = path.join(NOTE_FILE_NAME);
-            save_notes(&notes.iter().collect::<Vec<_>>(), note_file)?;
+            save_notes(&notes.iter().collect::<Vec<_>>(), &note_file)?;
         }
 
         path.push(review_name);
@@ -436,11 +857,28 @@ file_name = "foo.md"
 
         create_review(path.to_path_buf(), "review_helper", "cool_feature", cool_feature_contents, Vec::new())?;
 
-        let notes = vec![NoteStore {
-            context: "foo/bar.cpp".to_string(),
-            is_done: true,
-            text: "fix bug".to_string(),
-        }];
+        let notes = vec![
+            NoteStore {
+                context: "foo/bar.cpp".to_string(),
+                is_done: true,
+                text: "fix bug".to_string(),
+                ..Default::default()
+            },
+            NoteStore {
+                context: "foo/bar.cpp".to_string(),
+                line_range: Some(LineRange::new(DiffSide::Old, 142, 145)),
+                is_done: false,
+                text: "off-by-one here".to_string(),
+                ..Default::default()
+            },
+            NoteStore {
+                context: "foo/bar.cpp".to_string(),
+                line_range: Some(LineRange::new(DiffSide::New, 12, 12)),
+                is_outdated: true,
+                is_done: false,
+                text: "unused import".to_string(),
+            },
+        ];
         create_review(path.to_path_buf(), "review_helper", "fancy_ui", fancy_ui_contents, notes)?;
         create_repo(path.to_path_buf(), "trackme", trackme_content)?;
 
@@ -462,7 +900,7 @@ file_name = "foo.md"
 
         let repository_storage = ReviewHelperFileStorage::new(context.0.clone());
 
-        let repositories = repository_storage.load_repositories()?;
+        let repositories = repository_storage.load_repositories()?.repositories;
 
         let expected_repository = vec![
             RepositoryStore {
@@ -509,7 +947,7 @@ file_name = "foo.md"
 
         repository_storage.save_repository(&repository_store)?;
 
-        let load_result = repository_storage.load_repositories()?;
+        let load_result = repository_storage.load_repositories()?.repositories;
 
         assert_eq!(load_result, vec![expected_repository_store]);
 
@@ -586,10 +1024,12 @@ file_name = "foo.md"
             FileDiffStore {
                 file_path: PathBuf::from("bar.md"),
                 is_reviewed: false,
+                ..Default::default()
             },
             FileDiffStore {
                 file_path: PathBuf::from("foo.md"),
                 is_reviewed: true,
+                ..Default::default()
             },
         ];
 
@@ -601,6 +1041,7 @@ file_name = "foo.md"
         let expected_diff_range = DiffRangeStore {
             start: "ed7811b".to_string(),
             end: "a261b7b".to_string(),
+            ..Default::default()
         };
         assert_eq!(review.diff_range, expected_diff_range);
 
@@ -610,13 +1051,34 @@ file_name = "foo.md"
             assert!(expected_file_diffs.contains(&file_diff_item));
         }
 
-        assert_eq!(review.notes.len(), 1);
+        assert_eq!(review.notes.len(), 3);
         assert_eq!(
             review.notes[0],
             NoteStore {
                 context: "foo/bar.cpp".to_string(),
                 is_done: true,
                 text: "fix bug".to_string(),
+                ..Default::default()
+            }
+        );
+        assert_eq!(
+            review.notes[1],
+            NoteStore {
+                context: "foo/bar.cpp".to_string(),
+                line_range: Some(LineRange::new(DiffSide::Old, 142, 145)),
+                is_done: false,
+                text: "off-by-one here".to_string(),
+                ..Default::default()
+            }
+        );
+        assert_eq!(
+            review.notes[2],
+            NoteStore {
+                context: "foo/bar.cpp".to_string(),
+                line_range: Some(LineRange::new(DiffSide::New, 12, 12)),
+                is_outdated: true,
+                is_done: false,
+                text: "unused import".to_string(),
             }
         );
 
@@ -652,16 +1114,48 @@ file_name = "foo.md"
             diff_range: DiffRangeStore {
                 start: "0xfoo".to_string(),
                 end: "".to_string(),
+                iterations: vec![
+                    IterationStore {
+                        start: "0xfoo".to_string(),
+                        end: "0xbar".to_string(),
+                    },
+                    IterationStore {
+                        start: "0xfoo".to_string(),
+                        end: "0xbaz".to_string(),
+                    },
+                ],
+                interdiff_iteration: Some(2),
+                mode: DiffMode::Staged,
             },
-            file_diff_list: vec![FileDiffStore {
-                file_path: PathBuf::from("/foo/bar.txt"),
-                is_reviewed: true,
-            }],
-            notes: vec![NoteStore {
-                context: "/foo/bar.txt".to_string(),
-                text: "Fix bug".to_string(),
-                is_done: true,
-            }],
+            file_diff_list: vec![
+                FileDiffStore {
+                    file_path: PathBuf::from("/foo/bar.txt"),
+                    is_reviewed: true,
+                    reviewed_blob: Some("8ab686eafeb1f44702738c8b0f24f2567c36da6d".to_string()),
+                    ..Default::default()
+                },
+                FileDiffStore {
+                    file_path: PathBuf::from("/foo/baz.txt"),
+                    is_changed_since_review: true,
+                    reviewed_commits: vec!["1a2b3c4".to_string(), "5d6e7f8".to_string()],
+                    ..Default::default()
+                },
+            ],
+            notes: vec![
+                NoteStore {
+                    context: "/foo/bar.txt".to_string(),
+                    text: "Fix bug".to_string(),
+                    is_done: true,
+                    ..Default::default()
+                },
+                NoteStore {
+                    context: "/foo/bar.txt".to_string(),
+                    text: "Wrong bound".to_string(),
+                    line_range: Some(LineRange::new(DiffSide::New, 7, 7)),
+                    is_done: false,
+                    ..Default::default()
+                },
+            ],
         };
         let review_name = ReviewName::from("fancy_stuff");
         repository_storage.save_review_notes(&repository_name, &review_name, &review_store.notes.iter().collect::<Vec<_>>())?;
@@ -709,6 +1203,218 @@ file_name = "foo.md"
 
         Ok(())
     }
+    #[test]
+    #[serial]
+    fn test_migrating_legacy_review() -> anyhow::Result<()> {
+        struct Context(PathBuf);
+        impl Drop for Context {
+            fn drop(&mut self) {
+                let _ = fs::remove_dir_all(&self.0);
+            }
+        }
+
+        let context = Context(create_test_dir());
+        let repository_content = r#"name = "review_helper"
+path = "/home/harry/workspace/review_helper"
+base_branch = "main"
+"#;
+        create_repo(context.0.clone(), "review_helper", repository_content)?;
+        let legacy_review_content = r#"version = 1
+start_diff = "c134268"
+end_diff = "98bad35"
+first_commit = "9f89049b7f99682c48474d421ac126316adaed15"
+repo_path = "/home/harry/workspace/review_helper"
+
+[[diff_files]]
+is_reviewed = true
+file_name = ".gitignore"
+"#;
+        create_review(context.0.clone(), "review_helper", "legacy", legacy_review_content, Vec::new())?;
+        create_review(context.0.clone(), "review_helper", "newer", "version = 3\nstart_diff = \"\"\n", Vec::new())?;
+
+        let repository_storage = ReviewHelperFileStorage::new(context.0.clone());
+        let repository_name = RepositoryName::from("review_helper");
+        let repositories = repository_storage.load_repositories()?.repositories;
+        assert_eq!(repositories[0].first_commit, "9f89049b7f99682c48474d421ac126316adaed15");
+        assert!(context.0.join("review_helper").join("review_helper.toml.v1.bak").exists());
+        repository_storage.save_repository(&repositories[0])?;
+
+        let review = repository_storage
+            .load_review(&repository_name, &ReviewName::from("legacy"))?
+            .expect("Should load the legacy review!");
+        assert_eq!(review.diff_range.start, "c134268");
+        assert_eq!(review.file_diff_list.len(), 1);
+
+        let review_dir = context.0.join("review_helper").join("legacy");
+        let review_table = read_table(&review_dir.join("legacy.toml"))?;
+        assert_eq!(file_format::format_version(&review_table)?, FORMAT_VERSION);
+        assert!(!review_table.contains_key("repo_path"));
+        assert_eq!(fs::read_to_string(review_dir.join("legacy.toml.v1.bak"))?, legacy_review_content);
+
+        assert!(matches!(
+            repository_storage.load_review(&repository_name, &ReviewName::from("newer")),
+            Err(StorageError::File(_, e)) if matches!(*e, StorageError::UnsupportedVersion(_))
+        ));
+        Ok(())
+    }
+
+    #[test]
+    #[serial]
+    fn test_loading_broken_files() -> anyhow::Result<()> {
+        struct Context(PathBuf);
+        impl Drop for Context {
+            fn drop(&mut self) {
+                let _ = fs::remove_dir_all(&self.0);
+            }
+        }
+
+        let context = Context(create_test_dir());
+        create_test_repos(&context.0)?;
+        create_repo(context.0.clone(), "broken", "name = \"broken\"\nfirst_commit = \"5a99f03\"\n")?;
+        create_repo(context.0.clone(), "truncated", "name = \"truncated\"\npath = \"/home/harry/trunc")?;
+        create_review(context.0.clone(), "review_helper", "no_end", "start_diff = \"c134268\"\n", Vec::new())?;
+        create_review(
+            context.0.clone(),
+            "review_helper",
+            "no_file_name",
+            "start_diff = \"c134268\"\nend_diff = \"\"\n\n[[diff_files]]\nis_reviewed = true\n",
+            Vec::new(),
+        )?;
+
+        let repository_storage = ReviewHelperFileStorage::new(context.0.clone());
+        let loaded_repositories = repository_storage.load_repositories()?;
+        let mut repository_names = loaded_repositories.repositories.iter().map(|r| r.name.as_str()).collect::<Vec<_>>();
+        repository_names.sort();
+        assert_eq!(repository_names, vec!["review_helper", "trackme"]);
+
+        let mut errors = loaded_repositories.errors.iter().map(ToString::to_string).collect::<Vec<_>>();
+        errors.sort();
+        assert_eq!(errors.len(), 2);
+        assert!(errors[0].contains("broken.toml") && errors[0].ends_with("Missing key 'path'"));
+        assert!(errors[1].contains("truncated.toml"));
+
+        let repository_name = RepositoryName::from("review_helper");
+        let error = repository_storage
+            .load_review(&repository_name, &ReviewName::from("no_end"))
+            .unwrap_err()
+            .to_string();
+        assert!(error.contains("no_end.toml") && error.ends_with("Missing key 'end_diff'"), "{}", error);
+        let error = repository_storage
+            .load_review(&repository_name, &ReviewName::from("no_file_name"))
+            .unwrap_err()
+            .to_string();
+        assert!(error.ends_with("Missing key 'diff_files[0].file_name'"), "{}", error);
+        assert!(repository_storage.load_review(&repository_name, &ReviewName::from("fancy_ui"))?.is_some());
+        Ok(())
+    }
+
+    #[test]
+    #[serial]
+    fn test_falling_back_to_previous_generation() -> anyhow::Result<()> {
+        struct Context(PathBuf);
+        impl Drop for Context {
+            fn drop(&mut self) {
+                let _ = fs::remove_dir_all(&self.0);
+            }
+        }
+
+        let context = Context(create_test_dir());
+        create_test_repos(&context.0)?;
+        let repository_storage = ReviewHelperFileStorage::new(context.0.clone());
+        let repository_name = RepositoryName::from("review_helper");
+        let review_name = ReviewName::from("fancy_ui");
+        let review_dir = context.0.join("review_helper").join("fancy_ui");
+        let review_file = review_dir.join("fancy_ui.toml");
+        let note_file = review_dir.join(NOTE_FILE_NAME);
+
+        let file_diff = FileDiffStore {
+            file_path: PathBuf::from("foo.md"),
+            is_reviewed: true,
+            ..Default::default()
+        };
+        for start in ["c134268", "98bad35"] {
+            let diff_range = DiffRangeStore {
+                start: start.to_string(),
+                end: String::new(),
+                ..Default::default()
+            };
+            repository_storage.save_review_file_diffs(&repository_name, &review_name, &diff_range, &[&file_diff])?;
+            let note = NoteStore {
+                text: format!("note of {}", start),
+                ..Default::default()
+            };
+            repository_storage.save_review_notes(&repository_name, &review_name, &[&note])?;
+        }
+        assert!(!suffixed_path(&review_file, TEMP_SUFFIX).exists());
+        let backup_table = read_table(&suffixed_path(&review_file, BACKUP_SUFFIX))?;
+        assert_eq!(backup_table["start_diff"].as_str(), Some("c134268"));
+
+        // A crash of an older version left truncated files behind.
+        fs::write(&review_file, "")?;
+        fs::write(&note_file, "# Notes of 'foo.md\n")?;
+        let review = repository_storage
+            .load_review(&repository_name, &review_name)?
+            .expect("Should load the backup!");
+        assert_eq!(review.diff_range.start, "c134268");
+        assert_eq!(review.file_diff_list.len(), 1);
+        assert_eq!(review.notes[0].text, "note of c134268");
+        assert!(suffixed_path(&review_file, BROKEN_SUFFIX).exists());
+        assert!(suffixed_path(&note_file, BROKEN_SUFFIX).exists());
+
+        fs::write(review_dir.join("fancy_ui.toml"), "version = 3\n")?;
+        assert!(repository_storage.load_review(&repository_name, &review_name).is_err());
+        Ok(())
+    }
+
+    #[test]
+    #[serial]
+    fn test_detecting_changes_of_other_instances() -> anyhow::Result<()> {
+        struct Context(PathBuf);
+        impl Drop for Context {
+            fn drop(&mut self) {
+                let _ = fs::remove_dir_all(&self.0);
+            }
+        }
+
+        let context = Context(create_test_dir());
+        create_test_repos(&context.0)?;
+        let repository_name = RepositoryName::from("review_helper");
+        let review_name = ReviewName::from("fancy_ui");
+        let storage = ReviewHelperFileStorage::new(context.0.clone());
+        let other_storage = ReviewHelperFileStorage::new(context.0.clone());
+
+        let mut review = storage.load_review(&repository_name, &review_name)?.expect("Should load the review!");
+        other_storage.load_review(&repository_name, &review_name)?;
+        let note = NoteStore {
+            text: "from the other instance".to_string(),
+            ..Default::default()
+        };
+        other_storage.save_review_notes(&repository_name, &review_name, &[&note])?;
+
+        let note = NoteStore {
+            text: "would clobber the other note".to_string(),
+            ..Default::default()
+        };
+        assert!(matches!(
+            storage.save_review_notes(&repository_name, &review_name, &[&note]),
+            Err(StorageError::Conflict(_))
+        ));
+        review.file_diff_list[0].is_reviewed = !review.file_diff_list[0].is_reviewed;
+        let file_diffs = review.file_diff_list.iter().collect::<Vec<_>>();
+        storage.save_review_file_diffs(&repository_name, &review_name, &review.diff_range, &file_diffs)?;
+        assert!(matches!(
+            other_storage.save_review_file_diffs(&repository_name, &review_name, &review.diff_range, &[]),
+            Err(StorageError::Conflict(_))
+        ));
+
+        assert!(storage.is_review_changed(&repository_name, &review_name)?);
+        let review = storage.load_review(&repository_name, &review_name)?.expect("Should reload the review!");
+        assert!(!storage.is_review_changed(&repository_name, &review_name)?);
+        assert_eq!(review.notes[0].text, "from the other instance");
+        storage.save_review_notes(&repository_name, &review_name, &[&note])?;
+        Ok(())
+    }
+
     #[test]
     #[serial]
     fn test_renaming_review() -> anyhow::Result<()> {
@@ -736,4 +1442,114 @@ file_name = "foo.md"
 
         Ok(())
     }
+
+    #[serial]
+    #[test]
+    fn test_appending_review_events() -> anyhow::Result<()> {
+        struct Context(PathBuf);
+        impl Drop for Context {
+            fn drop(&mut self) {
+                let _ = fs::remove_dir_all(&self.0);
+            }
+        }
+
+        let context = Context(create_test_dir());
+        create_test_repos(&context.0)?;
+        let repository_storage = ReviewHelperFileStorage::new(context.0.clone());
+
+        let repository_name = RepositoryName::from("review_helper");
+        let review_name = ReviewName::from("fancy_ui");
+        assert!(repository_storage.load_review_events(&repository_name, &review_name)?.is_empty());
+
+        let events = vec![
+            ReviewEventStore {
+                time: "2024-03-01T10:00:00+01:00".to_string(),
+                author: "harry".to_string(),
+                event: ReviewEvent::FileReviewed {
+                    file: "foo.md".to_string(),
+                    is_reviewed: true,
+                },
+            },
+            ReviewEventStore {
+                time: "2024-03-01T10:05:00+01:00".to_string(),
+                author: "harry".to_string(),
+                event: ReviewEvent::NoteAdded {
+                    context: "foo.md".to_string(),
+                    text: "first line\n\"second\" line".to_string(),
+                },
+            },
+        ];
+        repository_storage.append_review_events(&repository_name, &review_name, &events[..1])?;
+        repository_storage.append_review_events(&repository_name, &review_name, &events[1..])?;
+        assert_eq!(repository_storage.load_review_events(&repository_name, &review_name)?, events);
+
+        // A crash of an older version, which appended in place, cut off the last table.
+        let activity_file = context.0.join("review_helper").join("fancy_ui").join(ACTIVITY_FILE_NAME);
+        let contents = fs::read_to_string(&activity_file)?;
+        fs::write(&activity_file, &contents[..contents.rfind("author").expect("Should contain the last event")])?;
+        assert_eq!(repository_storage.load_review_events(&repository_name, &review_name)?, events[..1]);
+        repository_storage.append_review_events(&repository_name, &review_name, &events[1..])?;
+        assert_eq!(repository_storage.load_review_events(&repository_name, &review_name)?, events);
+        assert!(suffixed_path(&activity_file, BACKUP_SUFFIX).exists());
+
+        // The log moves with its review.
+        let new_review_name = ReviewName::from("cool_fancy_ui");
+        repository_storage.rename_review(&repository_name, &review_name, &new_review_name)?;
+        assert_eq!(repository_storage.load_review_events(&repository_name, &new_review_name)?, events);
+
+        assert!(matches!(
+            repository_storage.append_review_events(&repository_name, &ReviewName::from("unknown"), &events),
+            Err(StorageError::ReviewNotFound(_))
+        ));
+        Ok(())
+    }
+
+    #[serial]
+    #[test]
+    fn test_saving_review_snapshots() -> anyhow::Result<()> {
+        struct Context(PathBuf);
+        impl Drop for Context {
+            fn drop(&mut self) {
+                let _ = fs::remove_dir_all(&self.0);
+            }
+        }
+
+        let context = Context(create_test_dir());
+        create_test_repos(&context.0)?;
+        let repository_storage = ReviewHelperFileStorage::new(context.0.clone());
+
+        let repository_name = RepositoryName::from("review_helper");
+        let review_name = ReviewName::from("fancy_ui");
+        assert!(repository_storage.load_review_snapshots(&repository_name, &review_name)?.is_empty());
+
+        let review = repository_storage.load_review(&repository_name, &review_name)?.expect("Review should exist");
+        let first_snapshot = SnapshotStore {
+            name: "first".to_string(),
+            time: "2024-03-01T10:00:00+01:00".to_string(),
+            review: review.clone(),
+        };
+        let second_snapshot = SnapshotStore {
+            name: "second".to_string(),
+            time: "2024-03-02T10:00:00+01:00".to_string(),
+            review: ReviewStore::default(),
+        };
+        repository_storage.save_review_snapshot(&repository_name, &review_name, &second_snapshot)?;
+        repository_storage.save_review_snapshot(&repository_name, &review_name, &first_snapshot)?;
+        assert_eq!(
+            repository_storage.load_review_snapshots(&repository_name, &review_name)?,
+            vec![first_snapshot.clone(), second_snapshot]
+        );
+
+        // Snapshots are neither reviews nor change the review.
+        assert_eq!(repository_storage.load_review_names(&repository_name)?.len(), 2);
+        assert_eq!(repository_storage.load_review(&repository_name, &review_name)?, Some(review));
+
+        repository_storage.delete_review_snapshot(&repository_name, &review_name, "second")?;
+        assert!(matches!(
+            repository_storage.delete_review_snapshot(&repository_name, &review_name, "second"),
+            Err(StorageError::SnapshotNotFound(_))
+        ));
+        assert_eq!(repository_storage.load_review_snapshots(&repository_name, &review_name)?, vec![first_snapshot]);
+        Ok(())
+    }
 }
@@ -0,0 +1,1003 @@
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::path::PathBuf;
+use std::sync::{Mutex, MutexGuard};
+use std::time::Duration;
+
+use rusqlite::{Connection, OptionalExtension, params};
+
+use crate::storage::repository_storage::{
+    DiffMode, DiffRangeStore, DiffSide, FileDiffStore, IterationStore, LineRange, NoteStore, ReviewEventStore, ReviewName, ReviewStore, SnapshotStore,
+    StorageError,
+};
+use crate::storage::{LoadedRepositories, RepositoryName, RepositoryStore, ReviewHelperStorage, StorageResult};
+
+const DATABASE_FILE_NAME: &str = "review_helper.sqlite";
+const SCHEMA_VERSION: i32 = 1;
+/// Another instance (e.g. the command line interface) may hold the write lock for a moment.
+const BUSY_TIMEOUT: Duration = Duration::from_secs(5);
+
+const SCHEMA: &str = r#"
+CREATE TABLE IF NOT EXISTS repositories (
+    id INTEGER PRIMARY KEY,
+    name TEXT NOT NULL UNIQUE,
+    path TEXT NOT NULL,
+    first_commit TEXT NOT NULL,
+    base_branch TEXT NOT NULL
+);
+CREATE TABLE IF NOT EXISTS reviews (
+    id INTEGER PRIMARY KEY,
+    repository_id INTEGER NOT NULL REFERENCES repositories(id) ON DELETE CASCADE,
+    name TEXT NOT NULL,
+    start_diff TEXT NOT NULL DEFAULT '',
+    end_diff TEXT NOT NULL DEFAULT '',
+    diff_mode TEXT NOT NULL DEFAULT 'commits',
+    interdiff_iteration INTEGER,
+    revision INTEGER NOT NULL DEFAULT 0,
+    UNIQUE(repository_id, name)
+);
+CREATE TABLE IF NOT EXISTS iterations (
+    review_id INTEGER NOT NULL REFERENCES reviews(id) ON DELETE CASCADE,
+    position INTEGER NOT NULL,
+    start_diff TEXT NOT NULL,
+    end_diff TEXT NOT NULL,
+    PRIMARY KEY(review_id, position)
+);
+CREATE TABLE IF NOT EXISTS file_diffs (
+    review_id INTEGER NOT NULL REFERENCES reviews(id) ON DELETE CASCADE,
+    file_path TEXT NOT NULL,
+    is_reviewed INTEGER NOT NULL,
+    reviewed_blob TEXT,
+    is_changed_since_review INTEGER NOT NULL DEFAULT 0,
+    reviewed_commits TEXT NOT NULL DEFAULT '',
+    PRIMARY KEY(review_id, file_path)
+);
+CREATE TABLE IF NOT EXISTS notes (
+    review_id INTEGER NOT NULL REFERENCES reviews(id) ON DELETE CASCADE,
+    position INTEGER NOT NULL,
+    text TEXT NOT NULL,
+    context TEXT NOT NULL,
+    line_side TEXT,
+    line_start INTEGER,
+    line_end INTEGER,
+    is_outdated INTEGER NOT NULL,
+    is_done INTEGER NOT NULL,
+    PRIMARY KEY(review_id, position)
+);
+CREATE TABLE IF NOT EXISTS review_events (
+    id INTEGER PRIMARY KEY,
+    review_id INTEGER NOT NULL REFERENCES reviews(id) ON DELETE CASCADE,
+    time TEXT NOT NULL,
+    author TEXT NOT NULL,
+    event TEXT NOT NULL
+);
+CREATE TABLE IF NOT EXISTS snapshots (
+    id INTEGER PRIMARY KEY,
+    review_id INTEGER NOT NULL REFERENCES reviews(id) ON DELETE CASCADE,
+    name TEXT NOT NULL,
+    time TEXT NOT NULL,
+    start_diff TEXT NOT NULL,
+    end_diff TEXT NOT NULL,
+    diff_mode TEXT NOT NULL DEFAULT 'commits',
+    interdiff_iteration INTEGER,
+    UNIQUE(review_id, name)
+);
+CREATE TABLE IF NOT EXISTS snapshot_iterations (
+    snapshot_id INTEGER NOT NULL REFERENCES snapshots(id) ON DELETE CASCADE,
+    position INTEGER NOT NULL,
+    start_diff TEXT NOT NULL,
+    end_diff TEXT NOT NULL,
+    PRIMARY KEY(snapshot_id, position)
+);
+CREATE TABLE IF NOT EXISTS snapshot_file_diffs (
+    snapshot_id INTEGER NOT NULL REFERENCES snapshots(id) ON DELETE CASCADE,
+    file_path TEXT NOT NULL,
+    is_reviewed INTEGER NOT NULL,
+    reviewed_blob TEXT,
+    is_changed_since_review INTEGER NOT NULL DEFAULT 0,
+    reviewed_commits TEXT NOT NULL DEFAULT '',
+    PRIMARY KEY(snapshot_id, file_path)
+);
+CREATE TABLE IF NOT EXISTS snapshot_notes (
+    snapshot_id INTEGER NOT NULL REFERENCES snapshots(id) ON DELETE CASCADE,
+    position INTEGER NOT NULL,
+    text TEXT NOT NULL,
+    context TEXT NOT NULL,
+    line_side TEXT,
+    line_start INTEGER,
+    line_end INTEGER,
+    is_outdated INTEGER NOT NULL,
+    is_done INTEGER NOT NULL,
+    PRIMARY KEY(snapshot_id, position)
+);
+"#;
+
+/// Stores all repositories and reviews in one SQLite database. Every save runs in a
+/// transaction and only writes the rows that changed.
+///
+/// Every change of a review increments its `revision`, reviews changed by another instance since
+/// they were loaded or saved are not overwritten, `StorageError::Conflict` is returned instead.
+pub struct ReviewHelperSqliteStorage {
+    connection: Connection,
+    /// The revision of every review id as it was last loaded or saved by this instance.
+    revisions: Mutex<HashMap<i64, i64>>,
+}
+
+impl ReviewHelperSqliteStorage {
+    pub fn new(path: PathBuf) -> StorageResult<Self> {
+        fs::create_dir_all(&path)?;
+        let connection = Connection::open(path.join(DATABASE_FILE_NAME))?;
+        connection.busy_timeout(BUSY_TIMEOUT)?;
+        connection.pragma_update(None, "foreign_keys", true)?;
+        connection.pragma_update_and_check(None, "journal_mode", "WAL", |_| Ok(()))?;
+        let schema_version: i32 = connection.pragma_query_value(None, "user_version", |row| row.get(0))?;
+        upgrade_schema(&connection, schema_version)?;
+        connection.execute_batch(SCHEMA)?;
+        connection.pragma_update(None, "user_version", SCHEMA_VERSION)?;
+        Ok(Self {
+            connection,
+            revisions: Mutex::default(),
+        })
+    }
+
+    fn revisions(&self) -> MutexGuard<'_, HashMap<i64, i64>> {
+        // The map stays consistent even if another thread panicked while holding the lock.
+        self.revisions.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// Whether the review differs from its recorded revision. Reviews that were never loaded are unchanged.
+    fn is_changed(&self, connection: &Connection, review_id: i64) -> StorageResult<bool> {
+        let Some(recorded_revision) = self.revisions().get(&review_id).copied() else {
+            return Ok(false);
+        };
+        Ok(review_revision(connection, review_id)? != recorded_revision)
+    }
+
+    fn check(&self, connection: &Connection, review_id: i64, review_name: &ReviewName) -> StorageResult<()> {
+        if self.is_changed(connection, review_id)? {
+            return Err(StorageError::Conflict(review_name.as_str().to_string()));
+        }
+        Ok(())
+    }
+
+    fn record(&self, connection: &Connection, review_id: i64) -> StorageResult<()> {
+        let revision = review_revision(connection, review_id)?;
+        self.revisions().insert(review_id, revision);
+        Ok(())
+    }
+
+    /// Increments the revision if the transaction changed anything since `total_changes` and
+    /// records it.
+    fn finish_save(&self, connection: &Connection, review_id: i64, total_changes: u64) -> StorageResult<()> {
+        if connection.total_changes() != total_changes {
+            connection.execute("UPDATE reviews SET revision = revision + 1 WHERE id = ?1", [review_id])?;
+        }
+        self.record(connection, review_id)
+    }
+}
+
+fn review_revision(connection: &Connection, review_id: i64) -> StorageResult<i64> {
+    Ok(connection.query_row("SELECT revision FROM reviews WHERE id = ?1", [review_id], |row| row.get(0))?)
+}
+
+fn repository_id(connection: &Connection, repository_name: &RepositoryName) -> StorageResult<i64> {
+    connection
+        .query_row("SELECT id FROM repositories WHERE name = ?1", [repository_name.as_str()], |row| row.get(0))
+        .optional()?
+        .ok_or_else(|| StorageError::RepositoryNotFound(repository_name.as_str().to_string()))
+}
+
+fn review_id(connection: &Connection, repository_id: i64, review_name: &ReviewName) -> StorageResult<Option<i64>> {
+    Ok(connection
+        .query_row(
+            "SELECT id FROM reviews WHERE repository_id = ?1 AND name = ?2",
+            params![repository_id, review_name.as_str()],
+            |row| row.get(0),
+        )
+        .optional()?)
+}
+
+fn create_review(connection: &Connection, repository_id: i64, review_name: &ReviewName) -> StorageResult<i64> {
+    connection.execute(
+        "INSERT INTO reviews (repository_id, name) VALUES (?1, ?2) ON CONFLICT(repository_id, name) DO NOTHING",
+        params![repository_id, review_name.as_str()],
+    )?;
+    review_id(connection, repository_id, review_name)?.ok_or_else(|| StorageError::ReviewNotFound(review_name.as_str().to_string()))
+}
+
+/// Upgrades the tables of an existing database of an older version, new databases are already
+/// created with the current schema. Databases of newer versions are refused.
+fn upgrade_schema(_connection: &Connection, schema_version: i32) -> StorageResult<()> {
+    if schema_version > SCHEMA_VERSION {
+        return Err(StorageError::UnsupportedVersion(schema_version.to_string()));
+    }
+    Ok(())
+}
+
+/// Writes the iterations of a review (`iterations`) or snapshot (`snapshot_iterations`), only
+/// the changed rows are written.
+fn save_iterations(connection: &Connection, table: &str, owner_column: &str, owner_id: i64, iterations: &[IterationStore]) -> StorageResult<()> {
+    {
+        let mut upsert = connection.prepare(&format!(
+            "INSERT INTO {0} ({1}, position, start_diff, end_diff) VALUES (?1, ?2, ?3, ?4)
+             ON CONFLICT({1}, position) DO UPDATE SET start_diff = excluded.start_diff, end_diff = excluded.end_diff
+             WHERE (start_diff, end_diff) IS NOT (excluded.start_diff, excluded.end_diff)",
+            table, owner_column
+        ))?;
+        for (position, iteration) in iterations.iter().enumerate() {
+            upsert.execute(params![owner_id, position as i64, iteration.start, iteration.end])?;
+        }
+    }
+    connection.execute(
+        &format!("DELETE FROM {} WHERE {} = ?1 AND position >= ?2", table, owner_column),
+        params![owner_id, iterations.len() as i64],
+    )?;
+    Ok(())
+}
+
+fn load_iterations(connection: &Connection, table: &str, owner_column: &str, owner_id: i64) -> StorageResult<Vec<IterationStore>> {
+    Ok(connection
+        .prepare(&format!(
+            "SELECT start_diff, end_diff FROM {} WHERE {} = ?1 ORDER BY position",
+            table, owner_column
+        ))?
+        .query_map([owner_id], |row| {
+            Ok(IterationStore {
+                start: row.get(0)?,
+                end: row.get(1)?,
+            })
+        })?
+        .collect::<Result<Vec<_>, _>>()?)
+}
+
+fn file_diff_from_row(row: &rusqlite::Row<'_>) -> rusqlite::Result<FileDiffStore> {
+    Ok(FileDiffStore {
+        file_path: PathBuf::from(row.get::<_, String>(0)?),
+        is_reviewed: row.get(1)?,
+        reviewed_blob: row.get(2)?,
+        is_changed_since_review: row.get(3)?,
+        reviewed_commits: row.get::<_, String>(4)?.split_whitespace().map(str::to_string).collect(),
+    })
+}
+
+/// The id of an existing review.
+fn existing_review_id(connection: &Connection, repository_name: &RepositoryName, review_name: &ReviewName) -> StorageResult<i64> {
+    let repository_id = repository_id(connection, repository_name)?;
+    review_id(connection, repository_id, review_name)?.ok_or_else(|| StorageError::ReviewNotFound(review_name.as_str().to_string()))
+}
+
+fn note_from_row(row: &rusqlite::Row<'_>) -> rusqlite::Result<NoteStore> {
+    Ok(NoteStore {
+        text: row.get(0)?,
+        context: row.get(1)?,
+        line_range: line_range_from_sql(row.get(2)?, row.get(3)?, row.get(4)?),
+        is_outdated: row.get(5)?,
+        is_done: row.get(6)?,
+    })
+}
+
+fn side_to_sql(side: DiffSide) -> &'static str {
+    match side {
+        DiffSide::Old => "old",
+        DiffSide::New => "new",
+    }
+}
+
+fn diff_mode_from_sql(mode: String) -> DiffMode {
+    mode.parse().unwrap_or_default()
+}
+
+fn line_range_from_sql(side: Option<String>, start: Option<u32>, end: Option<u32>) -> Option<LineRange> {
+    let side = match side?.as_str() {
+        "old" => DiffSide::Old,
+        _ => DiffSide::New,
+    };
+    Some(LineRange::new(side, start?, end?))
+}
+
+impl ReviewHelperStorage for ReviewHelperSqliteStorage {
+    fn load_repositories(&self) -> StorageResult<LoadedRepositories> {
+        let mut statement = self
+            .connection
+            .prepare("SELECT name, path, first_commit, base_branch FROM repositories ORDER BY name")?;
+        let repositories = statement
+            .query_map([], |row| {
+                Ok(RepositoryStore {
+                    name: RepositoryName::from(row.get::<_, String>(0)?.as_str()),
+                    path: PathBuf::from(row.get::<_, String>(1)?),
+                    first_commit: row.get(2)?,
+                    base_branch: row.get(3)?,
+                })
+            })?
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(LoadedRepositories {
+            repositories,
+            errors: Vec::new(),
+        })
+    }
+
+    fn save_repository(&self, repository_store: &RepositoryStore) -> StorageResult<()> {
+        self.connection.execute(
+            "INSERT INTO repositories (name, path, first_commit, base_branch) VALUES (?1, ?2, ?3, ?4)
+             ON CONFLICT(name) DO UPDATE SET path = excluded.path, first_commit = excluded.first_commit, base_branch = excluded.base_branch",
+            params![
+                repository_store.name.as_str(),
+                repository_store.path.to_string_lossy(),
+                repository_store.first_commit,
+                repository_store.base_branch
+            ],
+        )?;
+        Ok(())
+    }
+
+    fn delete_repository(&self, repository_name: &RepositoryName) -> StorageResult<()> {
+        let deleted = self
+            .connection
+            .execute("DELETE FROM repositories WHERE name = ?1", [repository_name.as_str()])?;
+        if deleted == 0 {
+            return Err(StorageError::RepositoryNotFound(repository_name.as_str().to_string()));
+        }
+        Ok(())
+    }
+
+    fn load_review_names(&self, repository_name: &RepositoryName) -> StorageResult<Vec<ReviewName>> {
+        let repository_id = repository_id(&self.connection, repository_name)?;
+        let mut statement = self.connection.prepare("SELECT name FROM reviews WHERE repository_id = ?1 ORDER BY name")?;
+        let review_names = statement
+            .query_map([repository_id], |row| Ok(ReviewName::from(row.get::<_, String>(0)?.as_str())))?
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(review_names)
+    }
+
+    fn is_review_changed(&self, repository_name: &RepositoryName, review_name: &ReviewName) -> StorageResult<bool> {
+        let repository_id = repository_id(&self.connection, repository_name)?;
+        match review_id(&self.connection, repository_id, review_name)? {
+            Some(review_id) => self.is_changed(&self.connection, review_id),
+            None => Ok(false),
+        }
+    }
+
+    fn load_review(&self, repository_name: &RepositoryName, review_name: &ReviewName) -> StorageResult<Option<ReviewStore>> {
+        // One read transaction, so a concurrent save can't be seen halfway.
+        let transaction = self.connection.unchecked_transaction()?;
+        let repository_id = repository_id(&transaction, repository_name)?;
+        let Some(review_id) = review_id(&transaction, repository_id, review_name)? else {
+            return Ok(None);
+        };
+
+        let mut diff_range = transaction.query_row(
+            "SELECT start_diff, end_diff, interdiff_iteration, diff_mode FROM reviews WHERE id = ?1",
+            [review_id],
+            |row| {
+                Ok(DiffRangeStore {
+                    start: row.get(0)?,
+                    end: row.get(1)?,
+                    interdiff_iteration: row.get(2)?,
+                    mode: diff_mode_from_sql(row.get(3)?),
+                    ..Default::default()
+                })
+            },
+        )?;
+        diff_range.iterations = load_iterations(&transaction, "iterations", "review_id", review_id)?;
+
+        let file_diff_list = transaction
+            .prepare("SELECT file_path, is_reviewed, reviewed_blob, is_changed_since_review, reviewed_commits FROM file_diffs WHERE review_id = ?1 ORDER BY file_path")?
+            .query_map([review_id], file_diff_from_row)?
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let notes = transaction
+            .prepare("SELECT text, context, line_side, line_start, line_end, is_outdated, is_done FROM notes WHERE review_id = ?1 ORDER BY position")?
+            .query_map([review_id], note_from_row)?
+            .collect::<Result<Vec<_>, _>>()?;
+
+        self.record(&transaction, review_id)?;
+        transaction.commit()?;
+        Ok(Some(ReviewStore {
+            diff_range,
+            file_diff_list,
+            notes,
+        }))
+    }
+
+    fn delete_review(&self, repository_name: &RepositoryName, review_name: &ReviewName) -> StorageResult<()> {
+        let repository_id = repository_id(&self.connection, repository_name)?;
+        let deleted = self.connection.execute(
+            "DELETE FROM reviews WHERE repository_id = ?1 AND name = ?2",
+            params![repository_id, review_name.as_str()],
+        )?;
+        if deleted == 0 {
+            return Err(StorageError::ReviewNotFound(review_name.as_str().to_string()));
+        }
+        Ok(())
+    }
+
+    fn rename_review(&self, repository_name: &RepositoryName, old_review_name: &ReviewName, new_review_name: &ReviewName) -> StorageResult<()> {
+        let repository_id = repository_id(&self.connection, repository_name)?;
+        let renamed = self.connection.execute(
+            "UPDATE reviews SET name = ?3 WHERE repository_id = ?1 AND name = ?2",
+            params![repository_id, old_review_name.as_str(), new_review_name.as_str()],
+        )?;
+        if renamed == 0 {
+            return Err(StorageError::ReviewNotFound(old_review_name.as_str().to_string()));
+        }
+        Ok(())
+    }
+
+    fn save_review_notes(&self, repository_name: &RepositoryName, review_name: &ReviewName, notes: &[&NoteStore]) -> StorageResult<()> {
+        let transaction = self.connection.unchecked_transaction()?;
+        let total_changes = transaction.total_changes();
+        let repository_id = repository_id(&transaction, repository_name)?;
+        let review_id = create_review(&transaction, repository_id, review_name)?;
+        self.check(&transaction, review_id, review_name)?;
+
+        {
+            let mut upsert = transaction.prepare(
+                "INSERT INTO notes (review_id, position, text, context, line_side, line_start, line_end, is_outdated, is_done)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)
+                 ON CONFLICT(review_id, position) DO UPDATE SET
+                    text = excluded.text, context = excluded.context, line_side = excluded.line_side, line_start = excluded.line_start,
+                    line_end = excluded.line_end, is_outdated = excluded.is_outdated, is_done = excluded.is_done
+                 WHERE (text, context, line_side, line_start, line_end, is_outdated, is_done)
+                    IS NOT (excluded.text, excluded.context, excluded.line_side, excluded.line_start, excluded.line_end, excluded.is_outdated, excluded.is_done)",
+            )?;
+            for (position, note) in notes.iter().enumerate() {
+                upsert.execute(params![
+                    review_id,
+                    position as i64,
+                    note.text,
+                    note.context,
+                    note.line_range.map(|line_range| side_to_sql(line_range.side)),
+                    note.line_range.map(|line_range| line_range.start),
+                    note.line_range.map(|line_range| line_range.end),
+                    note.is_outdated,
+                    note.is_done
+                ])?;
+            }
+        }
+        transaction.execute(
+            "DELETE FROM notes WHERE review_id = ?1 AND position >= ?2",
+            params![review_id, notes.len() as i64],
+        )?;
+        self.finish_save(&transaction, review_id, total_changes)?;
+        transaction.commit()?;
+        Ok(())
+    }
+
+    fn save_review_file_diffs(
+        &self,
+        repository_name: &RepositoryName,
+        review_name: &ReviewName,
+        diff_range: &DiffRangeStore,
+        file_diffs: &[&FileDiffStore],
+    ) -> StorageResult<()> {
+        let transaction = self.connection.unchecked_transaction()?;
+        let total_changes = transaction.total_changes();
+        let repository_id = repository_id(&transaction, repository_name)?;
+        let review_id = create_review(&transaction, repository_id, review_name)?;
+        self.check(&transaction, review_id, review_name)?;
+
+        transaction.execute(
+            "UPDATE reviews SET start_diff = ?2, end_diff = ?3, interdiff_iteration = ?4, diff_mode = ?5
+             WHERE id = ?1 AND (start_diff, end_diff, interdiff_iteration, diff_mode) IS NOT (?2, ?3, ?4, ?5)",
+            params![
+                review_id,
+                diff_range.start,
+                diff_range.end,
+                diff_range.interdiff_iteration,
+                diff_range.mode.to_string()
+            ],
+        )?;
+        save_iterations(&transaction, "iterations", "review_id", review_id, &diff_range.iterations)?;
+
+        let file_paths = file_diffs
+            .iter()
+            .map(|file_diff| file_diff.file_path.to_string_lossy().to_string())
+            .collect::<HashSet<_>>();
+        let stored_file_paths = transaction
+            .prepare("SELECT file_path FROM file_diffs WHERE review_id = ?1")?
+            .query_map([review_id], |row| row.get::<_, String>(0))?
+            .collect::<Result<Vec<_>, _>>()?;
+        for file_path in stored_file_paths.iter().filter(|file_path| !file_paths.contains(*file_path)) {
+            transaction.execute("DELETE FROM file_diffs WHERE review_id = ?1 AND file_path = ?2", params![review_id, file_path])?;
+        }
+
+        {
+            let mut upsert = transaction.prepare(
+                "INSERT INTO file_diffs (review_id, file_path, is_reviewed, reviewed_blob, is_changed_since_review, reviewed_commits)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6)
+                 ON CONFLICT(review_id, file_path) DO UPDATE SET
+                     is_reviewed = excluded.is_reviewed,
+                     reviewed_blob = excluded.reviewed_blob,
+                     is_changed_since_review = excluded.is_changed_since_review,
+                     reviewed_commits = excluded.reviewed_commits
+                 WHERE (is_reviewed, reviewed_blob, is_changed_since_review, reviewed_commits) IS NOT
+                     (excluded.is_reviewed, excluded.reviewed_blob, excluded.is_changed_since_review, excluded.reviewed_commits)",
+            )?;
+            for file_diff in file_diffs {
+                upsert.execute(params![
+                    review_id,
+                    file_diff.file_path.to_string_lossy(),
+                    file_diff.is_reviewed,
+                    file_diff.reviewed_blob,
+                    file_diff.is_changed_since_review,
+                    file_diff.reviewed_commits.join(" ")
+                ])?;
+            }
+        }
+        self.finish_save(&transaction, review_id, total_changes)?;
+        transaction.commit()?;
+        Ok(())
+    }
+
+    fn append_review_events(&self, repository_name: &RepositoryName, review_name: &ReviewName, events: &[ReviewEventStore]) -> StorageResult<()> {
+        let transaction = self.connection.unchecked_transaction()?;
+        let review_id = existing_review_id(&transaction, repository_name, review_name)?;
+        {
+            // The event itself is kept as JSON, so that new kinds of events don't need new columns.
+            let mut insert = transaction.prepare("INSERT INTO review_events (review_id, time, author, event) VALUES (?1, ?2, ?3, ?4)")?;
+            for event in events {
+                let event_json = serde_json::to_string(&event.event).map_err(|e| StorageError::Serialize(e.to_string()))?;
+                insert.execute(params![review_id, event.time, event.author, event_json])?;
+            }
+        }
+        transaction.commit()?;
+        Ok(())
+    }
+
+    fn load_review_events(&self, repository_name: &RepositoryName, review_name: &ReviewName) -> StorageResult<Vec<ReviewEventStore>> {
+        let repository_id = repository_id(&self.connection, repository_name)?;
+        let Some(review_id) = review_id(&self.connection, repository_id, review_name)? else {
+            return Ok(Vec::new());
+        };
+        let rows = self
+            .connection
+            .prepare("SELECT time, author, event FROM review_events WHERE review_id = ?1 ORDER BY id")?
+            .query_map([review_id], |row| {
+                Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?, row.get::<_, String>(2)?))
+            })?
+            .collect::<Result<Vec<_>, _>>()?;
+        rows.into_iter()
+            .map(|(time, author, event_json)| {
+                let event = serde_json::from_str(&event_json).map_err(|e| StorageError::Deserialize(e.to_string()))?;
+                Ok(ReviewEventStore { time, author, event })
+            })
+            .collect()
+    }
+
+    fn save_review_snapshot(&self, repository_name: &RepositoryName, review_name: &ReviewName, snapshot: &SnapshotStore) -> StorageResult<()> {
+        let transaction = self.connection.unchecked_transaction()?;
+        let review_id = existing_review_id(&transaction, repository_name, review_name)?;
+        transaction.execute("DELETE FROM snapshots WHERE review_id = ?1 AND name = ?2", params![review_id, snapshot.name])?;
+        transaction.execute(
+            "INSERT INTO snapshots (review_id, name, time, start_diff, end_diff, interdiff_iteration, diff_mode) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+            params![
+                review_id,
+                snapshot.name,
+                snapshot.time,
+                snapshot.review.diff_range.start,
+                snapshot.review.diff_range.end,
+                snapshot.review.diff_range.interdiff_iteration,
+                snapshot.review.diff_range.mode.to_string()
+            ],
+        )?;
+        let snapshot_id = transaction.last_insert_rowid();
+        save_iterations(
+            &transaction,
+            "snapshot_iterations",
+            "snapshot_id",
+            snapshot_id,
+            &snapshot.review.diff_range.iterations,
+        )?;
+        {
+            let mut insert = transaction.prepare(
+                "INSERT INTO snapshot_file_diffs (snapshot_id, file_path, is_reviewed, reviewed_blob, is_changed_since_review, reviewed_commits)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+            )?;
+            for file_diff in &snapshot.review.file_diff_list {
+                insert.execute(params![
+                    snapshot_id,
+                    file_diff.file_path.to_string_lossy(),
+                    file_diff.is_reviewed,
+                    file_diff.reviewed_blob,
+                    file_diff.is_changed_since_review,
+                    file_diff.reviewed_commits.join(" ")
+                ])?;
+            }
+            let mut insert = transaction.prepare(
+                "INSERT INTO snapshot_notes (snapshot_id, position, text, context, line_side, line_start, line_end, is_outdated, is_done)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)",
+            )?;
+            for (position, note) in snapshot.review.notes.iter().enumerate() {
+                insert.execute(params![
+                    snapshot_id,
+                    position as i64,
+                    note.text,
+                    note.context,
+                    note.line_range.map(|line_range| side_to_sql(line_range.side)),
+                    note.line_range.map(|line_range| line_range.start),
+                    note.line_range.map(|line_range| line_range.end),
+                    note.is_outdated,
+                    note.is_done
+                ])?;
+            }
+        }
+        transaction.commit()?;
+        Ok(())
+    }
+
+    fn load_review_snapshots(&self, repository_name: &RepositoryName, review_name: &ReviewName) -> StorageResult<Vec<SnapshotStore>> {
+        let transaction = self.connection.unchecked_transaction()?;
+        let review_id = existing_review_id(&transaction, repository_name, review_name)?;
+        let snapshot_rows = transaction
+            .prepare("SELECT id, name, time, start_diff, end_diff, interdiff_iteration, diff_mode FROM snapshots WHERE review_id = ?1 ORDER BY time, name")?
+            .query_map([review_id], |row| {
+                let snapshot = SnapshotStore {
+                    name: row.get(1)?,
+                    time: row.get(2)?,
+                    review: ReviewStore {
+                        diff_range: DiffRangeStore {
+                            start: row.get(3)?,
+                            end: row.get(4)?,
+                            interdiff_iteration: row.get(5)?,
+                            mode: diff_mode_from_sql(row.get(6)?),
+                            ..Default::default()
+                        },
+                        ..Default::default()
+                    },
+                };
+                Ok((row.get::<_, i64>(0)?, snapshot))
+            })?
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let mut snapshots = Vec::new();
+        for (snapshot_id, mut snapshot) in snapshot_rows {
+            snapshot.review.diff_range.iterations = load_iterations(&transaction, "snapshot_iterations", "snapshot_id", snapshot_id)?;
+            snapshot.review.file_diff_list = transaction
+                .prepare(
+                    "SELECT file_path, is_reviewed, reviewed_blob, is_changed_since_review, reviewed_commits FROM snapshot_file_diffs WHERE snapshot_id = ?1 ORDER BY file_path",
+                )?
+                .query_map([snapshot_id], file_diff_from_row)?
+                .collect::<Result<Vec<_>, _>>()?;
+            snapshot.review.notes = transaction
+                .prepare(
+                    "SELECT text, context, line_side, line_start, line_end, is_outdated, is_done FROM snapshot_notes WHERE snapshot_id = ?1 ORDER BY position",
+                )?
+                .query_map([snapshot_id], note_from_row)?
+                .collect::<Result<Vec<_>, _>>()?;
+            snapshots.push(snapshot);
+        }
+        transaction.commit()?;
+        Ok(snapshots)
+    }
+
+    fn delete_review_snapshot(&self, repository_name: &RepositoryName, review_name: &ReviewName, snapshot_name: &str) -> StorageResult<()> {
+        let review_id = existing_review_id(&self.connection, repository_name, review_name)?;
+        let deleted = self
+            .connection
+            .execute("DELETE FROM snapshots WHERE review_id = ?1 AND name = ?2", params![review_id, snapshot_name])?;
+        if deleted == 0 {
+            return Err(StorageError::SnapshotNotFound(snapshot_name.to_string()));
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serial_test::serial;
+    use std::{env, fs};
+
+    use super::*;
+    use crate::storage::repository_storage::ReviewEvent;
+
+    struct Context(PathBuf);
+    impl Drop for Context {
+        fn drop(&mut self) {
+            let _ = fs::remove_dir_all(&self.0);
+        }
+    }
+
+    fn create_context() -> Context {
+        let mut path = env::temp_dir();
+        path.push(format!("{}_sqlite_storage_test", std::env!("CARGO_CRATE_NAME")));
+        let context = Context(path);
+        let _ = fs::remove_dir_all(&context.0);
+        context
+    }
+
+    fn repository_store() -> RepositoryStore {
+        RepositoryStore {
+            path: PathBuf::from("/home/harry/workspace/review_helper"),
+            name: RepositoryName::from("review_helper"),
+            first_commit: "9f89049b7f99682c48474d421ac126316adaed15".to_string(),
+            base_branch: "main".to_string(),
+        }
+    }
+
+    fn review_store() -> ReviewStore {
+        ReviewStore {
+            diff_range: DiffRangeStore {
+                start: "ed7811b".to_string(),
+                end: "a261b7b".to_string(),
+                iterations: vec![IterationStore {
+                    start: "ed7811b".to_string(),
+                    end: "9f89049".to_string(),
+                }],
+                interdiff_iteration: Some(1),
+                mode: DiffMode::Commits,
+            },
+            file_diff_list: vec![
+                FileDiffStore {
+                    file_path: PathBuf::from("bar.md"),
+                    is_reviewed: false,
+                    ..Default::default()
+                },
+                FileDiffStore {
+                    file_path: PathBuf::from("foo.md"),
+                    is_reviewed: true,
+                    ..Default::default()
+                },
+            ],
+            notes: vec![
+                NoteStore {
+                    text: "general note".to_string(),
+                    ..Default::default()
+                },
+                NoteStore {
+                    context: "foo.md".to_string(),
+                    line_range: Some(LineRange::new(DiffSide::Old, 142, 145)),
+                    text: "off-by-one here".to_string(),
+                    ..Default::default()
+                },
+                NoteStore {
+                    context: "foo.md".to_string(),
+                    line_range: Some(LineRange::new(DiffSide::New, 12, 12)),
+                    is_outdated: true,
+                    is_done: true,
+                    text: "unused import".to_string(),
+                },
+            ],
+        }
+    }
+
+    fn save_review(storage: &ReviewHelperSqliteStorage, review_name: &ReviewName, review_store: &ReviewStore) -> StorageResult<()> {
+        let repository_name = repository_store().name;
+        storage.save_review_file_diffs(
+            &repository_name,
+            review_name,
+            &review_store.diff_range,
+            &review_store.file_diff_list.iter().collect::<Vec<_>>(),
+        )?;
+        storage.save_review_notes(&repository_name, review_name, &review_store.notes.iter().collect::<Vec<_>>())
+    }
+
+    #[serial]
+    #[test]
+    fn test_saving_repositories() -> anyhow::Result<()> {
+        let context = create_context();
+        let storage = ReviewHelperSqliteStorage::new(context.0.clone())?;
+
+        let mut repository_store = repository_store();
+        storage.save_repository(&repository_store)?;
+        repository_store.base_branch = "develop".to_string();
+        storage.save_repository(&repository_store)?;
+
+        assert_eq!(
+            ReviewHelperSqliteStorage::new(context.0.clone())?.load_repositories()?.repositories,
+            vec![repository_store.clone()]
+        );
+
+        storage.delete_repository(&repository_store.name)?;
+        assert!(storage.load_repositories()?.repositories.is_empty());
+        assert!(storage.delete_repository(&repository_store.name).is_err());
+        Ok(())
+    }
+
+    #[serial]
+    #[test]
+    fn test_storing_review() -> anyhow::Result<()> {
+        let context = create_context();
+        let storage = ReviewHelperSqliteStorage::new(context.0.clone())?;
+        storage.save_repository(&repository_store())?;
+
+        let repository_name = repository_store().name;
+        let review_name = ReviewName::from("fancy_ui");
+        assert!(storage.load_review(&repository_name, &review_name)?.is_none());
+        assert!(storage.load_review(&RepositoryName::from("unknown"), &review_name).is_err());
+
+        let mut review_store = review_store();
+        save_review(&storage, &review_name, &review_store)?;
+        assert_eq!(storage.load_review(&repository_name, &review_name)?, Some(review_store.clone()));
+
+        review_store.diff_range.end = String::new();
+        review_store.file_diff_list[0].is_reviewed = true;
+        review_store.file_diff_list[0].reviewed_blob = Some("8ab686eafeb1f44702738c8b0f24f2567c36da6d".to_string());
+        review_store.file_diff_list[1].is_changed_since_review = true;
+        review_store.file_diff_list[1].reviewed_commits = vec!["ed7811b".to_string(), "a261b7b".to_string()];
+        review_store.diff_range.iterations.push(IterationStore {
+            start: "ed7811b".to_string(),
+            end: "a261b7b".to_string(),
+        });
+        review_store.diff_range.interdiff_iteration = None;
+        review_store.diff_range.mode = DiffMode::Staged;
+        save_review(&storage, &review_name, &review_store)?;
+        assert_eq!(storage.load_review(&repository_name, &review_name)?, Some(review_store.clone()));
+
+        review_store.file_diff_list.pop();
+        review_store.notes.remove(0);
+        review_store.diff_range.iterations.remove(0);
+        save_review(&storage, &review_name, &review_store)?;
+        assert_eq!(storage.load_review(&repository_name, &review_name)?, Some(review_store));
+        Ok(())
+    }
+
+    #[serial]
+    #[test]
+    fn test_detecting_changes_of_other_instances() -> anyhow::Result<()> {
+        let context = create_context();
+        let storage = ReviewHelperSqliteStorage::new(context.0.clone())?;
+        let other_storage = ReviewHelperSqliteStorage::new(context.0.clone())?;
+        storage.save_repository(&repository_store())?;
+        let repository_name = repository_store().name;
+        let review_name = ReviewName::from("fancy_ui");
+        save_review(&storage, &review_name, &review_store())?;
+
+        let mut review = storage.load_review(&repository_name, &review_name)?.expect("Should load the review!");
+        other_storage.load_review(&repository_name, &review_name)?;
+        // Saving the same content again changes nothing.
+        save_review(&other_storage, &review_name, &review)?;
+        assert!(!storage.is_review_changed(&repository_name, &review_name)?);
+
+        let note = NoteStore {
+            text: "from the other instance".to_string(),
+            ..Default::default()
+        };
+        other_storage.save_review_notes(&repository_name, &review_name, &[&note])?;
+        assert!(storage.is_review_changed(&repository_name, &review_name)?);
+        assert!(!other_storage.is_review_changed(&repository_name, &review_name)?);
+
+        review.file_diff_list[0].is_reviewed = !review.file_diff_list[0].is_reviewed;
+        let file_diffs = review.file_diff_list.iter().collect::<Vec<_>>();
+        assert!(matches!(
+            storage.save_review_file_diffs(&repository_name, &review_name, &review.diff_range, &file_diffs),
+            Err(StorageError::Conflict(_))
+        ));
+
+        let reloaded = storage.load_review(&repository_name, &review_name)?.expect("Should reload the review!");
+        assert!(!storage.is_review_changed(&repository_name, &review_name)?);
+        assert_eq!(reloaded.notes, vec![note]);
+        storage.save_review_file_diffs(&repository_name, &review_name, &review.diff_range, &file_diffs)?;
+        assert!(other_storage.is_review_changed(&repository_name, &review_name)?);
+        Ok(())
+    }
+
+    #[serial]
+    #[test]
+    fn test_opening_existing_schema() -> anyhow::Result<()> {
+        let context = create_context();
+        {
+            let storage = ReviewHelperSqliteStorage::new(context.0.clone())?;
+            storage.save_repository(&repository_store())?;
+            save_review(&storage, &ReviewName::from("fancy_ui"), &review_store())?;
+        }
+
+        let storage = ReviewHelperSqliteStorage::new(context.0.clone())?;
+        assert_eq!(
+            storage.load_review(&repository_store().name, &ReviewName::from("fancy_ui"))?,
+            Some(review_store())
+        );
+        drop(storage);
+
+        Connection::open(context.0.join(DATABASE_FILE_NAME))?.pragma_update(None, "user_version", SCHEMA_VERSION + 1)?;
+        assert!(matches!(
+            ReviewHelperSqliteStorage::new(context.0.clone()),
+            Err(StorageError::UnsupportedVersion(_))
+        ));
+        Ok(())
+    }
+
+    #[serial]
+    #[test]
+    fn test_renaming_and_removing_reviews() -> anyhow::Result<()> {
+        let context = create_context();
+        let storage = ReviewHelperSqliteStorage::new(context.0.clone())?;
+        storage.save_repository(&repository_store())?;
+
+        let repository_name = repository_store().name;
+        save_review(&storage, &ReviewName::from("fancy_ui"), &review_store())?;
+        save_review(&storage, &ReviewName::from("cool_feature"), &ReviewStore::default())?;
+        assert_eq!(
+            storage.load_review_names(&repository_name)?,
+            vec![ReviewName::from("cool_feature"), ReviewName::from("fancy_ui")]
+        );
+
+        storage.rename_review(&repository_name, &ReviewName::from("fancy_ui"), &ReviewName::from("cool_fancy_ui"))?;
+        assert!(
+            storage
+                .rename_review(&repository_name, &ReviewName::from("fancy_ui"), &ReviewName::from("other"))
+                .is_err()
+        );
+        assert_eq!(storage.load_review(&repository_name, &ReviewName::from("cool_fancy_ui"))?, Some(review_store()));
+
+        storage.delete_review(&repository_name, &ReviewName::from("cool_feature"))?;
+        assert!(storage.delete_review(&repository_name, &ReviewName::from("cool_feature")).is_err());
+        assert_eq!(storage.load_review_names(&repository_name)?, vec![ReviewName::from("cool_fancy_ui")]);
+
+        storage.delete_repository(&repository_name)?;
+        let remaining_notes: i64 = storage.connection.query_row("SELECT COUNT(*) FROM notes", [], |row| row.get(0))?;
+        assert_eq!(remaining_notes, 0);
+        Ok(())
+    }
+
+    #[serial]
+    #[test]
+    fn test_appending_review_events() -> anyhow::Result<()> {
+        let context = create_context();
+        let storage = ReviewHelperSqliteStorage::new(context.0.clone())?;
+        storage.save_repository(&repository_store())?;
+
+        let repository_name = repository_store().name;
+        let review_name = ReviewName::from("fancy_ui");
+        let event = ReviewEventStore {
+            time: "2024-03-01T10:00:00+01:00".to_string(),
+            author: "harry".to_string(),
+            event: ReviewEvent::DiffRangeChanged {
+                old_start: "ed7811b".to_string(),
+                old_end: String::new(),
+                new_start: "ed7811b".to_string(),
+                new_end: "a261b7b".to_string(),
+            },
+        };
+        assert!(
+            storage
+                .append_review_events(&repository_name, &review_name, std::slice::from_ref(&event))
+                .is_err()
+        );
+
+        save_review(&storage, &review_name, &review_store())?;
+        assert!(storage.load_review_events(&repository_name, &review_name)?.is_empty());
+        let mut other_event = event.clone();
+        other_event.event = ReviewEvent::NoteResolved {
+            context: "foo.md".to_string(),
+            text: "unused import".to_string(),
+            is_done: true,
+        };
+        storage.append_review_events(&repository_name, &review_name, &[event.clone(), other_event.clone()])?;
+        assert_eq!(storage.load_review_events(&repository_name, &review_name)?, vec![event, other_event]);
+
+        storage.delete_review(&repository_name, &review_name)?;
+        let remaining_events: i64 = storage.connection.query_row("SELECT COUNT(*) FROM review_events", [], |row| row.get(0))?;
+        assert_eq!(remaining_events, 0);
+        Ok(())
+    }
+
+    #[serial]
+    #[test]
+    fn test_saving_review_snapshots() -> anyhow::Result<()> {
+        let context = create_context();
+        let storage = ReviewHelperSqliteStorage::new(context.0.clone())?;
+        storage.save_repository(&repository_store())?;
+
+        let repository_name = repository_store().name;
+        let review_name = ReviewName::from("fancy_ui");
+        save_review(&storage, &review_name, &review_store())?;
+        assert!(storage.load_review_snapshots(&repository_name, &review_name)?.is_empty());
+
+        let mut snapshot = SnapshotStore {
+            name: "before_rebase".to_string(),
+            time: "2024-03-01T10:00:00+01:00".to_string(),
+            review: review_store(),
+        };
+        storage.save_review_snapshot(&repository_name, &review_name, &snapshot)?;
+        snapshot.review.notes.pop();
+        storage.save_review_snapshot(&repository_name, &review_name, &snapshot)?;
+        assert_eq!(storage.load_review_snapshots(&repository_name, &review_name)?, vec![snapshot]);
+
+        storage.delete_review_snapshot(&repository_name, &review_name, "before_rebase")?;
+        assert!(storage.delete_review_snapshot(&repository_name, &review_name, "before_rebase").is_err());
+        assert!(storage.load_review_snapshots(&repository_name, &review_name)?.is_empty());
+        let remaining_notes: i64 = storage.connection.query_row("SELECT COUNT(*) FROM snapshot_notes", [], |row| row.get(0))?;
+        assert_eq!(remaining_notes, 0);
+        Ok(())
+    }
+}
@@ -1,5 +1,12 @@
 use std::convert::From;
+use std::fmt;
 use std::path::PathBuf;
+use std::str::FromStr;
+
+use serde_derive::{Deserialize, Serialize};
+
+/// Stands for the index where a commit is expected, an empty commit stands for the working tree.
+pub const INDEX: &str = ":index";
 
 #[derive(Debug, Default, Clone, PartialEq, Eq, Hash)]
 pub struct RepositoryName(String);
@@ -51,22 +58,198 @@ pub struct RepositoryStore {
     pub base_branch: String,
 }
 
+/// An earlier pass over a review, the diff range it was reviewed at.
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct IterationStore {
+    pub start: String,
+    pub end: String,
+}
+
+/// What a diff range compares, the working tree and index are meant for a self-review before
+/// committing.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum DiffMode {
+    /// `start..end`, an empty end is the working tree.
+    #[default]
+    Commits,
+    /// The index against `start`, like `git diff --cached`.
+    Staged,
+    /// The working tree against the index.
+    Unstaged,
+    /// The working tree against `start`.
+    WorkingTree,
+}
+
+impl DiffMode {
+    pub const ALL: [DiffMode; 4] = [DiffMode::Commits, DiffMode::Staged, DiffMode::Unstaged, DiffMode::WorkingTree];
+}
+
+impl fmt::Display for DiffMode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            DiffMode::Commits => "commits",
+            DiffMode::Staged => "staged",
+            DiffMode::Unstaged => "unstaged",
+            DiffMode::WorkingTree => "working_tree",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+impl FromStr for DiffMode {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        DiffMode::ALL
+            .into_iter()
+            .find(|mode| mode.to_string() == s)
+            .ok_or_else(|| format!("Invalid diff mode '{}'", s))
+    }
+}
+
+/// `iterations` are the earlier diff ranges of the review, oldest first. With an
+/// `interdiff_iteration` (numbered from 1) only the changes since the end of that iteration
+/// are shown, restricted to the files of `start..end`.
 #[derive(Debug, Default, Clone, PartialEq)]
 pub struct DiffRangeStore {
     pub start: String,
     pub end: String,
+    pub mode: DiffMode,
+    pub iterations: Vec<IterationStore>,
+    pub interdiff_iteration: Option<usize>,
 }
 
+impl DiffRangeStore {
+    /// The iteration the interdiff starts at.
+    pub fn interdiff_base(&self) -> Option<&IterationStore> {
+        self.iterations.get(self.interdiff_iteration?.checked_sub(1)?)
+    }
+    /// The compared sides as git revisions, an empty one is the working tree and `INDEX` the index.
+    pub fn revisions(&self) -> (&str, &str) {
+        match self.mode {
+            DiffMode::Commits => (&self.start, &self.end),
+            DiffMode::Staged => (&self.start, INDEX),
+            DiffMode::Unstaged => (INDEX, ""),
+            DiffMode::WorkingTree => (&self.start, ""),
+        }
+    }
+    /// The commits the files are compared between, the interdiff starts at the end of its iteration.
+    pub fn compared_commits(&self) -> (&str, &str) {
+        let (start, end) = self.revisions();
+        match self.interdiff_base() {
+            Some(iteration) => (&iteration.end, end),
+            None => (start, end),
+        }
+    }
+    /// The diff range `start..end`, which keeps this one as iteration if it is replaced. Ranges
+    /// ending in the working copy or index can't be compared later and aren't kept.
+    pub fn next(&self, start: String, end: String, mode: DiffMode, interdiff_iteration: Option<usize>) -> DiffRangeStore {
+        let mut iterations = self.iterations.clone();
+        let current = IterationStore {
+            start: self.start.clone(),
+            end: self.end.clone(),
+        };
+        let is_replaced = self.start != start || self.end != end || self.mode != mode;
+        let is_complete = self.mode == DiffMode::Commits && !current.start.is_empty() && !current.end.is_empty();
+        if is_replaced && is_complete && iterations.last() != Some(&current) {
+            iterations.push(current);
+        }
+        let interdiff_iteration = interdiff_iteration.filter(|number| (1..=iterations.len()).contains(number));
+        DiffRangeStore {
+            start,
+            end,
+            mode,
+            iterations,
+            interdiff_iteration,
+        }
+    }
+}
+
+/// `reviewed_blob` is the git blob of the file when it was marked as reviewed. A reviewed file
+/// whose blob differs after the diff range was recomputed is reset and flagged as changed.
+/// `reviewed_commits` holds the commits in which the file was reviewed in commit-by-commit mode.
 #[derive(Debug, Default, Clone, PartialEq)]
 pub struct FileDiffStore {
     pub file_path: PathBuf,
     pub is_reviewed: bool,
+    pub reviewed_blob: Option<String>,
+    pub is_changed_since_review: bool,
+    pub reviewed_commits: Vec<String>,
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum DiffSide {
+    Old,
+    #[default]
+    New,
+}
+
+/// Inclusive, 1-based line range on one side of a file diff.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct LineRange {
+    pub side: DiffSide,
+    pub start: u32,
+    pub end: u32,
+}
+
+impl LineRange {
+    pub fn new(side: DiffSide, start: u32, end: u32) -> Self {
+        Self {
+            side,
+            start: start.min(end),
+            end: start.max(end),
+        }
+    }
+}
+
+impl fmt::Display for LineRange {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.side == DiffSide::Old {
+            write!(f, "old:")?;
+        }
+        if self.start == self.end {
+            write!(f, "{}", self.start)
+        } else {
+            write!(f, "{}-{}", self.start, self.end)
+        }
+    }
+}
+
+impl FromStr for LineRange {
+    type Err = String;
+
+    /// Parses `142`, `142-145`, `new:142-145` or `old:142-145`.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim();
+        let (side, range) = if let Some(range) = s.strip_prefix("old:") {
+            (DiffSide::Old, range)
+        } else if let Some(range) = s.strip_prefix("new:") {
+            (DiffSide::New, range)
+        } else {
+            (DiffSide::New, s)
+        };
+        let parse_line = |line: &str| -> Result<u32, String> {
+            match line.trim().parse::<u32>() {
+                Ok(0) | Err(_) => Err(format!("Invalid line number '{}'", line)),
+                Ok(line) => Ok(line),
+            }
+        };
+        match range.split_once('-') {
+            Some((start, end)) => Ok(LineRange::new(side, parse_line(start)?, parse_line(end)?)),
+            None => {
+                let line = parse_line(range)?;
+                Ok(LineRange::new(side, line, line))
+            }
+        }
+    }
 }
 
 #[derive(Debug, Default, Clone, PartialEq)]
 pub struct NoteStore {
     pub text: String,
     pub context: String,
+    pub line_range: Option<LineRange>,
+    pub is_outdated: bool,
     pub is_done: bool,
 }
 
@@ -77,28 +260,195 @@ pub struct ReviewStore {
     pub notes: Vec<NoteStore>,
 }
 
+/// A named copy of a review as it was stored when the snapshot was taken, `time` is a RFC 3339 timestamp.
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct SnapshotStore {
+    pub name: String,
+    pub time: String,
+    pub review: ReviewStore,
+}
+
+impl SnapshotStore {
+    /// A snapshot of the review as it is now.
+    pub fn new(name: String, review: ReviewStore) -> Self {
+        Self {
+            name,
+            time: chrono::Local::now().to_rfc3339_opts(chrono::SecondsFormat::Secs, false),
+            review,
+        }
+    }
+}
+
+/// One entry of the activity log of a review, notes are identified by their context and text.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum ReviewEvent {
+    FileReviewed {
+        file: String,
+        is_reviewed: bool,
+    },
+    FileReviewedInCommit {
+        file: String,
+        commit: String,
+        is_reviewed: bool,
+    },
+    FileChangedSinceReview {
+        file: String,
+    },
+    FileReviewCarriedOver {
+        file: String,
+    },
+    NoteAdded {
+        context: String,
+        text: String,
+    },
+    NoteEdited {
+        context: String,
+        text: String,
+    },
+    NoteResolved {
+        context: String,
+        text: String,
+        is_done: bool,
+    },
+    NoteDeleted {
+        context: String,
+        text: String,
+    },
+    DiffRangeChanged {
+        old_start: String,
+        old_end: String,
+        new_start: String,
+        new_end: String,
+    },
+    HistoryRewritten {
+        old_end: String,
+        new_end: String,
+    },
+    DiffModeChanged {
+        old_mode: String,
+        new_mode: String,
+    },
+    SnapshotTaken {
+        name: String,
+    },
+    SnapshotRestored {
+        name: String,
+    },
+}
+
+impl fmt::Display for ReviewEvent {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let in_context = |context: &str| if context.is_empty() { String::new() } else { format!(" in {}", context) };
+        let or_working_copy = |end: &str| if end.is_empty() { "working copy".to_string() } else { end.to_string() };
+        match self {
+            ReviewEvent::FileReviewed { file, is_reviewed: true } => write!(f, "Marked {} as reviewed", file),
+            ReviewEvent::FileReviewed { file, is_reviewed: false } => write!(f, "Marked {} as not reviewed", file),
+            ReviewEvent::FileReviewedInCommit {
+                file,
+                commit,
+                is_reviewed: true,
+            } => write!(f, "Marked {} as reviewed in commit {}", file, commit),
+            ReviewEvent::FileReviewedInCommit {
+                file,
+                commit,
+                is_reviewed: false,
+            } => write!(f, "Marked {} as not reviewed in commit {}", file, commit),
+            ReviewEvent::FileChangedSinceReview { file } => write!(f, "Reset {} to not reviewed, it changed since its review", file),
+            ReviewEvent::FileReviewCarriedOver { file } => write!(f, "Kept {} reviewed, its changes are the same in the rewritten history", file),
+            ReviewEvent::NoteAdded { context, text } => write!(f, "Added note{}: {}", in_context(context), text),
+            ReviewEvent::NoteEdited { context, text } => write!(f, "Edited note{}: {}", in_context(context), text),
+            ReviewEvent::NoteResolved { context, text, is_done: true } => write!(f, "Resolved note{}: {}", in_context(context), text),
+            ReviewEvent::NoteResolved { context, text, is_done: false } => write!(f, "Reopened note{}: {}", in_context(context), text),
+            ReviewEvent::NoteDeleted { context, text } => write!(f, "Deleted note{}: {}", in_context(context), text),
+            ReviewEvent::DiffRangeChanged {
+                old_start,
+                old_end,
+                new_start,
+                new_end,
+            } => write!(
+                f,
+                "Changed diff range from {}..{} to {}..{}",
+                old_start,
+                or_working_copy(old_end),
+                new_start,
+                or_working_copy(new_end)
+            ),
+            ReviewEvent::HistoryRewritten { old_end, new_end } => {
+                write!(f, "Rewritten history detected, {} is not part of {}", old_end, or_working_copy(new_end))
+            }
+            ReviewEvent::DiffModeChanged { old_mode, new_mode } => write!(f, "Changed diff mode from {} to {}", old_mode, new_mode),
+            ReviewEvent::SnapshotTaken { name } => write!(f, "Took snapshot {}", name),
+            ReviewEvent::SnapshotRestored { name } => write!(f, "Restored snapshot {}", name),
+        }
+    }
+}
+
+/// `time` is a RFC 3339 timestamp, `author` the git user of the repository.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ReviewEventStore {
+    pub time: String,
+    pub author: String,
+    #[serde(flatten)]
+    pub event: ReviewEvent,
+}
+
+impl ReviewEventStore {
+    /// An event that happens now.
+    pub fn new(author: String, event: ReviewEvent) -> Self {
+        Self {
+            time: chrono::Local::now().to_rfc3339_opts(chrono::SecondsFormat::Secs, false),
+            author,
+            event,
+        }
+    }
+}
+
 #[derive(thiserror::Error, Debug)]
 pub enum StorageError {
     #[error("Repository '{0}' not found!")]
     RepositoryNotFound(String),
     #[error("Review '{0}' not found!")]
     ReviewNotFound(String),
+    #[error("Snapshot '{0}' not found!")]
+    SnapshotNotFound(String),
     #[error("Io error occured: {0}")]
     Io(#[from] std::io::Error),
     #[error("Serialize error occured: {0}")]
     Serialize(String),
     #[error("Deserialize error occured: {0}")]
     Deserialize(String),
+    #[error("Missing key '{0}'")]
+    MissingKey(String),
+    #[error("Key '{0}' must be a {1}")]
+    InvalidValue(String, &'static str),
+    #[error("{0}: {1}")]
+    File(String, Box<StorageError>),
+    #[error("{0} was changed by another instance of review_helper")]
+    Conflict(String),
+    #[error("Unsupported storage format version {0}, the data was written by a newer review_helper")]
+    UnsupportedVersion(String),
+    #[error("Database error occured: {0}")]
+    Database(#[from] rusqlite::Error),
 }
 
 pub type StorageResult<T> = Result<T, StorageError>;
 
+/// Broken repositories are skipped while loading, so that the remaining ones are still usable.
+#[derive(Debug, Default)]
+pub struct LoadedRepositories {
+    pub repositories: Vec<RepositoryStore>,
+    pub errors: Vec<StorageError>,
+}
+
 pub trait ReviewHelperStorage {
-    fn load_repositories(&self) -> StorageResult<Vec<RepositoryStore>>;
+    fn load_repositories(&self) -> StorageResult<LoadedRepositories>;
     fn save_repository(&self, repository_store: &RepositoryStore) -> StorageResult<()>;
     fn delete_repository(&self, repository_name: &RepositoryName) -> StorageResult<()>;
     fn load_review_names(&self, repository_name: &RepositoryName) -> StorageResult<Vec<ReviewName>>;
     fn load_review(&self, repository_name: &RepositoryName, review_name: &ReviewName) -> StorageResult<Option<ReviewStore>>;
+    /// Whether the review was changed outside of this instance since it was loaded or saved.
+    fn is_review_changed(&self, repository_name: &RepositoryName, review_name: &ReviewName) -> StorageResult<bool>;
     fn delete_review(&self, repository_name: &RepositoryName, review_name: &ReviewName) -> StorageResult<()>;
     fn rename_review(&self, repository_name: &RepositoryName, old_review_name: &ReviewName, new_review_name: &ReviewName) -> StorageResult<()>;
     fn save_review_notes(&self, repository_name: &RepositoryName, review_name: &ReviewName, notes: &[&NoteStore]) -> StorageResult<()>;
@@ -109,4 +459,73 @@ pub trait ReviewHelperStorage {
         diff_range: &DiffRangeStore,
         file_diffs: &[&FileDiffStore],
     ) -> StorageResult<()>;
+    /// The activity log is only appended to, it is renamed and deleted together with its review.
+    fn append_review_events(&self, repository_name: &RepositoryName, review_name: &ReviewName, events: &[ReviewEventStore]) -> StorageResult<()>;
+    /// The events of the review in the order they were appended.
+    fn load_review_events(&self, repository_name: &RepositoryName, review_name: &ReviewName) -> StorageResult<Vec<ReviewEventStore>>;
+    /// Replaces a snapshot of the same name, snapshots are renamed and deleted together with their review.
+    fn save_review_snapshot(&self, repository_name: &RepositoryName, review_name: &ReviewName, snapshot: &SnapshotStore) -> StorageResult<()>;
+    /// The snapshots of the review, oldest first.
+    fn load_review_snapshots(&self, repository_name: &RepositoryName, review_name: &ReviewName) -> StorageResult<Vec<SnapshotStore>>;
+    fn delete_review_snapshot(&self, repository_name: &RepositoryName, review_name: &ReviewName, snapshot_name: &str) -> StorageResult<()>;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn diff_range(start: &str, end: &str) -> DiffRangeStore {
+        DiffRangeStore {
+            start: start.to_string(),
+            end: end.to_string(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_diff_range_iterations() {
+        let first = diff_range("ed7811b", "9f89049");
+        assert_eq!(first.compared_commits(), ("ed7811b", "9f89049"));
+
+        // An interdiff needs an earlier iteration.
+        let second = first.next("ed7811b".to_string(), "a261b7b".to_string(), DiffMode::Commits, Some(2));
+        assert_eq!(second.iterations.len(), 1);
+        assert_eq!(second.interdiff_iteration, None);
+
+        let second = first.next("ed7811b".to_string(), "a261b7b".to_string(), DiffMode::Commits, Some(1));
+        assert_eq!(second.interdiff_base().map(|iteration| iteration.end.as_str()), Some("9f89049"));
+        assert_eq!(second.compared_commits(), ("9f89049", "a261b7b"));
+
+        // Refreshing or switching back to the whole range keeps the iterations.
+        let refreshed = second.next("ed7811b".to_string(), "a261b7b".to_string(), DiffMode::Commits, None);
+        assert_eq!(refreshed.iterations, second.iterations);
+        assert_eq!(refreshed.compared_commits(), ("ed7811b", "a261b7b"));
+
+        // The working copy can't be compared later.
+        let working_copy = refreshed.next("ed7811b".to_string(), String::new(), DiffMode::Commits, None);
+        assert_eq!(working_copy.iterations.len(), 2);
+        let third = working_copy.next("ed7811b".to_string(), "c134268".to_string(), DiffMode::Commits, None);
+        assert_eq!(third.iterations, working_copy.iterations);
+    }
+
+    #[test]
+    fn test_diff_modes() {
+        let commits = diff_range("ed7811b", "9f89049");
+        let staged = commits.next("ed7811b".to_string(), "9f89049".to_string(), DiffMode::Staged, None);
+        assert_eq!(staged.compared_commits(), ("ed7811b", INDEX));
+        assert_eq!(staged.iterations.len(), 1);
+
+        // The index changes all the time, so it's never kept as iteration.
+        let unstaged = staged.next("ed7811b".to_string(), "9f89049".to_string(), DiffMode::Unstaged, None);
+        assert_eq!(unstaged.compared_commits(), (INDEX, ""));
+        assert_eq!(unstaged.iterations, staged.iterations);
+
+        let working_tree = unstaged.next("ed7811b".to_string(), String::new(), DiffMode::WorkingTree, None);
+        assert_eq!(working_tree.compared_commits(), ("ed7811b", ""));
+
+        for mode in DiffMode::ALL {
+            assert_eq!(mode.to_string().parse::<DiffMode>(), Ok(mode));
+        }
+        assert!("index".parse::<DiffMode>().is_err());
+    }
 }
@@ -0,0 +1,475 @@
+use crate::model::model_utils::extension_from_filename;
+
+/// Lines longer than this are not tokenized, e.g. minified files.
+const MAX_HIGHLIGHT_LINE_LENGTH: usize = 1000;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Language {
+    Rust,
+    Cpp,
+    Python,
+    Toml,
+    Markdown,
+    Slint,
+    PlainText,
+}
+
+impl Language {
+    pub fn from_file_name(file_name: &str) -> Language {
+        let extension = extension_from_filename(file_name).map(|e| e.to_lowercase());
+        match extension.as_deref() {
+            Some("rs") => Language::Rust,
+            Some("c" | "h" | "cc" | "cpp" | "cxx" | "hh" | "hpp" | "hxx" | "inl") => Language::Cpp,
+            Some("py" | "pyi") => Language::Python,
+            Some("toml") => Language::Toml,
+            Some("md" | "markdown") => Language::Markdown,
+            Some("slint") => Language::Slint,
+            _ => Language::PlainText,
+        }
+    }
+
+    fn keywords(&self) -> &'static [&'static str] {
+        match self {
+            Language::Rust => &[
+                "as", "async", "await", "break", "const", "continue", "crate", "dyn", "else", "enum", "extern", "false", "fn", "for", "if", "impl", "in",
+                "let", "loop", "match", "mod", "move", "mut", "pub", "ref", "return", "self", "Self", "static", "struct", "super", "trait", "true", "type",
+                "unsafe", "use", "where", "while",
+            ],
+            Language::Cpp => &[
+                "auto",
+                "bool",
+                "break",
+                "case",
+                "catch",
+                "char",
+                "class",
+                "const",
+                "constexpr",
+                "continue",
+                "default",
+                "delete",
+                "do",
+                "double",
+                "else",
+                "enum",
+                "explicit",
+                "extern",
+                "false",
+                "float",
+                "for",
+                "friend",
+                "if",
+                "inline",
+                "int",
+                "long",
+                "namespace",
+                "new",
+                "noexcept",
+                "nullptr",
+                "operator",
+                "override",
+                "private",
+                "protected",
+                "public",
+                "return",
+                "short",
+                "signed",
+                "sizeof",
+                "static",
+                "struct",
+                "switch",
+                "template",
+                "this",
+                "throw",
+                "true",
+                "try",
+                "typedef",
+                "typename",
+                "union",
+                "unsigned",
+                "using",
+                "virtual",
+                "void",
+                "volatile",
+                "while",
+            ],
+            Language::Python => &[
+                "and", "as", "assert", "async", "await", "break", "class", "continue", "def", "del", "elif", "else", "except", "False", "finally", "for",
+                "from", "global", "if", "import", "in", "is", "lambda", "None", "nonlocal", "not", "or", "pass", "raise", "return", "True", "try", "while",
+                "with", "yield",
+            ],
+            Language::Slint => &[
+                "animate",
+                "callback",
+                "changed",
+                "component",
+                "else",
+                "enum",
+                "export",
+                "false",
+                "for",
+                "from",
+                "function",
+                "global",
+                "if",
+                "import",
+                "in",
+                "in-out",
+                "inherits",
+                "out",
+                "private",
+                "property",
+                "public",
+                "pure",
+                "return",
+                "root",
+                "parent",
+                "self",
+                "states",
+                "struct",
+                "transitions",
+                "true",
+                "when",
+            ],
+            Language::Toml => &["true", "false"],
+            Language::Markdown | Language::PlainText => &[],
+        }
+    }
+
+    fn line_comment(&self) -> Option<&'static str> {
+        match self {
+            Language::Rust | Language::Cpp | Language::Slint => Some("//"),
+            Language::Python | Language::Toml => Some("#"),
+            Language::Markdown | Language::PlainText => None,
+        }
+    }
+
+    fn has_block_comments(&self) -> bool {
+        matches!(self, Language::Rust | Language::Cpp | Language::Slint)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TokenKind {
+    Plain,
+    Keyword,
+    Type,
+    String,
+    Number,
+    Comment,
+    Attribute,
+    Heading,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Span {
+    pub text: String,
+    pub kind: TokenKind,
+}
+
+/// State that is carried from one line to the next.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+enum LineState {
+    #[default]
+    Normal,
+    BlockComment,
+    TripleQuote(char),
+    CodeBlock,
+}
+
+pub struct Highlighter {
+    language: Language,
+    state: LineState,
+}
+
+impl Highlighter {
+    pub fn new(language: Language) -> Self {
+        Self {
+            language,
+            state: LineState::Normal,
+        }
+    }
+
+    /// Forgets multi-line state, e.g. at the start of a new diff hunk.
+    pub fn reset(&mut self) {
+        self.state = LineState::Normal;
+    }
+
+    pub fn highlight_line(&mut self, line: &str) -> Vec<Span> {
+        if self.language == Language::PlainText || line.len() > MAX_HIGHLIGHT_LINE_LENGTH {
+            return vec![Span {
+                text: line.to_string(),
+                kind: TokenKind::Plain,
+            }];
+        }
+        let mut spans = Spans::default();
+        match self.language {
+            Language::Markdown => self.highlight_markdown(line, &mut spans),
+            _ => self.highlight_code(line, &mut spans),
+        }
+        spans.0
+    }
+
+    fn highlight_markdown(&mut self, line: &str, spans: &mut Spans) {
+        let trimmed = line.trim_start();
+        if trimmed.starts_with("```") {
+            self.state = if self.state == LineState::CodeBlock {
+                LineState::Normal
+            } else {
+                LineState::CodeBlock
+            };
+            spans.push(line, TokenKind::String);
+        } else if self.state == LineState::CodeBlock {
+            spans.push(line, TokenKind::String);
+        } else if trimmed.starts_with('#') {
+            spans.push(line, TokenKind::Heading);
+        } else {
+            let parts = line.split('`').collect::<Vec<_>>();
+            let is_closed = parts.len() % 2 == 1;
+            for (index, part) in parts.iter().enumerate() {
+                if index % 2 == 1 && (is_closed || index + 1 < parts.len()) {
+                    spans.push(&format!("`{}`", part), TokenKind::String);
+                } else if index % 2 == 1 {
+                    spans.push(&format!("`{}", part), TokenKind::Plain);
+                } else {
+                    spans.push(part, TokenKind::Plain);
+                }
+            }
+        }
+    }
+
+    fn highlight_code(&mut self, line: &str, spans: &mut Spans) {
+        let language = self.language;
+        let chars = line.chars().collect::<Vec<_>>();
+        let text = |from: usize, to: usize| -> String { chars[from..to].iter().collect() };
+        let starts_with = |pos: usize, pattern: &str| -> bool {
+            let pattern = pattern.chars().collect::<Vec<_>>();
+            chars.len() >= pos + pattern.len() && chars[pos..pos + pattern.len()] == pattern[..]
+        };
+        let find = |from: usize, pattern: &str| -> Option<usize> { (from..chars.len()).find(|pos| starts_with(*pos, pattern)) };
+
+        let trimmed = line.trim_start();
+        if self.state == LineState::Normal {
+            let is_attribute_line = match language {
+                Language::Rust => trimmed.starts_with("#[") || trimmed.starts_with("#!["),
+                Language::Cpp => trimmed.starts_with('#'),
+                _ => false,
+            };
+            if is_attribute_line {
+                spans.push(line, TokenKind::Attribute);
+                return;
+            }
+            if language == Language::Toml && trimmed.starts_with('[') {
+                spans.push(line, TokenKind::Heading);
+                return;
+            }
+        }
+
+        let mut pos = 0;
+        while pos < chars.len() {
+            match self.state {
+                LineState::BlockComment => {
+                    let end = find(pos, "*/").map(|end| end + 2);
+                    spans.push(&text(pos, end.unwrap_or(chars.len())), TokenKind::Comment);
+                    if end.is_some() {
+                        self.state = LineState::Normal;
+                    }
+                    pos = end.unwrap_or(chars.len());
+                    continue;
+                }
+                LineState::TripleQuote(quote) => {
+                    let closing = String::from_iter([quote; 3]);
+                    let end = find(pos, &closing).map(|end| end + 3);
+                    spans.push(&text(pos, end.unwrap_or(chars.len())), TokenKind::String);
+                    if end.is_some() {
+                        self.state = LineState::Normal;
+                    }
+                    pos = end.unwrap_or(chars.len());
+                    continue;
+                }
+                LineState::Normal | LineState::CodeBlock => {}
+            }
+
+            let c = chars[pos];
+            if let Some(comment) = language.line_comment()
+                && starts_with(pos, comment)
+            {
+                spans.push(&text(pos, chars.len()), TokenKind::Comment);
+                break;
+            }
+            if language.has_block_comments() && starts_with(pos, "/*") {
+                self.state = LineState::BlockComment;
+                spans.push("/*", TokenKind::Comment);
+                pos += 2;
+                continue;
+            }
+            if language == Language::Python && (starts_with(pos, "\"\"\"") || starts_with(pos, "'''")) {
+                self.state = LineState::TripleQuote(c);
+                spans.push(&text(pos, pos + 3), TokenKind::String);
+                pos += 3;
+                continue;
+            }
+            if c == '"' || (c == '\'' && is_char_literal(language, &chars, pos)) {
+                let mut end = pos + 1;
+                while end < chars.len() && chars[end] != c {
+                    end += if chars[end] == '\\' { 2 } else { 1 };
+                }
+                let end = (end + 1).min(chars.len());
+                spans.push(&text(pos, end), TokenKind::String);
+                pos = end;
+                continue;
+            }
+            if c.is_ascii_digit() {
+                let end = (pos..chars.len())
+                    .find(|p| !(chars[*p].is_alphanumeric() || chars[*p] == '_' || chars[*p] == '.'))
+                    .unwrap_or(chars.len());
+                spans.push(&text(pos, end), TokenKind::Number);
+                pos = end;
+                continue;
+            }
+            if c == '@' && language == Language::Slint {
+                let end = (pos + 1..chars.len()).find(|p| !is_identifier_char(language, chars[*p])).unwrap_or(chars.len());
+                spans.push(&text(pos, end), TokenKind::Attribute);
+                pos = end;
+                continue;
+            }
+            if c.is_alphabetic() || c == '_' {
+                let end = (pos..chars.len()).find(|p| !is_identifier_char(language, chars[*p])).unwrap_or(chars.len());
+                let word = text(pos, end);
+                let kind = if language.keywords().contains(&word.as_str()) {
+                    TokenKind::Keyword
+                } else if language == Language::Toml && trimmed.starts_with(word.as_str()) && pos == chars.len() - trimmed.chars().count() {
+                    TokenKind::Attribute
+                } else if c.is_uppercase() && language != Language::Toml {
+                    TokenKind::Type
+                } else if language == Language::Rust && chars.get(end) == Some(&'!') {
+                    TokenKind::Attribute
+                } else {
+                    TokenKind::Plain
+                };
+                spans.push(&word, kind);
+                pos = end;
+                continue;
+            }
+            spans.push(&c.to_string(), TokenKind::Plain);
+            pos += 1;
+        }
+    }
+}
+
+fn is_identifier_char(language: Language, c: char) -> bool {
+    c.is_alphanumeric() || c == '_' || (c == '-' && matches!(language, Language::Slint | Language::Toml))
+}
+
+/// Distinguishes `'a'` and `'\n'` from lifetimes like `'a` in Rust.
+fn is_char_literal(language: Language, chars: &[char], pos: usize) -> bool {
+    match language {
+        Language::Rust => match chars.get(pos + 1) {
+            Some('\\') => true,
+            Some(_) => chars.get(pos + 2) == Some(&'\''),
+            None => false,
+        },
+        Language::Cpp | Language::Python | Language::Toml => true,
+        _ => false,
+    }
+}
+
+/// Collects spans and merges neighbours of the same kind.
+#[derive(Default)]
+struct Spans(Vec<Span>);
+
+impl Spans {
+    fn push(&mut self, text: &str, kind: TokenKind) {
+        if text.is_empty() {
+            return;
+        }
+        match self.0.last_mut() {
+            Some(last) if last.kind == kind => last.text.push_str(text),
+            _ => self.0.push(Span { text: text.to_string(), kind }),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn span(text: &str, kind: TokenKind) -> Span {
+        Span { text: text.to_string(), kind }
+    }
+
+    #[test]
+    fn test_language_from_file_name() {
+        assert_eq!(Language::from_file_name("src/git_utils.rs"), Language::Rust);
+        assert_eq!(Language::from_file_name("include/foo.HPP"), Language::Cpp);
+        assert_eq!(Language::from_file_name("ui/appwindow.slint"), Language::Slint);
+        assert_eq!(Language::from_file_name("Cargo.toml"), Language::Toml);
+        assert_eq!(Language::from_file_name("assets/icon.png"), Language::PlainText);
+        assert_eq!(Language::from_file_name("Makefile"), Language::PlainText);
+    }
+
+    #[test]
+    fn test_highlight_rust() {
+        let mut highlighter = Highlighter::new(Language::Rust);
+
+        assert_eq!(
+            highlighter.highlight_line("let s: &'a str = \"x\"; // done"),
+            vec![
+                span("let", TokenKind::Keyword),
+                span(" s: &'a str = ", TokenKind::Plain),
+                span("\"x\"", TokenKind::String),
+                span("; ", TokenKind::Plain),
+                span("// done", TokenKind::Comment),
+            ]
+        );
+
+        assert_eq!(highlighter.highlight_line("/* open"), vec![span("/* open", TokenKind::Comment)]);
+        assert_eq!(
+            highlighter.highlight_line("close */ 42"),
+            vec![span("close */", TokenKind::Comment), span(" ", TokenKind::Plain), span("42", TokenKind::Number)]
+        );
+    }
+
+    #[test]
+    fn test_highlight_python_triple_quotes() {
+        let mut highlighter = Highlighter::new(Language::Python);
+
+        assert_eq!(
+            highlighter.highlight_line("def f(): \"\"\"doc"),
+            vec![
+                span("def", TokenKind::Keyword),
+                span(" f(): ", TokenKind::Plain),
+                span("\"\"\"doc", TokenKind::String),
+            ]
+        );
+        assert_eq!(
+            highlighter.highlight_line("end\"\"\" # x"),
+            vec![
+                span("end\"\"\"", TokenKind::String),
+                span(" ", TokenKind::Plain),
+                span("# x", TokenKind::Comment)
+            ]
+        );
+    }
+
+    #[test]
+    fn test_highlight_markdown() {
+        let mut highlighter = Highlighter::new(Language::Markdown);
+
+        assert_eq!(highlighter.highlight_line("# Title"), vec![span("# Title", TokenKind::Heading)]);
+        assert_eq!(
+            highlighter.highlight_line("use `cargo` or `make"),
+            vec![
+                span("use ", TokenKind::Plain),
+                span("`cargo`", TokenKind::String),
+                span(" or `make", TokenKind::Plain)
+            ]
+        );
+    }
+
+    #[test]
+    fn test_highlight_plain_text() {
+        let mut highlighter = Highlighter::new(Language::PlainText);
+        assert_eq!(highlighter.highlight_line("fn main() {}"), vec![span("fn main() {}", TokenKind::Plain)]);
+    }
+}
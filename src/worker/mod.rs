@@ -3,6 +3,8 @@ mod worker_impl;
 pub use self::worker_impl::*;
 
 mod review_helper_settings;
+mod review_history;
+mod storage_watcher;
 mod ui_updater;
 
-use review_helper_settings::ReviewHelperSettings;
+pub use review_helper_settings::ReviewHelperSettings;
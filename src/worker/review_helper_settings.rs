@@ -5,6 +5,8 @@ use std::{
 
 use serde_derive::{Deserialize, Serialize};
 
+use crate::storage::StorageBackend;
+
 const REVIEW_HELPER_CONFIG_FILENAME: &str = "review_helper_settings.toml";
 
 #[derive(Serialize, Deserialize)]
@@ -13,6 +15,9 @@ pub struct ReviewHelperSettings {
     pub editor: String,
     pub editor_args: Vec<String>,
     pub color_scheme: String,
+    /// Takes effect on the next start.
+    #[serde(default)]
+    pub storage_backend: StorageBackend,
     #[serde(skip)]
     path: PathBuf,
 }
@@ -24,6 +29,7 @@ impl Default for ReviewHelperSettings {
             editor: "code".to_string(),
             editor_args: vec!["-n".to_string(), "{file}".to_string()],
             color_scheme: "Dark".to_string(),
+            storage_backend: StorageBackend::default(),
             path: PathBuf::new(),
         }
     }
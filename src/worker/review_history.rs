@@ -0,0 +1,131 @@
+//! Undo and redo of review edits. Every edit is recorded as its inverse, applying an inverse
+//! yields the inverse of the inverse, which is pushed onto the opposite stack.
+
+use crate::repositories::{FileDiffId, FileDiffs, NoteId, Notes};
+use crate::storage::repository_storage::{DiffRangeStore, LineRange, NoteStore};
+use crate::worker::NoteChangeType;
+
+const MAX_HISTORY_LENGTH: usize = 100;
+
+pub struct NoteAnchor {
+    pub note_id: NoteId,
+    pub line_range: Option<LineRange>,
+    pub is_outdated: bool,
+}
+
+impl NoteAnchor {
+    pub fn of_note(note_id: &NoteId, note: &NoteStore) -> Self {
+        Self {
+            note_id: note_id.clone(),
+            line_range: note.line_range,
+            is_outdated: note.is_outdated,
+        }
+    }
+}
+
+/// The anchors of all notes, which are relocated when the diff range changes.
+pub fn note_anchors(notes: &Notes) -> Vec<NoteAnchor> {
+    notes.iter().map(|(note_id, note)| NoteAnchor::of_note(note_id, note)).collect()
+}
+
+pub enum ReviewEdit {
+    Note {
+        note_id: NoteId,
+        change_type: NoteChangeType,
+    },
+    /// With a commit the file is marked in that commit of the commit-by-commit mode only.
+    FileDiff {
+        file_diff_id: FileDiffId,
+        is_reviewed: bool,
+        commit: Option<String>,
+    },
+    /// Adds a new note, or restores a deleted one under its previous id.
+    AddNote {
+        note_id: Option<NoteId>,
+        note: NoteStore,
+    },
+    DeleteNote(NoteId),
+    NoteAnchors(Vec<NoteAnchor>),
+    DiffRange {
+        diff_range: DiffRangeStore,
+        file_diffs: FileDiffs,
+        note_anchors: Vec<NoteAnchor>,
+    },
+}
+
+/// The history of one review, it lives as long as the review stays loaded.
+#[derive(Default)]
+pub struct ReviewHistory {
+    undo_edits: Vec<ReviewEdit>,
+    redo_edits: Vec<ReviewEdit>,
+}
+
+fn push_bounded(edits: &mut Vec<ReviewEdit>, edit: ReviewEdit) {
+    if edits.len() == MAX_HISTORY_LENGTH {
+        edits.remove(0);
+    }
+    edits.push(edit);
+}
+
+impl ReviewHistory {
+    /// Records the inverse of a new edit, the undone edits can't be redone afterwards.
+    pub fn record(&mut self, inverse: ReviewEdit) {
+        self.redo_edits.clear();
+        push_bounded(&mut self.undo_edits, inverse);
+    }
+    pub fn pop_undo(&mut self) -> Option<ReviewEdit> {
+        self.undo_edits.pop()
+    }
+    pub fn pop_redo(&mut self) -> Option<ReviewEdit> {
+        self.redo_edits.pop()
+    }
+    /// Keeps the inverse of an undone edit for redo.
+    pub fn push_redo(&mut self, inverse: ReviewEdit) {
+        push_bounded(&mut self.redo_edits, inverse);
+    }
+    /// Keeps the inverse of a redone edit for undo.
+    pub fn push_undo(&mut self, inverse: ReviewEdit) {
+        push_bounded(&mut self.undo_edits, inverse);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn is_delete_of(edit: Option<ReviewEdit>, id: usize) -> bool {
+        matches!(edit, Some(ReviewEdit::DeleteNote(note_id)) if note_id == NoteId::from(id))
+    }
+
+    #[test]
+    fn test_review_history() {
+        let mut history = ReviewHistory::default();
+        assert!(history.pop_undo().is_none());
+
+        history.record(ReviewEdit::DeleteNote(NoteId::from(1usize)));
+        history.record(ReviewEdit::DeleteNote(NoteId::from(2usize)));
+        assert!(is_delete_of(history.pop_undo(), 2));
+        history.push_redo(ReviewEdit::DeleteNote(NoteId::from(3usize)));
+
+        assert!(is_delete_of(history.pop_redo(), 3));
+        history.push_undo(ReviewEdit::DeleteNote(NoteId::from(4usize)));
+        assert!(history.pop_redo().is_none());
+
+        // A new edit drops the undone edits.
+        history.push_redo(ReviewEdit::DeleteNote(NoteId::from(5usize)));
+        history.record(ReviewEdit::DeleteNote(NoteId::from(6usize)));
+        assert!(history.pop_redo().is_none());
+        assert!(is_delete_of(history.pop_undo(), 6));
+        assert!(is_delete_of(history.pop_undo(), 4));
+        assert!(is_delete_of(history.pop_undo(), 1));
+
+        for id in 0..MAX_HISTORY_LENGTH + 10 {
+            history.record(ReviewEdit::DeleteNote(NoteId::from(id)));
+        }
+        let mut undo_count = 0;
+        while history.pop_undo().is_some() {
+            undo_count += 1;
+        }
+        assert_eq!(undo_count, MAX_HISTORY_LENGTH);
+    }
+}
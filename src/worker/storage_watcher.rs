@@ -0,0 +1,59 @@
+use std::path::Path;
+
+use notify::{Event, EventKind, RecommendedWatcher, RecursiveMode, Watcher};
+
+use crate::worker::{WorkerChannel, WorkerMessage};
+
+/// Watches the storage directory, so that reviews edited outside of review_helper,
+/// e.g. `notes.md` in a text editor, are reloaded. The watcher stops when it is dropped.
+pub fn watch_storage(storage_path: &Path, channel: WorkerChannel) -> notify::Result<RecommendedWatcher> {
+    let mut watcher = notify::recommended_watcher(move |result: notify::Result<Event>| match result {
+        Ok(event) if is_storage_change(&event) => {
+            let _ = channel.send(WorkerMessage::StorageChanged);
+        }
+        Ok(_) => {}
+        Err(e) => log::warn!("Watching the storage failed: {}", e),
+    })?;
+    watcher.watch(storage_path, RecursiveMode::Recursive)?;
+    Ok(watcher)
+}
+
+/// Only changes of review files and notes count, not the lock, temporary or backup files.
+fn is_storage_change(event: &Event) -> bool {
+    let is_modification = matches!(event.kind, EventKind::Create(_) | EventKind::Modify(_) | EventKind::Remove(_));
+    is_modification
+        && event
+            .paths
+            .iter()
+            .any(|path| path.extension().is_some_and(|extension| extension == "toml" || extension == "md"))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    use notify::event::{AccessKind, AccessMode, CreateKind, ModifyKind, RenameMode};
+
+    use super::*;
+
+    #[test]
+    fn test_is_storage_change() {
+        let event = |kind, path: &str| Event::new(kind).add_path(PathBuf::from(path));
+
+        assert!(is_storage_change(&event(EventKind::Modify(ModifyKind::Any), "/data/repo/review/notes.md")));
+        assert!(is_storage_change(&event(EventKind::Create(CreateKind::File), "/data/repo/review/review.toml")));
+        assert!(is_storage_change(
+            &event(EventKind::Modify(ModifyKind::Name(RenameMode::Both)), "/data/repo/review/review.toml.tmp")
+                .add_path(PathBuf::from("/data/repo/review/review.toml"))
+        ));
+        assert!(!is_storage_change(&event(
+            EventKind::Access(AccessKind::Close(AccessMode::Write)),
+            "/data/repo/review/notes.md"
+        )));
+        assert!(!is_storage_change(&event(EventKind::Modify(ModifyKind::Any), "/data/review_helper.lock")));
+        assert!(!is_storage_change(&event(
+            EventKind::Modify(ModifyKind::Any),
+            "/data/repo/review/review.toml.bak"
+        )));
+    }
+}
@@ -9,6 +9,7 @@ use slint::{ComponentHandle, Model, ModelExt, SharedString, VecModel};
 
 use crate::cast_model;
 use crate::check_result;
+use crate::content_diff::{DiffCell, SideBySideRow};
 use crate::git_utils;
 use crate::git_utils::DiffStatus;
 use crate::model::IdModel;
@@ -16,6 +17,7 @@ use crate::model::model_utils;
 use crate::repositories::FileDiffId;
 use crate::storage::RepositoryStore;
 use crate::storage::repository_storage::FileDiffStore;
+use crate::syntax_highlighting::{Span, TokenKind};
 use crate::ui::SlintChangeTypeOccurrence;
 use crate::ui::SlintContextType;
 use crate::ui::{self, SlintFileDiff, SlintNote, SlintReview};
@@ -44,6 +46,7 @@ impl From<(&FileDiffId, &FileDiffStore)> for ui::SlintFileDiff {
         SlintFileDiff {
             id: id.as_i32(),
             is_reviewed: file_diff_store.is_reviewed,
+            is_changed_since_review: file_diff_store.is_changed_since_review,
             file_path: SharedString::from(file_diff_store.file_path.to_string_lossy().as_ref()),
             ..Default::default()
         }
@@ -87,12 +90,14 @@ impl UiUpdater {
             let editor = SharedString::from(&review_helper_settings.editor);
             let editor_args = SharedString::from(&review_helper_settings.editor_args.join(","));
             let color_scheme = SharedString::from(&review_helper_settings.color_scheme);
+            let storage_backend = SharedString::from(review_helper_settings.storage_backend.as_str());
 
             move |app_window| {
                 app_window.global::<ui::SlintReviewHelperSettings>().set_diff_tool(diff_tool);
                 app_window.global::<ui::SlintReviewHelperSettings>().set_editor(editor);
                 app_window.global::<ui::SlintReviewHelperSettings>().set_editor_args(editor_args);
                 app_window.global::<ui::SlintReviewHelperSettings>().set_color_scheme(color_scheme.clone());
+                app_window.global::<ui::SlintReviewHelperSettings>().set_storage_backend(storage_backend);
                 app_window.set_config_color_scheme(color_scheme);
             }
         });
@@ -190,11 +195,15 @@ impl UiUpdater {
             review.end_diff = end_diff;
             review.is_loaded = true;
             review.review_progress.total_count = ui_file_diffs.len() as i32;
+            review.review_progress.completed_count = 0;
             review.note_progress.total_count = ui_notes.len() as i32;
+            review.note_progress.completed_count = 0;
 
             let mut file_notes_map: HashMap<String, Rc<VecModel<i32>>> = HashMap::new();
 
+            // Reloaded reviews replace the previous contents.
             let notes_model = cast_model!(review.note_model, IdModel<ui::SlintNote>);
+            notes_model.clear();
             ui_notes.into_iter().enumerate().for_each(|(index, ui_note)| {
                 if ui_note.context_type == SlintContextType::File {
                     file_notes_map
@@ -209,6 +218,7 @@ impl UiUpdater {
             });
 
             let file_diff_model = cast_model!(review.file_diff_model, IdModel<ui::SlintFileDiff>);
+            file_diff_model.clear();
             ui_file_diffs.into_iter().for_each(|(file_diff_id, store)| {
                 if store.is_reviewed {
                     review.review_progress.completed_count += 1;
@@ -223,6 +233,7 @@ impl UiUpdater {
                         id: file_diff_id,
                         file_path: SharedString::from(file_path),
                         is_reviewed: store.is_reviewed,
+                        is_changed_since_review: store.is_changed_since_review,
                         referenced_notes: referenced_notes.into(),
                         ..Default::default()
                     },
@@ -253,6 +264,11 @@ impl UiUpdater {
                         name: name.clone(),
                         note_model: Rc::new(IdModel::default()).into(),
                         file_diff_model: Rc::new(IdModel::default()).into(),
+                        event_model: Rc::new(VecModel::default()).into(),
+                        snapshot_model: Rc::new(VecModel::default()).into(),
+                        snapshot_comparison: Rc::new(VecModel::default()).into(),
+                        iteration_model: Rc::new(VecModel::default()).into(),
+                        commit_model: Rc::new(VecModel::default()).into(),
                         is_loaded: false,
                         difference_statistics: ui::SlintDifferenceStatistics {
                             added_lines: 0,
@@ -280,6 +296,11 @@ impl UiUpdater {
                     name: review_name,
                     note_model: Rc::new(IdModel::default()).into(),
                     file_diff_model: Rc::new(IdModel::default()).into(),
+                    event_model: Rc::new(VecModel::default()).into(),
+                    snapshot_model: Rc::new(VecModel::default()).into(),
+                    snapshot_comparison: Rc::new(VecModel::default()).into(),
+                    iteration_model: Rc::new(VecModel::default()).into(),
+                    commit_model: Rc::new(VecModel::default()).into(),
                     is_loaded: true,
                     difference_statistics: ui::SlintDifferenceStatistics {
                         added_lines: 0,
@@ -308,6 +329,86 @@ impl UiUpdater {
             review_model.remove(review_id);
         });
     }
+    /// Replaces the activity log of the review, `ui_events` are in the order they happened.
+    pub fn set_review_events(&self, repository_id: usize, review_id: usize, ui_events: Vec<ui::SlintReviewEvent>) {
+        self.execute_in_event_loop(move |app_window| {
+            let review = model_utils::get_slint_review(&app_window, repository_id, review_id)
+                .unwrap_or_else(|| panic!("[BUG] RepositoryId {} ReviewId {} not found", repository_id, review_id));
+            let event_model = cast_model!(review.event_model, VecModel<ui::SlintReviewEvent>);
+            event_model.set_vec(ui_events.into_iter().rev().collect::<Vec<_>>());
+        });
+    }
+    /// New events are shown on top of the activity log.
+    pub fn add_review_events(&self, repository_id: usize, review_id: usize, ui_events: Vec<ui::SlintReviewEvent>) {
+        self.execute_in_event_loop(move |app_window| {
+            let review = model_utils::get_slint_review(&app_window, repository_id, review_id)
+                .unwrap_or_else(|| panic!("[BUG] RepositoryId {} ReviewId {} not found", repository_id, review_id));
+            let event_model = cast_model!(review.event_model, VecModel<ui::SlintReviewEvent>);
+            ui_events.into_iter().for_each(|ui_event| event_model.insert(0, ui_event));
+        });
+    }
+    /// Replaces the snapshots of the review, a previous comparison doesn't apply anymore.
+    pub fn set_snapshots(&self, repository_id: usize, review_id: usize, ui_snapshots: Vec<ui::SlintSnapshot>) {
+        self.execute_in_event_loop(move |app_window| {
+            let review = model_utils::get_slint_review(&app_window, repository_id, review_id)
+                .unwrap_or_else(|| panic!("[BUG] RepositoryId {} ReviewId {} not found", repository_id, review_id));
+            cast_model!(review.snapshot_model, VecModel<ui::SlintSnapshot>).set_vec(ui_snapshots);
+            cast_model!(review.snapshot_comparison, VecModel<SharedString>).clear();
+        });
+    }
+    pub fn set_iterations(&self, repository_id: usize, review_id: usize, ui_iterations: Vec<SharedString>, interdiff_iteration: i32) {
+        self.execute_in_event_loop(move |app_window| {
+            let review_model =
+                model_utils::get_review_model(&app_window, repository_id).unwrap_or_else(|| panic!("[BUG] RepositoryId {} not found", repository_id));
+            let review_model = cast_model!(review_model, IdModel<ui::SlintReview>);
+            let mut review = review_model.get(review_id).unwrap_or_else(|| panic!("[BUG] ReviewId {} not found", review_id));
+            cast_model!(review.iteration_model, VecModel<SharedString>).set_vec(ui_iterations);
+            review.interdiff_iteration = interdiff_iteration;
+            review_model.update(review_id, review);
+        });
+    }
+    pub fn set_commits_of_review(&self, repository_id: usize, review_id: usize, ui_commits: Vec<SharedString>, commit_index: i32) {
+        self.execute_in_event_loop(move |app_window| {
+            let review_model =
+                model_utils::get_review_model(&app_window, repository_id).unwrap_or_else(|| panic!("[BUG] RepositoryId {} not found", repository_id));
+            let review_model = cast_model!(review_model, IdModel<ui::SlintReview>);
+            let mut review = review_model.get(review_id).unwrap_or_else(|| panic!("[BUG] ReviewId {} not found", review_id));
+            cast_model!(review.commit_model, VecModel<SharedString>).set_vec(ui_commits);
+            review.commit_index = commit_index;
+            review_model.update(review_id, review);
+        });
+    }
+    /// The progress of the commit-by-commit mode rolls up the files of all commits.
+    pub fn set_review_progress(&self, repository_id: usize, review_id: usize, completed_count: usize, total_count: usize) {
+        self.execute_in_event_loop(move |app_window| {
+            let review_model =
+                model_utils::get_review_model(&app_window, repository_id).unwrap_or_else(|| panic!("[BUG] RepositoryId {} not found", repository_id));
+            let review_model = cast_model!(review_model, IdModel<ui::SlintReview>);
+            let mut review = review_model.get(review_id).unwrap_or_else(|| panic!("[BUG] ReviewId {} not found", review_id));
+            review.review_progress.completed_count = completed_count as i32;
+            review.review_progress.total_count = total_count as i32;
+            review_model.update(review_id, review);
+        });
+    }
+    pub fn set_snapshot_comparison(&self, repository_id: usize, review_id: usize, lines: Vec<SharedString>) {
+        self.execute_in_event_loop(move |app_window| {
+            let review = model_utils::get_slint_review(&app_window, repository_id, review_id)
+                .unwrap_or_else(|| panic!("[BUG] RepositoryId {} ReviewId {} not found", repository_id, review_id));
+            cast_model!(review.snapshot_comparison, VecModel<SharedString>).set_vec(lines);
+        });
+    }
+    pub fn set_diff_range(&self, repository_id: usize, review_id: usize, start_diff: SharedString, end_diff: SharedString, diff_mode: i32) {
+        self.execute_in_event_loop(move |app_window| {
+            let review_model =
+                model_utils::get_review_model(&app_window, repository_id).unwrap_or_else(|| panic!("[BUG] RepositoryId {} not found", repository_id));
+            let review_model = cast_model!(review_model, IdModel<ui::SlintReview>);
+            let mut review = review_model.get(review_id).unwrap_or_else(|| panic!("[BUG] ReviewId {} not found", review_id));
+            review.start_diff = start_diff;
+            review.end_diff = end_diff;
+            review.diff_mode = diff_mode;
+            review_model.update(review_id, review);
+        });
+    }
     pub fn rename_review(&self, repository_id: usize, review_id: usize, new_review_name: SharedString) {
         self.execute_in_event_loop(move |app_window| {
             let review_model =
@@ -363,6 +464,10 @@ impl UiUpdater {
                     }
                     note.context = SharedString::from(new_context);
                 }
+                NoteChangeType::LineRange(ref new_line_range) => {
+                    note.line_range = model_utils::line_range_to_ui(new_line_range.as_ref());
+                    note.is_outdated = false;
+                }
                 NoteChangeType::IsDone(new_is_done) => note.is_fixed = new_is_done,
             }
             note_model.update(note_id, note);
@@ -378,6 +483,20 @@ impl UiUpdater {
         });
     }
 
+    pub fn set_note_line_ranges(&self, repository_id: usize, review_id: usize, line_ranges: Vec<(usize, ui::SlintLineRange, bool)>) {
+        self.execute_in_event_loop(move |app_window| {
+            let note_model = model_utils::get_note_model(&app_window, repository_id, review_id)
+                .unwrap_or_else(|| panic!("[BUG] RepositoryId {} ReviewId {} not found", repository_id, review_id));
+            let note_model = cast_model!(note_model, IdModel<ui::SlintNote>);
+            line_ranges.into_iter().for_each(|(note_id, line_range, is_outdated)| {
+                let mut note = note_model.get(note_id).unwrap_or_else(|| panic!("[BUG] NoteId {} not found", note_id));
+                note.line_range = line_range;
+                note.is_outdated = is_outdated;
+                note_model.update(note_id, note);
+            });
+        });
+    }
+
     fn note_id_to_index(review: &SlintReview, note_id: usize) -> Option<usize> {
         let note_model = cast_model!(review.note_model, IdModel<ui::SlintNote>);
         note_model.id_to_index(note_id)
@@ -517,6 +636,7 @@ impl UiUpdater {
                         change_type: change_type_to_ui(&status.change_type),
                         file_path: SharedString::from(file_path),
                         is_reviewed: store.is_reviewed,
+                        is_changed_since_review: store.is_changed_since_review,
                         referenced_notes: referenced_notes.into(),
                     },
                 );
@@ -549,6 +669,7 @@ impl UiUpdater {
                 .get(file_diff_id)
                 .unwrap_or_else(|| panic!("[BUG] FileDiffId {} not found", file_diff_id));
             file_diff.is_reviewed = is_reviewed;
+            file_diff.is_changed_since_review = false;
             file_diff_model.update(file_diff_id, file_diff);
 
             review.review_progress.completed_count = if is_reviewed {
@@ -568,9 +689,11 @@ impl UiUpdater {
             let mut review = review_model.get(review_id).unwrap_or_else(|| panic!("[BUG] ReviewId {} not found", review_id));
 
             let note_model = cast_model!(review.note_model, IdModel<ui::SlintNote>);
-            note_model.add(note.id as usize, note);
-
             review.note_progress.total_count += 1;
+            if note.is_fixed {
+                review.note_progress.completed_count += 1;
+            }
+            note_model.add(note.id as usize, note);
 
             if let Some(file_diff_id) = opt_file_diff_id {
                 let file_diff_model = cast_model!(review.file_diff_model, IdModel<ui::SlintFileDiff>);
@@ -586,6 +709,33 @@ impl UiUpdater {
         });
     }
 
+    pub fn set_content_diff(&self, repository_id: usize, review_id: usize, file_diff_id: usize, file_path: String, rows: Vec<SideBySideRow>, is_binary: bool) {
+        self.execute_in_event_loop(move |app_window| {
+            let review_model =
+                model_utils::get_review_model(&app_window, repository_id).unwrap_or_else(|| panic!("[BUG] RepositoryId {} not found", repository_id));
+            let review_model = cast_model!(review_model, IdModel<ui::SlintReview>);
+            let mut review = review_model.get(review_id).unwrap_or_else(|| panic!("[BUG] ReviewId {} not found", review_id));
+
+            let hunk_rows = rows
+                .iter()
+                .positions(|row| matches!(row, SideBySideRow::Hunk { .. }))
+                .map(|index| index as i32)
+                .collect::<Vec<_>>();
+            let ui_rows = rows.into_iter().map(ui::SlintSideBySideRow::from).collect::<Vec<_>>();
+
+            review.content_diff = ui::SlintContentDiff {
+                file_diff_id: file_diff_id as i32,
+                file_path: SharedString::from(file_path),
+                rows: Rc::new(VecModel::from(ui_rows)).into(),
+                hunk_rows: Rc::new(VecModel::from(hunk_rows)).into(),
+                is_binary,
+                is_loaded: true,
+            };
+
+            review_model.update(review_id, review);
+        });
+    }
+
     pub fn set_commits(&self, commits: Vec<git_utils::Commit>) {
         self.execute_in_event_loop(move |app_window| {
             //TODO <All> must be translated
@@ -626,6 +776,66 @@ impl From<git_utils::Commit> for ui::SlintCommit {
     }
 }
 
+impl From<Option<DiffCell>> for ui::SlintDiffCell {
+    fn from(value: Option<DiffCell>) -> Self {
+        let Some(cell) = value else {
+            return ui::SlintDiffCell::default();
+        };
+        let kind = match cell.kind {
+            git_utils::DiffLineKind::Context => ui::SlintDiffLineKind::Context,
+            git_utils::DiffLineKind::Added => ui::SlintDiffLineKind::Added,
+            git_utils::DiffLineKind::Removed => ui::SlintDiffLineKind::Removed,
+        };
+        let spans = cell.spans.into_iter().map(ui::SlintTextSpan::from).collect::<Vec<_>>();
+        ui::SlintDiffCell {
+            line: cell.line as i32,
+            text: SharedString::from(cell.text),
+            kind,
+            spans: Rc::new(VecModel::from(spans)).into(),
+        }
+    }
+}
+
+impl From<Span> for ui::SlintTextSpan {
+    fn from(value: Span) -> Self {
+        let kind = match value.kind {
+            TokenKind::Plain => ui::SlintTokenKind::Plain,
+            TokenKind::Keyword => ui::SlintTokenKind::Keyword,
+            TokenKind::Type => ui::SlintTokenKind::Type,
+            TokenKind::String => ui::SlintTokenKind::String,
+            TokenKind::Number => ui::SlintTokenKind::Number,
+            TokenKind::Comment => ui::SlintTokenKind::Comment,
+            TokenKind::Attribute => ui::SlintTokenKind::Attribute,
+            TokenKind::Heading => ui::SlintTokenKind::Heading,
+        };
+        ui::SlintTextSpan {
+            text: SharedString::from(value.text),
+            kind,
+        }
+    }
+}
+
+impl From<SideBySideRow> for ui::SlintSideBySideRow {
+    fn from(value: SideBySideRow) -> Self {
+        match value {
+            SideBySideRow::Hunk { index, header } => ui::SlintSideBySideRow {
+                is_hunk_header: true,
+                hunk_index: index as i32,
+                header: SharedString::from(format!(
+                    "@@ -{},{} +{},{} @@",
+                    header.old_start, header.old_count, header.new_start, header.new_count
+                )),
+                ..Default::default()
+            },
+            SideBySideRow::Lines { old, new } => ui::SlintSideBySideRow {
+                old: old.into(),
+                new: new.into(),
+                ..Default::default()
+            },
+        }
+    }
+}
+
 fn change_type_to_ui(change_type: &git_utils::ChangeType) -> ui::SlintChangeType {
     match change_type {
         git_utils::ChangeType::Added => ui::SlintChangeType::Added,
@@ -1,18 +1,30 @@
-use std::collections::HashSet;
+use std::collections::{HashMap, HashSet};
 use std::fs;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 
 use slint::{ComponentHandle, SharedString};
 use tokio::sync::mpsc::{UnboundedReceiver, UnboundedSender};
 
-use crate::storage::repository_storage::{DiffRangeStore, ReviewName};
-use crate::storage::{RepositoryName, RepositoryStore, ReviewHelperStorage, create_storage};
+use crate::git_utils::{ChangeType, DiffStatus};
+use crate::model::model_utils;
+use crate::storage::repository_storage::{
+    DiffMode, DiffRangeStore, DiffSide, FileDiffStore, LineRange, NoteStore, ReviewEvent, ReviewEventStore, ReviewName, SnapshotStore, StorageError,
+};
+use crate::storage::{
+    LoadedRepositories, RepositoryName, RepositoryStore, ReviewHelperFileStorage, ReviewHelperStorage, StorageBackend, create_storage, prepare_app_data_path,
+};
 use crate::ui::{SlintContextType, SlintNote};
-use crate::{git_utils, ui};
+use crate::{content_diff, git_utils, report, ui};
 
-use crate::repositories::{FileDiffId, NoteId, Repositories, RepositoryId, Review, ReviewId};
+use crate::report::ReportFormat;
+use crate::repositories::{FileDiffId, FileDiffs, NoteId, Notes, Repositories, Repository, RepositoryId, Review, ReviewCommit, ReviewId};
+use crate::review_json::{self, ReviewJson};
+use crate::review_snapshot::{self, BEFORE_DIFF_RANGE_CHANGE, BEFORE_RESTORE};
+use crate::syntax_highlighting::Language;
 use crate::worker::ReviewHelperSettings;
 
+use crate::worker::review_history::{self, NoteAnchor, ReviewEdit, ReviewHistory};
+use crate::worker::storage_watcher;
 use crate::worker::ui_updater::{UiBasicRepository, UiUpdater};
 
 pub type WorkerChannel = UnboundedSender<WorkerMessage>;
@@ -27,6 +39,7 @@ pub enum ReviewHelperError {
 pub enum NoteChangeType {
     Text(String),
     Context(String),
+    LineRange(Option<LineRange>),
     IsDone(bool),
 }
 
@@ -45,6 +58,7 @@ pub enum WorkerMessage {
         editor: String,
         editor_args: Vec<String>,
         color_scheme: String,
+        storage_backend: StorageBackend,
     },
     NewRepository(PathBuf),
     DeleteRepository(RepositoryId),
@@ -72,16 +86,38 @@ pub enum WorkerMessage {
         review_id: ReviewId,
         content_change: ReviewContent,
     },
+    /// Only the files changed since `interdiff_iteration` (numbered from 1) are shown, if set.
     FindFileDifferences {
         repository_id: RepositoryId,
         review_id: ReviewId,
         diff_range: DiffRangeStore,
+        interdiff_iteration: Option<usize>,
+    },
+    /// Shows a single commit of the diff range, `None` shows all commits together.
+    SelectCommit {
+        repository_id: RepositoryId,
+        review_id: ReviewId,
+        commit_index: Option<usize>,
     },
     ShowFileDifferences {
         repository_id: RepositoryId,
         review_id: ReviewId,
         file_diff_id: FileDiffId,
     },
+    LoadContentDiff {
+        repository_id: RepositoryId,
+        review_id: ReviewId,
+        file_diff_id: FileDiffId,
+    },
+    ExportReport {
+        repository_id: RepositoryId,
+        review_id: ReviewId,
+        path: PathBuf,
+    },
+    ImportReview {
+        repository_id: RepositoryId,
+        path: PathBuf,
+    },
     DeleteNote {
         repository_id: RepositoryId,
         review_id: ReviewId,
@@ -92,7 +128,37 @@ pub enum WorkerMessage {
         review_id: ReviewId,
         text: String,
         context: String,
+        line_range: Option<LineRange>,
+    },
+    Undo {
+        repository_id: RepositoryId,
+        review_id: ReviewId,
+    },
+    Redo {
+        repository_id: RepositoryId,
+        review_id: ReviewId,
+    },
+    TakeSnapshot {
+        repository_id: RepositoryId,
+        review_id: ReviewId,
+        name: String,
+    },
+    RestoreSnapshot {
+        repository_id: RepositoryId,
+        review_id: ReviewId,
+        name: String,
     },
+    CompareSnapshot {
+        repository_id: RepositoryId,
+        review_id: ReviewId,
+        name: String,
+    },
+    DeleteSnapshot {
+        repository_id: RepositoryId,
+        review_id: ReviewId,
+        name: String,
+    },
+    StorageChanged,
 }
 
 pub struct Worker {
@@ -105,8 +171,9 @@ impl Worker {
         let (channel, rx) = tokio::sync::mpsc::unbounded_channel();
         let worker_thread = std::thread::spawn({
             let ui_handle = app_window.as_weak();
+            let channel = channel.clone();
             move || {
-                let mut worker_impl = WorkerImpl::new(ui_handle);
+                let mut worker_impl = WorkerImpl::new(ui_handle, channel);
                 worker_impl.worker_loop(rx);
             }
         });
@@ -121,16 +188,6 @@ impl Worker {
     }
 }
 
-fn prepare_app_data_path() -> PathBuf {
-    let mut app_data_path = dirs::data_local_dir().expect("Could not find OS specific dirs!");
-    app_data_path.push(std::env!("CARGO_CRATE_NAME"));
-    if !app_data_path.exists() {
-        let result = fs::create_dir(&app_data_path);
-        assert!(result.is_ok());
-    }
-    app_data_path
-}
-
 fn create_repository_store(path: PathBuf) -> Result<RepositoryStore, ReviewHelperError> {
     let path_str = path.to_str().unwrap_or_default();
 
@@ -153,15 +210,166 @@ fn create_repository_store(path: PathBuf) -> Result<RepositoryStore, ReviewHelpe
     Ok(repository_store)
 }
 
+fn relocate_note_anchors(repository_path: &Path, review: &mut Review, old_diff_range: &DiffRangeStore) -> Vec<NoteId> {
+    let new_diff_range = review.diff_range().clone();
+    let (old_start, old_end) = old_diff_range.revisions();
+    let (new_start, new_end) = new_diff_range.revisions();
+    let sides = [(DiffSide::Old, old_start, new_start), (DiffSide::New, old_end, new_end)];
+
+    let mut relocated_note_ids = Vec::new();
+    for (side, from_commit, to_commit) in sides {
+        if from_commit == to_commit {
+            continue;
+        }
+        for file in review.notes.anchored_files(side) {
+            match git_utils::diff_hunk_headers(repository_path, from_commit, to_commit, &file) {
+                Ok(hunks) => relocated_note_ids.extend(review.notes.relocate_line_ranges(&file, side, |line| git_utils::map_line(&hunks, line))),
+                Err(e) => log::warn!("Could not relocate notes of {}: {}", file, e),
+            }
+        }
+    }
+    relocated_note_ids
+}
+
+/// After a rebase or amend the old diff range isn't part of the history of the new one anymore.
+/// Reviewed files with the same changes (patch ids) in both ranges stay reviewed at their new
+/// blob, the others are reset afterwards because their blob changed.
+fn carry_over_rewritten_history(repository_path: &Path, review: &mut Review, old_diff_range: &DiffRangeStore) -> Vec<ReviewEvent> {
+    let new_diff_range = review.diff_range().clone();
+    // The working tree and index have no history that could be rewritten.
+    if old_diff_range.mode != DiffMode::Commits || new_diff_range.mode != DiffMode::Commits {
+        return Vec::new();
+    }
+    if old_diff_range.end.is_empty() || old_diff_range.end == new_diff_range.end {
+        return Vec::new();
+    }
+    match git_utils::is_ancestor(repository_path, &old_diff_range.end, &new_diff_range.end) {
+        Ok(false) => {}
+        Ok(true) => return Vec::new(),
+        Err(e) => {
+            log::warn!("Could not check whether {} was rewritten: {}", old_diff_range.end, e);
+            return Vec::new();
+        }
+    }
+
+    let mut events = vec![ReviewEvent::HistoryRewritten {
+        old_end: old_diff_range.end.clone(),
+        new_end: new_diff_range.end.clone(),
+    }];
+    let reviewed_files = review.file_diffs.reviewed_files_with_blob();
+    let reviewed_files = reviewed_files.iter().map(String::as_str).collect::<Vec<_>>();
+    let mut blob_hashes = match git_utils::blob_hashes(repository_path, &new_diff_range.end, &reviewed_files) {
+        Ok(blob_hashes) => blob_hashes,
+        Err(e) => {
+            log::warn!("Could not carry over the reviewed files: {}", e);
+            return events;
+        }
+    };
+    for file in reviewed_files {
+        let patch_ids = git_utils::file_patch_id(repository_path, &old_diff_range.start, &old_diff_range.end, file).and_then(|old_patch_id| {
+            let new_patch_id = git_utils::file_patch_id(repository_path, &new_diff_range.start, &new_diff_range.end, file)?;
+            Ok((old_patch_id, new_patch_id))
+        });
+        match (patch_ids, blob_hashes.remove(file)) {
+            (Ok((old_patch_id, new_patch_id)), Some(blob)) if old_patch_id == new_patch_id => {
+                review.file_diffs.move_reviewed_blob(file, blob);
+                events.push(ReviewEvent::FileReviewCarriedOver { file: file.to_string() });
+            }
+            (Err(e), _) => log::warn!("Could not compare the changes of {}: {}", file, e),
+            _ => {}
+        }
+    }
+    events
+}
+
+/// The file diffs of the review with their status in the diff range, unknown if git can't tell.
+/// With an interdiff only the files changed since its iteration are shown, in the commit-by-commit
+/// mode only the files of the current commit with their reviewed flag in it.
+fn file_diffs_with_status(repository_path: &Path, review: &Review) -> Vec<(i32, FileDiffStore, DiffStatus)> {
+    let (start, end) = review.compared_commits();
+    let mut file_diff_map = if start.is_empty() {
+        None
+    } else {
+        git_utils::diff_git_repo(repository_path, &start, &end)
+            .inspect_err(|e| log::warn!("Could not query the status of {}: {}", review.name().as_str(), e))
+            .ok()
+    };
+    let current_commit = review.current_commit();
+    let is_restricted = (review.diff_range().interdiff_base().is_some() || current_commit.is_some()) && file_diff_map.is_some();
+    review
+        .file_diffs
+        .iter()
+        .filter_map(|(id, store)| {
+            let diff_status = file_diff_map
+                .as_mut()
+                .and_then(|file_diff_map| file_diff_map.remove(store.file_path.to_string_lossy().as_ref()));
+            if is_restricted && diff_status.is_none() {
+                return None;
+            }
+            let diff_status = diff_status.unwrap_or(DiffStatus {
+                added_lines: 0,
+                removed_lines: 0,
+                change_type: ChangeType::Unknown,
+            });
+            let mut store = store.clone();
+            if let Some(commit) = current_commit {
+                store.is_reviewed = store.reviewed_commits.contains(&commit.hash);
+                store.is_changed_since_review = false;
+            }
+            Some((id.as_i32(), store, diff_status))
+        })
+        .collect()
+}
+
+/// Refreshing the file differences of an unchanged diff range or switching to an interdiff isn't
+/// an event.
+fn diff_range_events(old_diff_range: &DiffRangeStore, new_diff_range: &DiffRangeStore) -> Vec<ReviewEvent> {
+    let mut events = Vec::new();
+    if old_diff_range.start != new_diff_range.start || old_diff_range.end != new_diff_range.end {
+        events.push(ReviewEvent::DiffRangeChanged {
+            old_start: old_diff_range.start.clone(),
+            old_end: old_diff_range.end.clone(),
+            new_start: new_diff_range.start.clone(),
+            new_end: new_diff_range.end.clone(),
+        });
+    }
+    if old_diff_range.mode != new_diff_range.mode {
+        events.push(ReviewEvent::DiffModeChanged {
+            old_mode: old_diff_range.mode.to_string(),
+            new_mode: new_diff_range.mode.to_string(),
+        });
+    }
+    events
+}
+
+/// Moves the notes to the given anchors and returns their line ranges for the UI.
+fn move_note_anchors(notes: &mut Notes, note_anchors: &[NoteAnchor]) -> Vec<(usize, ui::SlintLineRange, bool)> {
+    note_anchors
+        .iter()
+        .filter_map(|anchor| {
+            let note = notes.get_mut(&anchor.note_id)?;
+            note.line_range = anchor.line_range;
+            note.is_outdated = anchor.is_outdated;
+            Some((
+                anchor.note_id.as_usize(),
+                model_utils::line_range_to_ui(anchor.line_range.as_ref()),
+                anchor.is_outdated,
+            ))
+        })
+        .collect()
+}
+
 struct WorkerImpl {
     ui_updater: UiUpdater,
     settings: ReviewHelperSettings,
     storage: Box<dyn ReviewHelperStorage>,
     repositories: Repositories,
+    histories: HashMap<(RepositoryId, ReviewId), ReviewHistory>,
+    _storage_watcher: Option<notify::RecommendedWatcher>,
 }
 
 impl WorkerImpl {
-    fn new(ui_weak: slint::Weak<ui::AppWindow>) -> Self {
+    fn new(ui_weak: slint::Weak<ui::AppWindow>, channel: WorkerChannel) -> Self {
         let app_data_path = prepare_app_data_path();
         let review_helper_settings = match ReviewHelperSettings::new(&app_data_path) {
             Ok(config) => config,
@@ -173,9 +381,26 @@ impl WorkerImpl {
 
         let ui_updater = UiUpdater::new(ui_weak);
 
-        let storage = create_storage(app_data_path);
+        let storage_watcher = storage_watcher::watch_storage(&app_data_path, channel)
+            .inspect_err(|e| log::warn!("Could not watch {}: {}", app_data_path.display(), e))
+            .ok();
 
-        let repositories = Repositories::new(storage.load_repositories().expect("Could not load repositories!"));
+        let storage = match create_storage(app_data_path.clone(), review_helper_settings.storage_backend) {
+            Ok(storage) => storage,
+            Err(e) => {
+                ui_updater.report_error(ui::SlintResult::OpenStorageFailed, &e.to_string());
+                Box::new(ReviewHelperFileStorage::new(app_data_path))
+            }
+        };
+
+        let loaded_repositories = storage.load_repositories().unwrap_or_else(|e| {
+            ui_updater.report_error(ui::SlintResult::LoadRepositoryFailed, &e.to_string());
+            LoadedRepositories::default()
+        });
+        for error in &loaded_repositories.errors {
+            ui_updater.report_error(ui::SlintResult::LoadRepositoryFailed, &error.to_string());
+        }
+        let repositories = Repositories::new(loaded_repositories.repositories);
 
         {
             let ui_repositories: Vec<_> = repositories
@@ -193,12 +418,23 @@ impl WorkerImpl {
             settings: review_helper_settings,
             storage,
             repositories,
+            histories: HashMap::new(),
+            _storage_watcher: storage_watcher,
         };
         worker_impl.query_diff_tools();
 
         worker_impl
     }
 
+    /// Reviews changed by another instance are reloaded instead of being overwritten.
+    fn report_review_store_error(&mut self, repository_id: RepositoryId, review_id: ReviewId, error: StorageError) {
+        if let StorageError::Conflict(_) = error {
+            self.ui_updater.report_error(ui::SlintResult::ReviewChangedOnDisk, &error.to_string());
+            self.load_review(repository_id, review_id);
+        } else {
+            self.ui_updater.report_error(ui::SlintResult::StoreFailed, &error.to_string());
+        }
+    }
     fn query_diff_tools(&self) {
         let result = git_utils::query_diff_tools();
         match result {
@@ -215,6 +451,40 @@ impl WorkerImpl {
         };
         self.ui_updater.report_error(ui_error, ui_error_text);
     }
+    fn export_report(&self, repository_id: RepositoryId, review_id: ReviewId, path: PathBuf) {
+        let repository = self
+            .repositories
+            .get(&repository_id)
+            .unwrap_or_else(|| panic!("[BUG] Could not find {}", repository_id));
+
+        let review = repository
+            .reviews
+            .get(&review_id)
+            .unwrap_or_else(|| panic!("[BUG] Could not find {} in {}", review_id, repository_id));
+
+        let format = ReportFormat::from_path(&path);
+        let result = report::render_report(repository, review, format).and_then(|content| Ok(fs::write(&path, content)?));
+        if let Err(e) = result {
+            self.ui_updater.report_error(ui::SlintResult::ExportReportFailed, &e.to_string());
+        }
+    }
+    fn import_review(&mut self, repository_id: RepositoryId, path: PathBuf) {
+        let repository = self
+            .repositories
+            .get_mut(&repository_id)
+            .unwrap_or_else(|| panic!("[BUG] Could not find {}", repository_id));
+
+        let result = fs::read_to_string(&path)
+            .map_err(anyhow::Error::from)
+            .and_then(|content| ReviewJson::from_json(&content))
+            .and_then(|review_json| review_json::import_review(self.storage.as_ref(), repository, &review_json, None));
+        match result {
+            Ok((review_id, review_name)) => self
+                .ui_updater
+                .initialize_reviews(repository_id.as_usize(), vec![(review_id.as_i32(), SharedString::from(review_name.as_str()))]),
+            Err(e) => self.ui_updater.report_error(ui::SlintResult::ImportReviewFailed, &e.to_string()),
+        }
+    }
     fn show_file_differences(&self, repository_id: RepositoryId, review_id: ReviewId, file_diff_id: FileDiffId) {
         let repository = self
             .repositories
@@ -231,16 +501,50 @@ impl WorkerImpl {
             .get(&file_diff_id)
             .unwrap_or_else(|| panic!("[BUG] Could not find {} of {} in {}", file_diff_id, review_id, repository_id));
 
-        let start_commit = review.diff_range().start.as_str();
-        let end_commit = review.diff_range().end.as_str();
+        let (start_commit, end_commit) = review.compared_commits();
         let file = &file_diff.file_path.to_string_lossy();
         let file = file.as_ref();
         let diff_tool = self.settings.diff_tool.as_str();
 
-        if let Err(e) = git_utils::diff_file(repository.path(), start_commit, end_commit, file, diff_tool) {
+        if let Err(e) = git_utils::diff_file(repository.path(), &start_commit, &end_commit, file, diff_tool) {
             self.ui_updater.report_error(ui::SlintResult::ShowFileDifferencesFailed, &e.to_string());
         }
     }
+    fn load_content_diff(&self, repository_id: RepositoryId, review_id: ReviewId, file_diff_id: FileDiffId) {
+        let repository = self
+            .repositories
+            .get(&repository_id)
+            .unwrap_or_else(|| panic!("[BUG] Could not find {}", repository_id));
+
+        let review = repository
+            .reviews
+            .get(&review_id)
+            .unwrap_or_else(|| panic!("[BUG] Could not find {} in {}", review_id, repository_id));
+
+        let file_diff = review
+            .file_diffs
+            .get(&file_diff_id)
+            .unwrap_or_else(|| panic!("[BUG] Could not find {} of {} in {}", file_diff_id, review_id, repository_id));
+
+        let (start_commit, end_commit) = review.compared_commits();
+        let file = file_diff.file_path.to_string_lossy().to_string();
+
+        match git_utils::diff_file_hunks(repository.path(), &start_commit, &end_commit, &file, content_diff::CONTEXT_LINES) {
+            Ok(file_hunks) => {
+                let mut rows = content_diff::side_by_side_rows(file_hunks.hunks);
+                content_diff::highlight_rows(&mut rows, Language::from_file_name(&file));
+                self.ui_updater.set_content_diff(
+                    repository_id.as_usize(),
+                    review_id.as_usize(),
+                    file_diff_id.as_usize(),
+                    file,
+                    rows,
+                    file_hunks.is_binary,
+                );
+            }
+            Err(e) => self.ui_updater.report_error(ui::SlintResult::LoadContentDiffFailed, &e.to_string()),
+        }
+    }
     fn load_commits(&self, repository_id: &RepositoryId) {
         let repository = self
             .repositories
@@ -266,7 +570,8 @@ impl WorkerImpl {
                     editor,
                     editor_args,
                     color_scheme,
-                } => self.save_settings(diff_tool, editor, editor_args, color_scheme),
+                    storage_backend,
+                } => self.save_settings(diff_tool, editor, editor_args, color_scheme, storage_backend),
                 WorkerMessage::NewRepository(path) => self.new_repository(path),
                 WorkerMessage::ChangeRepository { id, base_branch } => self.change_repository(id, base_branch),
                 WorkerMessage::LoadRepository { id } => {
@@ -281,40 +586,106 @@ impl WorkerImpl {
                     review_id,
                     content_change,
                 } => match content_change {
-                    ReviewContent::FileDiff { file_diff_id, is_reviewed } => self.change_review_file_diff(repository_id, review_id, file_diff_id, is_reviewed),
-                    ReviewContent::Note { note_id, change_type } => self.change_review_notes(repository_id, review_id, note_id, change_type),
+                    ReviewContent::FileDiff { file_diff_id, is_reviewed } => {
+                        let (_, review) = self.loaded_review(&repository_id, &review_id);
+                        let commit = review.current_commit().map(|commit| commit.hash.clone());
+                        self.edit_review(
+                            repository_id,
+                            review_id,
+                            ReviewEdit::FileDiff {
+                                file_diff_id,
+                                is_reviewed,
+                                commit,
+                            },
+                        )
+                    }
+                    ReviewContent::Note { note_id, change_type } => self.edit_review(repository_id, review_id, ReviewEdit::Note { note_id, change_type }),
                     ReviewContent::Name(new_review_name) => self.rename_review(repository_id, review_id, new_review_name),
                 },
                 WorkerMessage::FindFileDifferences {
                     repository_id,
                     review_id,
                     diff_range,
-                } => self.find_file_difference(repository_id, review_id, diff_range),
+                    interdiff_iteration,
+                } => {
+                    if let Some(inverse) = self.find_file_difference(repository_id.clone(), review_id.clone(), diff_range, interdiff_iteration) {
+                        self.history(&repository_id, &review_id).record(inverse);
+                    }
+                }
+                WorkerMessage::SelectCommit {
+                    repository_id,
+                    review_id,
+                    commit_index,
+                } => self.select_commit(repository_id, review_id, commit_index),
                 WorkerMessage::ShowFileDifferences {
                     repository_id,
                     review_id,
                     file_diff_id,
                 } => self.show_file_differences(repository_id, review_id, file_diff_id),
+                WorkerMessage::LoadContentDiff {
+                    repository_id,
+                    review_id,
+                    file_diff_id,
+                } => self.load_content_diff(repository_id, review_id, file_diff_id),
+                WorkerMessage::ExportReport {
+                    repository_id,
+                    review_id,
+                    path,
+                } => self.export_report(repository_id, review_id, path),
+                WorkerMessage::ImportReview { repository_id, path } => self.import_review(repository_id, path),
                 WorkerMessage::DeleteNote {
                     repository_id,
                     review_id,
                     note_id,
-                } => self.delete_note(repository_id, review_id, note_id),
+                } => self.edit_review(repository_id, review_id, ReviewEdit::DeleteNote(note_id)),
                 WorkerMessage::AddNote {
                     repository_id,
                     review_id,
                     text,
                     context,
-                } => self.add_note(repository_id, review_id, text, context),
+                    line_range,
+                } => {
+                    let note = NoteStore {
+                        text,
+                        context,
+                        line_range,
+                        ..Default::default()
+                    };
+                    self.edit_review(repository_id, review_id, ReviewEdit::AddNote { note_id: None, note })
+                }
+                WorkerMessage::Undo { repository_id, review_id } => self.undo(repository_id, review_id),
+                WorkerMessage::Redo { repository_id, review_id } => self.redo(repository_id, review_id),
+                WorkerMessage::TakeSnapshot {
+                    repository_id,
+                    review_id,
+                    name,
+                } => self.take_snapshot(repository_id, review_id, name),
+                WorkerMessage::RestoreSnapshot {
+                    repository_id,
+                    review_id,
+                    name,
+                } => self.restore_snapshot(repository_id, review_id, name),
+                WorkerMessage::CompareSnapshot {
+                    repository_id,
+                    review_id,
+                    name,
+                } => self.compare_snapshot(repository_id, review_id, &name),
+                WorkerMessage::DeleteSnapshot {
+                    repository_id,
+                    review_id,
+                    name,
+                } => self.delete_snapshot(repository_id, review_id, &name),
                 WorkerMessage::DeleteRepository(repository_id) => self.delete_repository(repository_id),
+                WorkerMessage::StorageChanged => self.reload_changed_reviews(),
             }
         }
     }
-    fn save_settings(&mut self, diff_tool: String, editor: String, editor_args: Vec<String>, color_scheme: String) {
+    fn save_settings(&mut self, diff_tool: String, editor: String, editor_args: Vec<String>, color_scheme: String, storage_backend: StorageBackend) {
         self.settings.diff_tool = diff_tool;
         self.settings.editor = editor;
         self.settings.editor_args = editor_args;
         self.settings.color_scheme = color_scheme;
+        self.settings.storage_backend = storage_backend;
         if let Err(e) = self.settings.save() {
             self.ui_updater.report_error(ui::SlintResult::StoreFailed, &e.to_string());
         }
@@ -349,6 +720,7 @@ impl WorkerImpl {
             self.ui_updater.report_error(ui::SlintResult::DeleteRepositoryFailed, &e.to_string());
             return;
         }
+        self.histories.retain(|(id, _), _| *id != repository_id);
         self.ui_updater.delete_repository(repository_id.as_usize());
     }
     fn change_repository(&mut self, repository_id: RepositoryId, base_branch: String) {
@@ -417,8 +789,28 @@ impl WorkerImpl {
             return;
         };
 
+        // A broken activity log doesn't prevent working on the review.
+        let ui_events = match self.storage.load_review_events(&repository.name, review_name) {
+            Ok(events) => events.iter().map(model_utils::review_event_to_ui).collect(),
+            Err(e) => {
+                self.ui_updater.report_error(ui::SlintResult::LoadReviewFailed, &e.to_string());
+                Vec::new()
+            }
+        };
+
+        let ui_snapshots = match self.storage.load_review_snapshots(&repository.name, review_name) {
+            Ok(snapshots) => snapshots.iter().map(model_utils::snapshot_to_ui).collect(),
+            Err(e) => {
+                self.ui_updater.report_error(ui::SlintResult::SnapshotFailed, &e.to_string());
+                Vec::new()
+            }
+        };
+
         let start_diff = SharedString::from(&store.diff_range.start);
         let end_diff = SharedString::from(&store.diff_range.end);
+        let diff_mode = model_utils::diff_mode_to_ui(store.diff_range.mode);
+        let ui_iterations = model_utils::iterations_to_ui(&store.diff_range);
+        let interdiff_iteration = store.diff_range.interdiff_iteration.unwrap_or_default() as i32;
 
         let review = Review::new(store, review_name.clone());
 
@@ -436,6 +828,8 @@ impl WorkerImpl {
                 context_type,
                 is_fixed: id_store_tuple.1.is_done,
                 text: SharedString::from(&id_store_tuple.1.text),
+                line_range: model_utils::line_range_to_ui(id_store_tuple.1.line_range.as_ref()),
+                is_outdated: id_store_tuple.1.is_outdated,
             });
         });
         let ui_file_diffs: Vec<_> = review
@@ -444,10 +838,61 @@ impl WorkerImpl {
             .map(|id_store_tuple| (id_store_tuple.0.as_i32(), id_store_tuple.1.clone()))
             .collect();
 
-        repository.reviews.insert_review(review_id.clone(), review);
+        // Ids of notes and file diffs change, so the edits of the previous review can't be undone.
+        self.histories.remove(&(repository_id.clone(), review_id.clone()));
+        let is_reload = match repository.reviews.get_mut(&review_id) {
+            Some(loaded_review) => {
+                *loaded_review = review;
+                true
+            }
+            None => {
+                repository.reviews.insert_review(review_id.clone(), review);
+                false
+            }
+        };
 
+        self.ui_updater.set_review(
+            repository_id.as_usize(),
+            review_id.as_usize(),
+            start_diff.clone(),
+            end_diff.clone(),
+            ui_notes,
+            ui_file_diffs,
+        );
+        self.ui_updater
+            .set_diff_range(repository_id.as_usize(), review_id.as_usize(), start_diff, end_diff, diff_mode);
+        self.ui_updater.set_review_events(repository_id.as_usize(), review_id.as_usize(), ui_events);
+        self.ui_updater.set_snapshots(repository_id.as_usize(), review_id.as_usize(), ui_snapshots);
         self.ui_updater
-            .set_review(repository_id.as_usize(), review_id.as_usize(), start_diff, end_diff, ui_notes, ui_file_diffs);
+            .set_iterations(repository_id.as_usize(), review_id.as_usize(), ui_iterations, interdiff_iteration);
+
+        // The difference statistics of reloaded reviews were already shown, so they are kept up to
+        // date, an interdiff shows only some of the files.
+        if is_reload || interdiff_iteration != 0 {
+            let review = repository
+                .reviews
+                .get(&review_id)
+                .unwrap_or_else(|| panic!("[BUG] Could not find {}", review_id));
+            let ui_file_diffs = file_diffs_with_status(repository.path(), review);
+            self.ui_updater.set_file_diffs(repository_id.as_usize(), review_id.as_usize(), ui_file_diffs);
+        }
+        self.update_review_commits(&repository_id, &review_id);
+    }
+    fn reload_changed_reviews(&mut self) {
+        let mut changed_reviews = Vec::new();
+        for (repository_id, repository) in self.repositories.iter() {
+            for (review_id, review) in repository.reviews.iter() {
+                match self.storage.is_review_changed(&repository.name, review.name()) {
+                    Ok(true) => changed_reviews.push((repository_id.clone(), review_id.clone())),
+                    Ok(false) => {}
+                    Err(e) => log::warn!("Could not check {} for changes: {}", review.name().as_str(), e),
+                }
+            }
+        }
+        for (repository_id, review_id) in changed_reviews {
+            log::info!("Reload {} ({}), it was changed outside of review_helper", review_id, repository_id);
+            self.load_review(repository_id, review_id);
+        }
     }
     fn new_review(&mut self, repository_id: RepositoryId, name: String) {
         let repository = self
@@ -488,6 +933,7 @@ impl WorkerImpl {
             return;
         }
 
+        self.histories.remove(&(repository_id.clone(), review_id.clone()));
         self.ui_updater.delete_review(repository_id.as_usize(), review_id.as_usize());
     }
     fn rename_review(&mut self, repository_id: RepositoryId, review_id: ReviewId, new_review_name: ReviewName) {
@@ -505,7 +951,175 @@ impl WorkerImpl {
         self.ui_updater
             .rename_review(repository_id.as_usize(), review_id.as_usize(), SharedString::from(new_review_name.as_str()));
     }
-    fn change_review_notes(&mut self, repository_id: RepositoryId, review_id: ReviewId, note_id: NoteId, change_type: NoteChangeType) {
+    fn history(&mut self, repository_id: &RepositoryId, review_id: &ReviewId) -> &mut ReviewHistory {
+        self.histories.entry((repository_id.clone(), review_id.clone())).or_default()
+    }
+    /// Appends the events to the activity log of the review, a failure doesn't undo the edit.
+    fn log_review_events(&self, repository_id: &RepositoryId, review_id: &ReviewId, events: Vec<ReviewEvent>) {
+        if events.is_empty() {
+            return;
+        }
+        let repository = self
+            .repositories
+            .get(repository_id)
+            .unwrap_or_else(|| panic!("[BUG] Could not find {}", repository_id));
+        let review = repository
+            .reviews
+            .get(review_id)
+            .unwrap_or_else(|| panic!("[BUG] Could not find {} ({})", review_id, repository_id));
+
+        let author = git_utils::user_name(repository.path());
+        let events = events.into_iter().map(|event| ReviewEventStore::new(author.clone(), event)).collect::<Vec<_>>();
+        if let Err(e) = self.storage.append_review_events(&repository.name, review.name(), &events) {
+            self.ui_updater.report_error(ui::SlintResult::StoreFailed, &e.to_string());
+            return;
+        }
+        self.ui_updater.add_review_events(
+            repository_id.as_usize(),
+            review_id.as_usize(),
+            events.iter().map(model_utils::review_event_to_ui).collect(),
+        );
+    }
+    fn loaded_review(&self, repository_id: &RepositoryId, review_id: &ReviewId) -> (&Repository, &Review) {
+        let repository = self
+            .repositories
+            .get(repository_id)
+            .unwrap_or_else(|| panic!("[BUG] Could not find {}", repository_id));
+        let review = repository
+            .reviews
+            .get(review_id)
+            .unwrap_or_else(|| panic!("[BUG] Could not find {} ({})", review_id, repository_id));
+        (repository, review)
+    }
+    fn load_snapshots(&self, repository_id: &RepositoryId, review_id: &ReviewId) -> Option<Vec<SnapshotStore>> {
+        let (repository, review) = self.loaded_review(repository_id, review_id);
+        self.storage
+            .load_review_snapshots(&repository.name, review.name())
+            .inspect_err(|e| self.ui_updater.report_error(ui::SlintResult::SnapshotFailed, &e.to_string()))
+            .ok()
+    }
+    fn find_snapshot(&self, repository_id: &RepositoryId, review_id: &ReviewId, name: &str) -> Option<SnapshotStore> {
+        let snapshot = self
+            .load_snapshots(repository_id, review_id)?
+            .into_iter()
+            .find(|snapshot| snapshot.name == name);
+        if snapshot.is_none() {
+            self.ui_updater
+                .report_error(ui::SlintResult::SnapshotFailed, &StorageError::SnapshotNotFound(name.to_string()).to_string());
+        }
+        snapshot
+    }
+    fn update_snapshots(&self, repository_id: &RepositoryId, review_id: &ReviewId) {
+        if let Some(snapshots) = self.load_snapshots(repository_id, review_id) {
+            self.ui_updater.set_snapshots(
+                repository_id.as_usize(),
+                review_id.as_usize(),
+                snapshots.iter().map(model_utils::snapshot_to_ui).collect(),
+            );
+        }
+    }
+    /// Stores the current state of the review as snapshot `name`, returns whether it was stored.
+    fn save_snapshot(&self, repository_id: &RepositoryId, review_id: &ReviewId, name: String) -> bool {
+        let (repository, review) = self.loaded_review(repository_id, review_id);
+        let snapshot = SnapshotStore::new(name.clone(), review.store());
+        if let Err(e) = self.storage.save_review_snapshot(&repository.name, review.name(), &snapshot) {
+            self.ui_updater.report_error(ui::SlintResult::SnapshotFailed, &e.to_string());
+            return false;
+        }
+        self.log_review_events(repository_id, review_id, vec![ReviewEvent::SnapshotTaken { name }]);
+        self.update_snapshots(repository_id, review_id);
+        true
+    }
+    fn take_snapshot(&self, repository_id: RepositoryId, review_id: ReviewId, name: String) {
+        let Some(snapshots) = self.load_snapshots(&repository_id, &review_id) else {
+            return;
+        };
+        if snapshots.iter().any(|snapshot| snapshot.name == name) {
+            self.ui_updater
+                .report_error(ui::SlintResult::SnapshotFailed, &format!("Snapshot '{}' already exists!", name));
+            return;
+        }
+        self.save_snapshot(&repository_id, &review_id, name);
+    }
+    /// The current state is kept as snapshot first, so that restoring can be reverted.
+    fn restore_snapshot(&mut self, repository_id: RepositoryId, review_id: ReviewId, name: String) {
+        let Some(snapshot) = self.find_snapshot(&repository_id, &review_id, &name) else {
+            return;
+        };
+        if !self.save_snapshot(&repository_id, &review_id, review_snapshot::auto_snapshot_name(BEFORE_RESTORE)) {
+            return;
+        }
+        let (repository, review) = self.loaded_review(&repository_id, &review_id);
+        if let Err(e) = review_snapshot::restore_snapshot(self.storage.as_ref(), &repository.name, review.name(), &snapshot) {
+            self.report_review_store_error(repository_id, review_id, e);
+            return;
+        }
+        self.log_review_events(&repository_id, &review_id, vec![ReviewEvent::SnapshotRestored { name }]);
+        self.load_review(repository_id, review_id);
+    }
+    fn compare_snapshot(&self, repository_id: RepositoryId, review_id: ReviewId, name: &str) {
+        let Some(snapshot) = self.find_snapshot(&repository_id, &review_id, name) else {
+            return;
+        };
+        let (_, review) = self.loaded_review(&repository_id, &review_id);
+        let differences = review_snapshot::compare_snapshot(&snapshot.review, &review.store());
+        let lines = if differences.is_empty() {
+            vec![SharedString::from(format!("Nothing changed since snapshot {}", name))]
+        } else {
+            differences.iter().map(|difference| SharedString::from(difference.to_string())).collect()
+        };
+        self.ui_updater.set_snapshot_comparison(repository_id.as_usize(), review_id.as_usize(), lines);
+    }
+    fn delete_snapshot(&self, repository_id: RepositoryId, review_id: ReviewId, name: &str) {
+        let (repository, review) = self.loaded_review(&repository_id, &review_id);
+        if let Err(e) = self.storage.delete_review_snapshot(&repository.name, review.name(), name) {
+            self.ui_updater.report_error(ui::SlintResult::SnapshotFailed, &e.to_string());
+            return;
+        }
+        self.update_snapshots(&repository_id, &review_id);
+    }
+    /// Applies a new edit and records its inverse for undo.
+    fn edit_review(&mut self, repository_id: RepositoryId, review_id: ReviewId, edit: ReviewEdit) {
+        if let Some(inverse) = self.apply_edit(repository_id.clone(), review_id.clone(), edit) {
+            self.history(&repository_id, &review_id).record(inverse);
+        }
+    }
+    fn undo(&mut self, repository_id: RepositoryId, review_id: ReviewId) {
+        let Some(edit) = self.history(&repository_id, &review_id).pop_undo() else {
+            return;
+        };
+        if let Some(inverse) = self.apply_edit(repository_id.clone(), review_id.clone(), edit) {
+            self.history(&repository_id, &review_id).push_redo(inverse);
+        }
+    }
+    fn redo(&mut self, repository_id: RepositoryId, review_id: ReviewId) {
+        let Some(edit) = self.history(&repository_id, &review_id).pop_redo() else {
+            return;
+        };
+        if let Some(inverse) = self.apply_edit(repository_id.clone(), review_id.clone(), edit) {
+            self.history(&repository_id, &review_id).push_undo(inverse);
+        }
+    }
+    /// Applies the edit and returns its inverse, `None` if it couldn't be stored.
+    fn apply_edit(&mut self, repository_id: RepositoryId, review_id: ReviewId, edit: ReviewEdit) -> Option<ReviewEdit> {
+        match edit {
+            ReviewEdit::Note { note_id, change_type } => self.change_review_notes(repository_id, review_id, note_id, change_type),
+            ReviewEdit::FileDiff {
+                file_diff_id,
+                is_reviewed,
+                commit,
+            } => self.change_review_file_diff(repository_id, review_id, file_diff_id, is_reviewed, commit),
+            ReviewEdit::AddNote { note_id, note } => self.add_note(repository_id, review_id, note_id, note),
+            ReviewEdit::DeleteNote(note_id) => self.delete_note(repository_id, review_id, note_id),
+            ReviewEdit::NoteAnchors(note_anchors) => self.set_note_anchors(repository_id, review_id, note_anchors),
+            ReviewEdit::DiffRange {
+                diff_range,
+                file_diffs,
+                note_anchors,
+            } => self.restore_diff_range(repository_id, review_id, diff_range, file_diffs, note_anchors),
+        }
+    }
+    fn change_review_notes(&mut self, repository_id: RepositoryId, review_id: ReviewId, note_id: NoteId, change_type: NoteChangeType) -> Option<ReviewEdit> {
         let repository = self
             .repositories
             .get_mut(&repository_id)
@@ -531,6 +1145,21 @@ impl WorkerImpl {
             .get_mut(&note_id)
             .unwrap_or_else(|| panic!("[BUG] Could not find {} ({}, {})", note_id, review_id, repository_id));
 
+        // Changing the line range also clears the outdated state, so its inverse is the whole anchor.
+        let inverse_change_type = match change_type {
+            NoteChangeType::Text(_) => Some(NoteChangeType::Text(note.text.clone())),
+            NoteChangeType::Context(_) => Some(NoteChangeType::Context(note.context.clone())),
+            NoteChangeType::LineRange(_) => None,
+            NoteChangeType::IsDone(_) => Some(NoteChangeType::IsDone(note.is_done)),
+        };
+        let inverse = match inverse_change_type {
+            Some(inverse_change_type) => ReviewEdit::Note {
+                note_id: note_id.clone(),
+                change_type: inverse_change_type,
+            },
+            None => ReviewEdit::NoteAnchors(vec![NoteAnchor::of_note(&note_id, note)]),
+        };
+
         let mut opt_context_type = None;
         match change_type.clone() {
             NoteChangeType::Text(new_text) => note.text = new_text,
@@ -546,11 +1175,26 @@ impl WorkerImpl {
 
                 note.context = new_context;
             }
+            NoteChangeType::LineRange(new_line_range) => {
+                note.line_range = new_line_range;
+                note.is_outdated = false;
+            }
             NoteChangeType::IsDone(new_is_done) => note.is_done = new_is_done,
         }
+        let event = match change_type {
+            NoteChangeType::IsDone(is_done) => ReviewEvent::NoteResolved {
+                context: note.context.clone(),
+                text: note.text.clone(),
+                is_done,
+            },
+            _ => ReviewEvent::NoteEdited {
+                context: note.context.clone(),
+                text: note.text.clone(),
+            },
+        };
         if let Err(e) = self.storage.save_review_notes(&repository.name, review.name(), &review.notes.stores()) {
-            self.ui_updater.report_error(ui::SlintResult::StoreFailed, &e.to_string());
-            return;
+            self.report_review_store_error(repository_id, review_id, e);
+            return None;
         }
         self.ui_updater.update_note(
             repository_id.as_usize(),
@@ -559,59 +1203,260 @@ impl WorkerImpl {
             change_type,
             opt_context_type,
         );
+        self.log_review_events(&repository_id, &review_id, vec![event]);
+        Some(inverse)
+    }
+    fn set_note_anchors(&mut self, repository_id: RepositoryId, review_id: ReviewId, note_anchors: Vec<NoteAnchor>) -> Option<ReviewEdit> {
+        let repository = self
+            .repositories
+            .get_mut(&repository_id)
+            .unwrap_or_else(|| panic!("[BUG] Could not find {}", repository_id));
+
+        let review = repository
+            .reviews
+            .get_mut(&review_id)
+            .unwrap_or_else(|| panic!("[BUG] Could not find {} ({})", review_id, repository_id));
+
+        let previous_anchors = note_anchors
+            .iter()
+            .filter_map(|anchor| Some(NoteAnchor::of_note(&anchor.note_id, review.notes.get(&anchor.note_id)?)))
+            .collect();
+        let ui_line_ranges = move_note_anchors(&mut review.notes, &note_anchors);
+        let events = note_anchors
+            .iter()
+            .filter_map(|anchor| review.notes.get(&anchor.note_id))
+            .map(|note| ReviewEvent::NoteEdited {
+                context: note.context.clone(),
+                text: note.text.clone(),
+            })
+            .collect();
+
+        if let Err(e) = self.storage.save_review_notes(&repository.name, review.name(), &review.notes.stores()) {
+            self.report_review_store_error(repository_id, review_id, e);
+            return None;
+        }
+        self.ui_updater
+            .set_note_line_ranges(repository_id.as_usize(), review_id.as_usize(), ui_line_ranges);
+        self.log_review_events(&repository_id, &review_id, events);
+        Some(ReviewEdit::NoteAnchors(previous_anchors))
     }
-    fn change_review_file_diff(&mut self, repository_id: RepositoryId, review_id: ReviewId, file_diff_id: FileDiffId, is_reviewed: bool) {
+    fn change_review_file_diff(
+        &mut self,
+        repository_id: RepositoryId,
+        review_id: ReviewId,
+        file_diff_id: FileDiffId,
+        is_reviewed: bool,
+        commit: Option<String>,
+    ) -> Option<ReviewEdit> {
         let repository = self
             .repositories
             .get_mut(&repository_id)
             .unwrap_or_else(|| panic!("[BUG] Could not find {}", repository_id));
 
+        let repository_path = repository.path().clone();
         let review = repository
             .reviews
             .get_mut(&review_id)
             .unwrap_or_else(|| panic!("[BUG] Could not find {} ({})", review_id, repository_id));
 
-        review.file_diffs.set_is_reviewed(&file_diff_id, is_reviewed);
+        let file_diff = review
+            .file_diffs
+            .get(&file_diff_id)
+            .unwrap_or_else(|| panic!("[BUG] Could not find {} of {} in {}", file_diff_id, review_id, repository_id));
+        let file = file_diff.file_path.to_string_lossy().to_string();
+        let inverse = ReviewEdit::FileDiff {
+            file_diff_id: file_diff_id.clone(),
+            is_reviewed: match &commit {
+                Some(commit) => file_diff.reviewed_commits.contains(commit),
+                None => file_diff.is_reviewed,
+            },
+            commit: commit.clone(),
+        };
+
+        let event = match &commit {
+            Some(commit) => {
+                review.file_diffs.set_is_reviewed_in_commit(&file_diff_id, commit, is_reviewed);
+                ReviewEvent::FileReviewedInCommit {
+                    file,
+                    commit: commit.clone(),
+                    is_reviewed,
+                }
+            }
+            None => {
+                // Without the blob the file is never reset when it changes.
+                let reviewed_blob = if is_reviewed {
+                    git_utils::blob_hash(&repository_path, review.diff_range().revisions().1, &file)
+                        .inspect_err(|e| log::warn!("Could not hash {}: {}", file, e))
+                        .ok()
+                        .flatten()
+                } else {
+                    None
+                };
+                review.file_diffs.set_is_reviewed(&file_diff_id, is_reviewed, reviewed_blob);
+                ReviewEvent::FileReviewed { file, is_reviewed }
+            }
+        };
         if let Err(e) = self
             .storage
             .save_review_file_diffs(&repository.name, review.name(), review.diff_range(), &review.file_diffs.stores())
         {
-            self.ui_updater.report_error(ui::SlintResult::StoreFailed, &e.to_string());
-            return;
+            self.report_review_store_error(repository_id, review_id, e);
+            return None;
         }
+        // Undo may change a commit that isn't shown, then the shown files are refreshed instead.
+        if review.current_commit().map(|current_commit| &current_commit.hash) == commit.as_ref() {
+            self.ui_updater
+                .set_file_diff_is_reviewed(repository_id.as_usize(), review_id.as_usize(), file_diff_id.as_usize(), is_reviewed);
+        } else {
+            self.show_review_file_diffs(&repository_id, &review_id);
+        }
+        self.log_review_events(&repository_id, &review_id, vec![event]);
+        Some(inverse)
+    }
+    /// Shows the file diffs of the review or of its current commit, whose progress covers all
+    /// commits.
+    fn show_review_file_diffs(&self, repository_id: &RepositoryId, review_id: &ReviewId) {
+        let (repository, review) = self.loaded_review(repository_id, review_id);
+        let ui_file_diffs = file_diffs_with_status(repository.path(), review);
+        self.ui_updater.set_file_diffs(repository_id.as_usize(), review_id.as_usize(), ui_file_diffs);
+        if review.current_commit().is_some() {
+            let (completed_count, total_count) = review.commit_progress();
+            self.ui_updater
+                .set_review_progress(repository_id.as_usize(), review_id.as_usize(), completed_count, total_count);
+        }
+    }
+    fn select_commit(&mut self, repository_id: RepositoryId, review_id: ReviewId, commit_index: Option<usize>) {
+        let repository = self
+            .repositories
+            .get_mut(&repository_id)
+            .unwrap_or_else(|| panic!("[BUG] Could not find {}", repository_id));
+        let review = repository
+            .reviews
+            .get_mut(&review_id)
+            .unwrap_or_else(|| panic!("[BUG] Could not find {} ({})", review_id, repository_id));
+        review.select_commit(commit_index);
+        let ui_commit_index = review.current_commit_index().map_or(0, |index| index as i32 + 1);
+        self.ui_updater.set_commits_of_review(
+            repository_id.as_usize(),
+            review_id.as_usize(),
+            model_utils::commits_to_ui(review.commits()),
+            ui_commit_index,
+        );
+        self.show_review_file_diffs(&repository_id, &review_id);
+    }
+    /// Queries the commits of the diff range for the commit-by-commit mode, which is left. The
+    /// working tree and index have no commits to walk.
+    fn update_review_commits(&mut self, repository_id: &RepositoryId, review_id: &ReviewId) {
+        let repository = self
+            .repositories
+            .get_mut(repository_id)
+            .unwrap_or_else(|| panic!("[BUG] Could not find {}", repository_id));
+        let repository_path = repository.path().clone();
+        let review = repository
+            .reviews
+            .get_mut(review_id)
+            .unwrap_or_else(|| panic!("[BUG] Could not find {} ({})", review_id, repository_id));
+        let diff_range = review.diff_range();
+        let commits = if diff_range.start.is_empty() || diff_range.mode != DiffMode::Commits {
+            Vec::new()
+        } else {
+            git_utils::query_range_commits(&repository_path, &diff_range.start, &diff_range.end)
+                .inspect_err(|e| log::warn!("Could not query the commits of {}: {}", review.name().as_str(), e))
+                .unwrap_or_default()
+        };
+        review.set_commits(
+            commits
+                .into_iter()
+                .map(|(commit, files)| ReviewCommit {
+                    hash: commit.hash,
+                    message: commit.message,
+                    files,
+                })
+                .collect(),
+        );
         self.ui_updater
-            .set_file_diff_is_reviewed(repository_id.as_usize(), review_id.as_usize(), file_diff_id.as_usize(), is_reviewed);
+            .set_commits_of_review(repository_id.as_usize(), review_id.as_usize(), model_utils::commits_to_ui(review.commits()), 0);
     }
-    fn find_file_difference(&mut self, repository_id: RepositoryId, review_id: ReviewId, diff_range: DiffRangeStore) {
+    fn find_file_difference(
+        &mut self,
+        repository_id: RepositoryId,
+        review_id: ReviewId,
+        diff_range: DiffRangeStore,
+        interdiff_iteration: Option<usize>,
+    ) -> Option<ReviewEdit> {
         let repository = self
             .repositories
             .get_mut(&repository_id)
             .unwrap_or_else(|| panic!("[BUG] Could not find {}", repository_id));
 
-        let Ok(mut file_diff_map) = git_utils::diff_git_repo(repository.path(), &diff_range.start, &diff_range.end) else {
+        let repository_path = repository.path().clone();
+        let (start_revision, end_revision) = diff_range.revisions();
+        let Ok(mut file_diff_map) = git_utils::diff_git_repo(&repository_path, start_revision, end_revision) else {
             self.ui_updater.report_error(ui::SlintResult::FindFileDifferenceFailed, "");
-            return;
+            return None;
         };
 
         let new_files = file_diff_map.keys().cloned().collect::<HashSet<_>>();
 
+        // None of the reviewed files would be left, so the current state is kept as snapshot.
+        let is_disjoint = repository
+            .reviews
+            .get(&review_id)
+            .is_some_and(|review| !review.file_diffs.file_id_map.is_empty() && review.file_diffs.file_id_map.keys().all(|file| !new_files.contains(file)));
+        if is_disjoint && !self.save_snapshot(&repository_id, &review_id, review_snapshot::auto_snapshot_name(BEFORE_DIFF_RANGE_CHANGE)) {
+            return None;
+        }
+
+        let repository = self
+            .repositories
+            .get_mut(&repository_id)
+            .unwrap_or_else(|| panic!("[BUG] Could not find {}", repository_id));
         let review = repository
             .reviews
             .get_mut(&review_id)
             .unwrap_or_else(|| panic!("[BUG] Could not find {} ({})", review_id, repository_id));
 
+        let inverse = ReviewEdit::DiffRange {
+            diff_range: review.diff_range().clone(),
+            file_diffs: review.file_diffs.clone(),
+            note_anchors: review_history::note_anchors(&review.notes),
+        };
+
         let (deleted_file_diff_ids, added_files) = review.file_diffs.update_file_diffs(new_files);
+        let old_diff_range = review.diff_range().clone();
+        let diff_range = old_diff_range.next(diff_range.start, diff_range.end, diff_range.mode, interdiff_iteration);
+        let mut events = diff_range_events(&old_diff_range, &diff_range);
         review.set_diff_range(diff_range);
+        events.extend(carry_over_rewritten_history(&repository_path, review, &old_diff_range));
+
+        let reviewed_files = review.file_diffs.reviewed_files_with_blob();
+        let reviewed_files = reviewed_files.iter().map(String::as_str).collect::<Vec<_>>();
+        match git_utils::blob_hashes(&repository_path, review.diff_range().revisions().1, &reviewed_files) {
+            Ok(blob_hashes) => {
+                let changed_file_diff_ids = review.file_diffs.reset_changed_files(&blob_hashes);
+                events.extend(changed_file_diff_ids.iter().filter_map(|id| {
+                    let file = review.file_diffs.get(id)?.file_path.to_string_lossy().to_string();
+                    Some(ReviewEvent::FileChangedSinceReview { file })
+                }));
+            }
+            Err(e) => log::warn!("Could not check the reviewed files for changes: {}", e),
+        }
 
-        let ui_file_diffs = review
-            .file_diffs
-            .iter()
-            .map(|(id, store)| {
-                let file = store.file_path.to_string_lossy().to_string();
-                let diff_status = file_diff_map.remove(&file).expect("Could not found Diff-Status of cached file!");
-                (id.as_i32(), store.clone(), diff_status)
-            })
-            .collect::<Vec<_>>();
+        let relocated_note_ids = relocate_note_anchors(&repository_path, review, &old_diff_range);
+
+        let ui_file_diffs = if review.diff_range().interdiff_base().is_some() {
+            file_diffs_with_status(&repository_path, review)
+        } else {
+            review
+                .file_diffs
+                .iter()
+                .map(|(id, store)| {
+                    let file = store.file_path.to_string_lossy().to_string();
+                    let diff_status = file_diff_map.remove(&file).expect("Could not found Diff-Status of cached file!");
+                    (id.as_i32(), store.clone(), diff_status)
+                })
+                .collect::<Vec<_>>()
+        };
 
         self.ui_updater.migrate_file_diff_notes_to_text_context(
             repository_id.as_usize(),
@@ -625,15 +1470,129 @@ impl WorkerImpl {
         );
 
         self.ui_updater.set_file_diffs(repository_id.as_usize(), review_id.as_usize(), ui_file_diffs);
+        self.ui_updater.set_diff_range(
+            repository_id.as_usize(),
+            review_id.as_usize(),
+            SharedString::from(&review.diff_range().start),
+            SharedString::from(&review.diff_range().end),
+            model_utils::diff_mode_to_ui(review.diff_range().mode),
+        );
+        self.ui_updater.set_iterations(
+            repository_id.as_usize(),
+            review_id.as_usize(),
+            model_utils::iterations_to_ui(review.diff_range()),
+            review.diff_range().interdiff_iteration.unwrap_or_default() as i32,
+        );
 
-        if let Err(e) = self
+        let mut store_result = Ok(());
+        if !relocated_note_ids.is_empty() {
+            let ui_line_ranges = relocated_note_ids
+                .iter()
+                .filter_map(|id| {
+                    let note = review.notes.get(id)?;
+                    Some((id.as_usize(), model_utils::line_range_to_ui(note.line_range.as_ref()), note.is_outdated))
+                })
+                .collect::<Vec<_>>();
+            self.ui_updater
+                .set_note_line_ranges(repository_id.as_usize(), review_id.as_usize(), ui_line_ranges);
+
+            store_result = self.storage.save_review_notes(&repository.name, review.name(), &review.notes.stores());
+        }
+
+        let store_result = store_result.and_then(|()| {
+            self.storage
+                .save_review_file_diffs(&repository.name, review.name(), review.diff_range(), &review.file_diffs.stores())
+        });
+        if let Err(e) = store_result {
+            self.report_review_store_error(repository_id, review_id, e);
+            return None;
+        }
+        self.log_review_events(&repository_id, &review_id, events);
+        self.update_review_commits(&repository_id, &review_id);
+        Some(inverse)
+    }
+    fn restore_diff_range(
+        &mut self,
+        repository_id: RepositoryId,
+        review_id: ReviewId,
+        diff_range: DiffRangeStore,
+        file_diffs: FileDiffs,
+        note_anchors: Vec<NoteAnchor>,
+    ) -> Option<ReviewEdit> {
+        let repository = self
+            .repositories
+            .get_mut(&repository_id)
+            .unwrap_or_else(|| panic!("[BUG] Could not find {}", repository_id));
+
+        let repository_path = repository.path().clone();
+        let review = repository
+            .reviews
+            .get_mut(&review_id)
+            .unwrap_or_else(|| panic!("[BUG] Could not find {} ({})", review_id, repository_id));
+
+        let inverse = ReviewEdit::DiffRange {
+            diff_range: review.diff_range().clone(),
+            file_diffs: review.file_diffs.clone(),
+            note_anchors: review_history::note_anchors(&review.notes),
+        };
+
+        let deleted_file_diff_ids = review
+            .file_diffs
+            .file_id_map
+            .iter()
+            .filter(|(file, id)| file_diffs.file_id_map.get(*file) != Some(*id))
+            .map(|(_, id)| id.as_usize())
+            .collect::<Vec<_>>();
+        let added_files = file_diffs
+            .file_id_map
+            .iter()
+            .filter(|(file, id)| review.file_diffs.file_id_map.get(*file) != Some(*id))
+            .map(|(file, _)| SharedString::from(file.as_str()))
+            .collect::<Vec<_>>();
+
+        let events = diff_range_events(review.diff_range(), &diff_range);
+        review.file_diffs.restore(file_diffs);
+        review.set_diff_range(diff_range);
+        let ui_line_ranges = move_note_anchors(&mut review.notes, &note_anchors);
+        let ui_file_diffs = file_diffs_with_status(&repository_path, review);
+
+        self.ui_updater.set_diff_range(
+            repository_id.as_usize(),
+            review_id.as_usize(),
+            SharedString::from(&review.diff_range().start),
+            SharedString::from(&review.diff_range().end),
+            model_utils::diff_mode_to_ui(review.diff_range().mode),
+        );
+        self.ui_updater.set_iterations(
+            repository_id.as_usize(),
+            review_id.as_usize(),
+            model_utils::iterations_to_ui(review.diff_range()),
+            review.diff_range().interdiff_iteration.unwrap_or_default() as i32,
+        );
+        self.ui_updater
+            .migrate_file_diff_notes_to_text_context(repository_id.as_usize(), review_id.as_usize(), deleted_file_diff_ids);
+        self.ui_updater
+            .migrate_file_diff_notes_to_file_context(repository_id.as_usize(), review_id.as_usize(), added_files);
+        self.ui_updater.set_file_diffs(repository_id.as_usize(), review_id.as_usize(), ui_file_diffs);
+        self.ui_updater
+            .set_note_line_ranges(repository_id.as_usize(), review_id.as_usize(), ui_line_ranges);
+
+        let store_result = self
             .storage
-            .save_review_file_diffs(&repository.name, review.name(), review.diff_range(), &review.file_diffs.stores())
-        {
-            self.ui_updater.report_error(ui::SlintResult::StoreFailed, &e.to_string());
+            .save_review_notes(&repository.name, review.name(), &review.notes.stores())
+            .and_then(|()| {
+                self.storage
+                    .save_review_file_diffs(&repository.name, review.name(), review.diff_range(), &review.file_diffs.stores())
+            });
+        if let Err(e) = store_result {
+            self.report_review_store_error(repository_id, review_id, e);
+            return None;
         }
+        self.log_review_events(&repository_id, &review_id, events);
+        self.update_review_commits(&repository_id, &review_id);
+        Some(inverse)
     }
-    fn delete_note(&mut self, repository_id: RepositoryId, review_id: ReviewId, note_id: NoteId) {
+    fn delete_note(&mut self, repository_id: RepositoryId, review_id: ReviewId, note_id: NoteId) -> Option<ReviewEdit> {
         let repository = self
             .repositories
             .get_mut(&repository_id)
@@ -650,8 +1609,8 @@ impl WorkerImpl {
             .unwrap_or_else(|| panic!("[BUG] Could not find {} ({}, {})", note_id, review_id, repository_id));
 
         if let Err(e) = self.storage.save_review_notes(&repository.name, review.name(), &review.notes.stores()) {
-            self.ui_updater.report_error(ui::SlintResult::StoreFailed, &e.to_string());
-            return;
+            self.report_review_store_error(repository_id, review_id, e);
+            return None;
         }
 
         if let Some(file_diff_id) = review.file_diffs.file_id_map.get(&store.context) {
@@ -660,8 +1619,17 @@ impl WorkerImpl {
         }
 
         self.ui_updater.delete_note(repository_id.as_usize(), review_id.as_usize(), note_id.as_usize());
+        let event = ReviewEvent::NoteDeleted {
+            context: store.context.clone(),
+            text: store.text.clone(),
+        };
+        self.log_review_events(&repository_id, &review_id, vec![event]);
+        Some(ReviewEdit::AddNote {
+            note_id: Some(note_id),
+            note: store,
+        })
     }
-    fn add_note(&mut self, repository_id: RepositoryId, review_id: ReviewId, text: String, context: String) {
+    fn add_note(&mut self, repository_id: RepositoryId, review_id: ReviewId, opt_note_id: Option<NoteId>, note: NoteStore) -> Option<ReviewEdit> {
         let repository = self
             .repositories
             .get_mut(&repository_id)
@@ -672,31 +1640,48 @@ impl WorkerImpl {
             .get_mut(&review_id)
             .unwrap_or_else(|| panic!("[BUG] Could not find {} ({})", review_id, repository_id));
 
-        let ui_text = SharedString::from(text.as_str());
-        let ui_context = SharedString::from(context.as_str());
-
-        let opt_file_diff_id = review.file_diffs.file_id_map.get(&context).map(|id| id.as_usize());
-        let note_id = review.notes.add_note(text, context);
-
-        if let Err(e) = self.storage.save_review_notes(&repository.name, review.name(), &review.notes.stores()) {
-            self.ui_updater.report_error(ui::SlintResult::StoreFailed, &e.to_string());
-            return;
-        }
-        let context_type = if review.file_diffs.file_id_map.contains_key(ui_context.as_str()) {
+        let opt_file_diff_id = review.file_diffs.file_id_map.get(&note.context).map(|id| id.as_usize());
+        let context_type = if opt_file_diff_id.is_some() {
             SlintContextType::File
         } else {
             SlintContextType::Text
         };
+        let ui_text = SharedString::from(note.text.as_str());
+        let ui_context = SharedString::from(note.context.as_str());
+        let ui_line_range = model_utils::line_range_to_ui(note.line_range.as_ref());
+        let (is_done, is_outdated) = (note.is_done, note.is_outdated);
+        let event = ReviewEvent::NoteAdded {
+            context: note.context.clone(),
+            text: note.text.clone(),
+        };
+
+        // Notes are restored under their previous id, so that older edits still refer to them.
+        let note_id = match opt_note_id {
+            Some(note_id) => {
+                review.notes.restore_note(note_id.clone(), note);
+                note_id
+            }
+            None => review.notes.add_note(note.text, note.context, note.line_range),
+        };
+
+        if let Err(e) = self.storage.save_review_notes(&repository.name, review.name(), &review.notes.stores()) {
+            self.report_review_store_error(repository_id, review_id, e);
+            return None;
+        }
 
         let ui_note = SlintNote {
             id: note_id.as_i32(),
             text: ui_text,
             context: ui_context,
             context_type,
-            is_fixed: false,
+            is_fixed: is_done,
+            line_range: ui_line_range,
+            is_outdated,
         };
 
         self.ui_updater
             .add_note(repository_id.as_usize(), review_id.as_usize(), ui_note, opt_file_diff_id);
+        self.log_review_events(&repository_id, &review_id, vec![event]);
+        Some(ReviewEdit::DeleteNote(note_id))
     }
 }